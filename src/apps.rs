@@ -0,0 +1,587 @@
+//! Portable app installs
+//!
+//! Scoop's layout, without needing Scoop: every version of an app is kept in
+//! `~/.get/apps/<name>/<version>`, `~/.get/apps/<name>/current` links to the
+//! active one, and each command it provides gets a shim in `~/.get/bin`.
+//! Shims go through `current`, so switching versions never rewrites them.
+//! `GET_HOME` moves both directories out of the home directory.
+//!
+//! On Unix a shim is a symlink, or a small `sh` script when it passes extra
+//! arguments. On Windows it is a `.cmd` file, and `current` is a junction,
+//! which needs no special privileges.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
+
+use crate::archive;
+use crate::config::Config;
+use crate::error::GetError;
+use crate::package_manager::ArchiveFormat;
+use crate::utils::validate_path_component;
+
+/// A command to expose from an app
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Shim {
+    /// The program, relative to the app's version directory
+    pub path: String,
+    /// The command name; defaults to the program's file name
+    #[serde(default)]
+    pub alias: Option<String>,
+    /// Arguments passed before the user's
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+impl Shim {
+    pub fn new(path: &str) -> Self {
+        Shim { path: path.to_string(), alias: None, args: Vec::new() }
+    }
+
+    /// The command name the shim is installed as
+    pub fn name(&self) -> String {
+        match &self.alias {
+            Some(alias) => alias.clone(),
+            None => {
+                let path = Path::new(&self.path);
+                let name = path.file_name().and_then(|n| n.to_str()).unwrap_or(&self.path);
+                // Windows shims add their own extension
+                match path.extension().and_then(|e| e.to_str()) {
+                    Some(e) if ["exe", "cmd", "bat", "ps1"].iter().any(|x| e.eq_ignore_ascii_case(x)) => {
+                        path.file_stem().and_then(|n| n.to_str()).unwrap_or(name).to_string()
+                    }
+                    _ => name.to_string(),
+                }
+            }
+        }
+    }
+}
+
+/// The commands of an app, as written in a manifest's `bin`
+///
+/// This is Scoop's format: a single path, or a list whose items are a path or
+/// `[path, alias, args...]`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum BinSpec {
+    One(String),
+    Many(Vec<BinEntry>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum BinEntry {
+    Path(String),
+    Aliased(Vec<String>),
+}
+
+impl BinSpec {
+    pub fn shims(&self) -> Vec<Shim> {
+        match self {
+            BinSpec::One(path) => vec![Shim::new(path)],
+            BinSpec::Many(entries) => entries
+                .iter()
+                .filter_map(|entry| match entry {
+                    BinEntry::Path(path) => Some(Shim::new(path)),
+                    BinEntry::Aliased(parts) => {
+                        let (path, rest) = parts.split_first()?;
+                        Some(Shim {
+                            path: path.clone(),
+                            alias: rest.first().filter(|a| !a.is_empty()).cloned(),
+                            args: rest.iter().skip(1).cloned().collect(),
+                        })
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+/// The apps and bin directories
+pub struct Apps {
+    root: PathBuf,
+    bin_dir: PathBuf,
+}
+
+impl Apps {
+    pub fn new(root: PathBuf, bin_dir: PathBuf) -> Self {
+        Apps { root, bin_dir }
+    }
+
+    pub fn from_config(config: &Config) -> Self {
+        Apps::new(config.get_apps_dir(), config.get_bin_dir())
+    }
+
+    pub fn bin_dir(&self) -> &Path {
+        &self.bin_dir
+    }
+
+    pub fn app_dir(&self, name: &str) -> PathBuf {
+        self.root.join(name)
+    }
+
+    pub fn version_dir(&self, name: &str, version: &str) -> PathBuf {
+        self.app_dir(name).join(version)
+    }
+
+    pub fn current_dir(&self, name: &str) -> PathBuf {
+        self.app_dir(name).join("current")
+    }
+
+    /// The version `current` links to
+    pub fn current_version(&self, name: &str) -> Option<String> {
+        let target = fs::read_link(self.current_dir(name)).ok()?;
+        target.file_name()?.to_str().map(str::to_string)
+    }
+
+    /// Every version kept for `name`
+    pub fn versions(&self, name: &str) -> Vec<String> {
+        let Ok(entries) = fs::read_dir(self.app_dir(name)) else {
+            return Vec::new();
+        };
+        entries
+            .flatten()
+            .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+            .filter_map(|e| e.file_name().to_str().map(str::to_string))
+            .filter(|v| v != "current")
+            .collect()
+    }
+
+    /// Every file and symlink of an unpacked version, for the install record
+    pub fn files(&self, name: &str, version: &str) -> Vec<PathBuf> {
+        WalkDir::new(self.version_dir(name, version))
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| !e.file_type().is_dir())
+            .map(|e| e.into_path())
+            .collect()
+    }
+
+    /// An empty directory for a version, replacing a previous attempt
+    pub fn prepare_version(&self, name: &str, version: &str) -> Result<PathBuf, GetError> {
+        validate_path_component("name", name)?;
+        validate_path_component("version", version)?;
+        if version == "current" {
+            return Err(GetError::ValidationError(format!("Invalid package version '{}'", version)));
+        }
+        let dir = self.version_dir(name, version);
+        if self.current_version(name).as_deref() == Some(version) {
+            remove_link(&self.current_dir(name))?;
+        }
+        if dir.exists() {
+            fs::remove_dir_all(&dir)?;
+        }
+        fs::create_dir_all(&dir)?;
+        Ok(dir)
+    }
+
+    /// Unpack a downloaded archive, or copy a bare program, into the version
+    /// directory; a program is stored under `file_name`
+    pub fn unpack(
+        &self,
+        name: &str,
+        version: &str,
+        file: &Path,
+        format: Option<ArchiveFormat>,
+        strip_components: usize,
+        file_name: &str,
+    ) -> Result<PathBuf, GetError> {
+        let dir = self.prepare_version(name, version)?;
+        unpack_into(&dir, file, format, strip_components, file_name, None)?;
+        Ok(dir)
+    }
+
+    /// Point `current` at `version`
+    pub fn set_current(&self, name: &str, version: &str) -> Result<PathBuf, GetError> {
+        let current = self.current_dir(name);
+        let target = self.version_dir(name, version);
+        if !target.is_dir() {
+            return Err(GetError::PackageNotFound(format!("{} {} is not installed", name, version)));
+        }
+        remove_link(&current)?;
+        link_dir(&target, &current)?;
+        Ok(current)
+    }
+
+    /// Create shims in the bin directory for `shims` of `name`, returning
+    /// their paths
+    pub fn create_shims(&self, name: &str, shims: &[Shim]) -> Result<Vec<PathBuf>, GetError> {
+        fs::create_dir_all(&self.bin_dir)?;
+        let current = self.current_dir(name);
+        let mut created = Vec::new();
+        for shim in shims {
+            let target = current.join(&shim.path);
+            if !target.exists() {
+                return Err(GetError::ValidationError(format!(
+                    "'{}' of {} does not exist",
+                    shim.path, name
+                )));
+            }
+            make_executable(&target)?;
+            created.push(write_shim(&self.bin_dir, &shim.name(), &target, &shim.args)?);
+        }
+        Ok(created)
+    }
+
+    /// Remove every version of `name` and the shims pointing into it
+    pub fn remove(&self, name: &str) -> Result<Vec<PathBuf>, GetError> {
+        let removed = self.remove_shims(name)?;
+        let current = self.current_dir(name);
+        remove_link(&current)?;
+        let dir = self.app_dir(name);
+        if dir.exists() {
+            fs::remove_dir_all(&dir)?;
+        }
+        Ok(removed)
+    }
+
+    /// Remove the shims that lead into `name`'s directory
+    pub fn remove_shims(&self, name: &str) -> Result<Vec<PathBuf>, GetError> {
+        let app_dir = self.app_dir(name);
+        let Ok(entries) = fs::read_dir(&self.bin_dir) else {
+            return Ok(Vec::new());
+        };
+        let mut removed = Vec::new();
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if shim_target(&path).is_some_and(|target| target.starts_with(&app_dir)) {
+                fs::remove_file(&path)?;
+                removed.push(path);
+            }
+        }
+        Ok(removed)
+    }
+}
+
+/// Unpack an archive, or copy a bare program, into a version directory made
+/// by [`Apps::prepare_version`]. With `extract_dir`, only that directory of
+/// the archive is kept, as its contents.
+pub fn unpack_into(
+    dir: &Path,
+    file: &Path,
+    format: Option<ArchiveFormat>,
+    strip_components: usize,
+    file_name: &str,
+    extract_dir: Option<&str>,
+) -> Result<(), GetError> {
+    let Some(format) = format else {
+        let target = dir.join(file_name);
+        fs::copy(file, &target)?;
+        make_executable(&target)?;
+        return Ok(());
+    };
+    let Some(extract_dir) = extract_dir else {
+        archive::extract(file, format, dir, strip_components)?;
+        return Ok(());
+    };
+
+    let subdir = relative_path(extract_dir)
+        .ok_or_else(|| GetError::ValidationError(format!("extract_dir '{}' is not inside the archive", extract_dir)))?;
+    let staging = tempfile::Builder::new().prefix(".extract").tempdir_in(dir)?;
+    archive::extract(file, format, staging.path(), strip_components)?;
+    let source = staging.path().join(subdir);
+    if !source.is_dir() {
+        return Err(GetError::ValidationError(format!(
+            "'{}' has no directory '{}'",
+            file.display(),
+            extract_dir
+        )));
+    }
+    for entry in fs::read_dir(&source)? {
+        let entry = entry?;
+        fs::rename(entry.path(), dir.join(entry.file_name()))?;
+    }
+    Ok(())
+}
+
+/// The programs directly inside `path` of an unpacked app, for manifests
+/// that put a directory on `PATH` instead of listing their commands
+pub fn find_path_commands(dir: &Path, path: &str) -> Result<Vec<Shim>, GetError> {
+    let relative = relative_path(path)
+        .ok_or_else(|| GetError::ValidationError(format!("'{}' is not inside the app", path)))?;
+    let mut found: Vec<Shim> = WalkDir::new(dir.join(&relative))
+        .min_depth(1)
+        .max_depth(1)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file() && is_program(e.path()))
+        .map(|e| Shim::new(&relative.join(e.file_name()).to_string_lossy()))
+        .collect();
+    found.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(found)
+}
+
+/// A manifest path that stays inside the app, with either separator;
+/// `.` is the app itself
+fn relative_path(path: &str) -> Option<PathBuf> {
+    let mut relative = PathBuf::new();
+    for part in path.split(['/', '\\']).filter(|p| !p.is_empty() && *p != ".") {
+        validate_path_component("path", part).ok()?;
+        relative.push(part);
+    }
+    Some(relative)
+}
+
+/// The programs in an unpacked app, relative to `dir`, for packages that
+/// don't list their commands. Nested directories are searched a few levels
+/// deep since archives often wrap everything in one.
+pub fn find_executables(dir: &Path) -> Vec<Shim> {
+    let mut found: Vec<Shim> = WalkDir::new(dir)
+        .max_depth(3)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file() && is_program(e.path()))
+        .filter_map(|e| e.path().strip_prefix(dir).ok().map(|p| p.to_string_lossy().into_owned()))
+        .map(|path| Shim::new(&path))
+        .collect();
+    found.sort_by(|a, b| a.path.cmp(&b.path));
+    found
+}
+
+#[cfg(unix)]
+fn is_program(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    // Scripts and libraries shipped alongside are not commands
+    path.extension().is_none() && path.metadata().map(|m| m.permissions().mode() & 0o111 != 0).unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_program(path: &Path) -> bool {
+    path.extension().and_then(|e| e.to_str()).map_or(false, |e| e.eq_ignore_ascii_case("exe"))
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path) -> Result<(), GetError> {
+    use std::os::unix::fs::PermissionsExt;
+    let mode = fs::metadata(path)?.permissions().mode();
+    fs::set_permissions(path, fs::Permissions::from_mode(mode | 0o755))?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn make_executable(_path: &Path) -> Result<(), GetError> {
+    Ok(())
+}
+
+/// Remove a symlink or junction, leaving what it points to
+fn remove_link(link: &Path) -> Result<(), GetError> {
+    match fs::symlink_metadata(link) {
+        Ok(meta) if meta.is_dir() || is_dir_link(&meta) => fs::remove_dir(link)?,
+        Ok(_) => fs::remove_file(link)?,
+        Err(_) => {}
+    }
+    Ok(())
+}
+
+/// Junctions and directory symlinks are removed as directories on Windows
+#[cfg(windows)]
+fn is_dir_link(meta: &fs::Metadata) -> bool {
+    use std::os::windows::fs::FileTypeExt;
+    meta.file_type().is_symlink_dir()
+}
+
+#[cfg(not(windows))]
+fn is_dir_link(_meta: &fs::Metadata) -> bool {
+    false
+}
+
+#[cfg(unix)]
+fn link_dir(target: &Path, link: &Path) -> Result<(), GetError> {
+    std::os::unix::fs::symlink(target, link)?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn link_dir(target: &Path, link: &Path) -> Result<(), GetError> {
+    let status = std::process::Command::new("cmd")
+        .arg("/C")
+        .arg("mklink")
+        .arg("/J")
+        .arg(link)
+        .arg(target)
+        .stdout(std::process::Stdio::null())
+        .status()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(GetError::CommandError(format!("Failed to link '{}' to '{}'", link.display(), target.display())))
+    }
+}
+
+#[cfg(unix)]
+fn write_shim(bin_dir: &Path, name: &str, target: &Path, args: &[String]) -> Result<PathBuf, GetError> {
+    let shim = bin_dir.join(name);
+    remove_link(&shim)?;
+    if args.is_empty() {
+        std::os::unix::fs::symlink(target, &shim)?;
+    } else {
+        let quoted: Vec<String> = args.iter().map(|a| format!("'{}'", a.replace('\'', r"'\''"))).collect();
+        fs::write(&shim, format!("#!/bin/sh\nexec '{}' {} \"$@\"\n", target.display(), quoted.join(" ")))?;
+        make_executable(&shim)?;
+    }
+    Ok(shim)
+}
+
+#[cfg(not(unix))]
+fn write_shim(bin_dir: &Path, name: &str, target: &Path, args: &[String]) -> Result<PathBuf, GetError> {
+    let shim = bin_dir.join(format!("{}.cmd", name));
+    let args = args.join(" ");
+    let is_powershell = target.extension().map_or(false, |e| e.eq_ignore_ascii_case("ps1"));
+    let command = if is_powershell {
+        format!("@powershell -NoProfile -ExecutionPolicy Bypass -File \"{}\" {} %*\r\n", target.display(), args)
+    } else {
+        format!("@\"{}\" {} %*\r\n", target.display(), args)
+    };
+    fs::write(&shim, command)?;
+    Ok(shim)
+}
+
+/// Where a shim leads, if it is one of ours
+fn shim_target(shim: &Path) -> Option<PathBuf> {
+    if let Ok(target) = fs::read_link(shim) {
+        return Some(target);
+    }
+    // Script shims name their target in quotes
+    let text = fs::read_to_string(shim).ok()?;
+    let line = text.lines().find(|l| l.starts_with("exec ") || l.starts_with('@'))?;
+    let quote = if line.starts_with("exec ") { '\'' } else { '"' };
+    let start = line.find(quote)? + 1;
+    let end = line[start..].find(quote)? + start;
+    Some(PathBuf::from(&line[start..end]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn apps(home: &Path) -> Apps {
+        Apps::from_config(&Config { home: Some(home.to_string_lossy().into_owned()), ..Default::default() })
+    }
+
+    fn write_zip(path: &Path, entries: &[(&str, &str)]) {
+        let mut zip = zip::ZipWriter::new(fs::File::create(path).unwrap());
+        let options = zip::write::SimpleFileOptions::default().unix_permissions(0o755);
+        for (name, contents) in entries {
+            zip.start_file(*name, options).unwrap();
+            zip.write_all(contents.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    /// Unpack a zip holding `bin/tool` that prints `version`, and activate it
+    fn install(apps: &Apps, dir: &Path, version: &str) -> Vec<PathBuf> {
+        let archive_path = dir.join(format!("tool-{}.zip", version));
+        write_zip(&archive_path, &[("bin/tool", &format!("#!/bin/sh\necho {}\n", version))]);
+        apps.unpack("tool", version, &archive_path, Some(ArchiveFormat::Zip), 0, "tool").unwrap();
+        apps.set_current("tool", version).unwrap();
+        apps.remove_shims("tool").unwrap();
+        apps.create_shims("tool", &[Shim::new("bin/tool")]).unwrap()
+    }
+
+    #[test]
+    fn lives_under_the_configured_home() {
+        let home = tempfile::tempdir().unwrap();
+        let apps = apps(home.path());
+        assert_eq!(apps.app_dir("tool"), home.path().join(".get").join("apps").join("tool"));
+        assert_eq!(apps.bin_dir(), home.path().join(".get").join("bin"));
+    }
+
+    #[test]
+    fn switches_current_between_versions() {
+        let home = tempfile::tempdir().unwrap();
+        let apps = apps(home.path());
+
+        let shims = install(&apps, home.path(), "1.0");
+        assert_eq!(shims, [apps.bin_dir().join("tool")]);
+        assert_eq!(apps.current_version("tool").as_deref(), Some("1.0"));
+        assert_eq!(fs::read_to_string(&shims[0]).unwrap(), "#!/bin/sh\necho 1.0\n");
+
+        // The shim goes through `current`, so it follows the switch
+        install(&apps, home.path(), "2.0");
+        assert_eq!(apps.current_version("tool").as_deref(), Some("2.0"));
+        assert_eq!(fs::read_to_string(&shims[0]).unwrap(), "#!/bin/sh\necho 2.0\n");
+        let mut versions = apps.versions("tool");
+        versions.sort();
+        assert_eq!(versions, ["1.0", "2.0"]);
+
+        apps.set_current("tool", "1.0").unwrap();
+        assert_eq!(fs::read_to_string(&shims[0]).unwrap(), "#!/bin/sh\necho 1.0\n");
+        assert!(apps.set_current("tool", "3.0").is_err());
+        assert_eq!(apps.current_version("tool").as_deref(), Some("1.0"));
+    }
+
+    #[test]
+    fn removes_shims_and_versions() {
+        let home = tempfile::tempdir().unwrap();
+        let apps = apps(home.path());
+        install(&apps, home.path(), "1.0");
+        fs::write(apps.bin_dir().join("other"), "not ours").unwrap();
+
+        let removed = apps.remove("tool").unwrap();
+        assert_eq!(removed, [apps.bin_dir().join("tool")]);
+        assert!(!apps.app_dir("tool").exists());
+        assert!(apps.bin_dir().join("other").exists());
+    }
+
+    #[test]
+    fn rejects_names_and_versions_outside_the_apps_directory() {
+        let home = tempfile::tempdir().unwrap();
+        let apps = apps(home.path());
+        for (name, version) in [("tool", ".."), ("tool", "../../escape"), ("..", "1.0"), ("a/b", "1.0"), ("tool", ""), ("tool", "current")] {
+            assert!(
+                matches!(apps.prepare_version(name, version), Err(GetError::ValidationError(_))),
+                "{} {}",
+                name,
+                version
+            );
+        }
+        assert!(!home.path().join(".get").exists());
+    }
+
+    #[test]
+    fn keeps_only_the_extract_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive_path = dir.path().join("app.zip");
+        write_zip(&archive_path, &[("app-1.0/bin/app", "app"), ("app-1.0/README", "read me"), ("other", "")]);
+        let version_dir = dir.path().join("1.0");
+        fs::create_dir(&version_dir).unwrap();
+
+        unpack_into(&version_dir, &archive_path, Some(ArchiveFormat::Zip), 0, "app.zip", Some("app-1.0")).unwrap();
+        let mut entries: Vec<String> =
+            fs::read_dir(&version_dir).unwrap().map(|e| e.unwrap().file_name().to_string_lossy().into_owned()).collect();
+        entries.sort();
+        assert_eq!(entries, ["README", "bin"]);
+
+        for extract_dir in ["missing", "..", "app-1.0/../.."] {
+            assert!(unpack_into(&version_dir, &archive_path, Some(ArchiveFormat::Zip), 0, "app.zip", Some(extract_dir)).is_err());
+        }
+    }
+
+    #[test]
+    fn finds_the_commands_of_a_path_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive_path = dir.path().join("app.zip");
+        write_zip(&archive_path, &[("bin/app", ""), ("bin/helper", ""), ("bin/nested/deep", ""), ("top", "")]);
+        unpack_into(dir.path(), &archive_path, Some(ArchiveFormat::Zip), 0, "app.zip", None).unwrap();
+
+        let paths = |path: &str| find_path_commands(dir.path(), path).unwrap().into_iter().map(|s| s.path).collect::<Vec<_>>();
+        assert_eq!(paths("bin"), ["bin/app", "bin/helper"]);
+        assert_eq!(paths("."), ["top"]);
+        assert!(find_path_commands(dir.path(), "../bin").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn removing_current_leaves_the_version() {
+        let home = tempfile::tempdir().unwrap();
+        let apps = apps(home.path());
+        install(&apps, home.path(), "1.0");
+
+        remove_link(&apps.current_dir("tool")).unwrap();
+        assert!(fs::symlink_metadata(apps.current_dir("tool")).is_err());
+        assert!(apps.version_dir("tool", "1.0").join("bin").join("tool").exists());
+    }
+}
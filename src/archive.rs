@@ -0,0 +1,571 @@
+//! Archive extraction
+//!
+//! Extracts every [`ArchiveFormat`] entry by entry, without unpacking to a
+//! temporary location first. Entry paths are checked before anything is
+//! written: absolute paths, `..` components and writes through symlinks
+//! created by the archive itself are rejected, and symlink targets must stay
+//! inside the destination without passing through another symlink. Unix
+//! permissions and symlinks are restored where the format records them.
+
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read};
+use std::path::{Component, Path, PathBuf};
+
+use crate::error::GetError;
+use crate::package_manager::{ArchiveFormat, PackageInfo};
+
+/// `S_IFMT` / `S_IFLNK` bits of a Unix mode
+const S_IFMT: u32 = 0o170000;
+const S_IFLNK: u32 = 0o120000;
+
+/// 7-Zip sets this attribute bit when the high 16 bits hold a Unix mode
+const FILE_ATTRIBUTE_UNIX_EXTENSION: u32 = 0x8000;
+
+/// What an extraction produced
+#[derive(Debug, Default)]
+pub struct ExtractSummary {
+    pub files: usize,
+    pub directories: usize,
+    pub symlinks: usize,
+    /// Entries dropped by `strip_components` or of unsupported types
+    pub skipped: usize,
+    /// Every file and symlink written, in archive order
+    pub paths: Vec<PathBuf>,
+    /// Directories that didn't exist before, parents first
+    pub created_dirs: Vec<PathBuf>,
+}
+
+impl ArchiveFormat {
+    /// Guess the format from a file name
+    pub fn detect(path: &Path) -> Option<ArchiveFormat> {
+        let name = path.file_name()?.to_str()?.to_lowercase();
+        if name.ends_with(".zip") || name.ends_with(".nupkg") {
+            Some(ArchiveFormat::Zip)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(ArchiveFormat::TarGz)
+        } else if name.ends_with(".tar.xz") || name.ends_with(".txz") {
+            Some(ArchiveFormat::TarXz)
+        } else if name.ends_with(".tar.bz2") || name.ends_with(".tbz2") || name.ends_with(".tbz") {
+            Some(ArchiveFormat::TarBz2)
+        } else if name.ends_with(".7z") {
+            Some(ArchiveFormat::SevenZip)
+        } else {
+            None
+        }
+    }
+}
+
+/// Expand the placeholders of an `extract_path`:
+///
+/// - `{name}`, `{version}`: the package being installed
+/// - `{home}`: the user's home directory (a leading `~` works too)
+/// - `{temp}`: the system temporary directory
+/// - `{env:VAR}`: the environment variable `VAR`
+pub fn expand_extract_path(template: &str, package: &PackageInfo) -> Result<PathBuf, GetError> {
+    let home = || {
+        dirs::home_dir()
+            .map(|h| h.to_string_lossy().into_owned())
+            .ok_or_else(|| GetError::ConfigError("Unable to determine home directory".to_string()))
+    };
+
+    let mut expanded = String::new();
+    let mut rest = template;
+    if let Some(stripped) = rest.strip_prefix('~') {
+        if stripped.is_empty() || stripped.starts_with(['/', '\\']) {
+            expanded.push_str(&home()?);
+            rest = stripped;
+        }
+    }
+
+    while let Some(start) = rest.find('{') {
+        expanded.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find('}')
+            .map(|end| start + end)
+            .ok_or_else(|| GetError::InvalidInput(format!("Unclosed '{{' in extract path '{}'", template)))?;
+        let value = match &rest[start + 1..end] {
+            "name" => package.name.clone(),
+            "version" => package.version.clone(),
+            "home" => home()?,
+            "temp" => std::env::temp_dir().to_string_lossy().into_owned(),
+            key => match key.strip_prefix("env:") {
+                Some(var) => std::env::var(var).map_err(|_| {
+                    GetError::InvalidInput(format!("Environment variable '{}' used in extract path is not set", var))
+                })?,
+                None => {
+                    return Err(GetError::InvalidInput(format!(
+                        "Unknown placeholder '{{{}}}' in extract path '{}'",
+                        key, template
+                    )));
+                }
+            },
+        };
+        expanded.push_str(&value);
+        rest = &rest[end + 1..];
+    }
+    expanded.push_str(rest);
+
+    if expanded.trim().is_empty() {
+        return Err(GetError::InvalidInput("Extract path is empty".to_string()));
+    }
+    Ok(PathBuf::from(expanded))
+}
+
+/// Extract `archive` into `dest`, dropping the first `strip_components` path
+/// components of every entry
+pub fn extract(archive: &Path, format: ArchiveFormat, dest: &Path, strip_components: usize) -> Result<ExtractSummary, GetError> {
+    let mut created_dirs = Vec::new();
+    create_dirs(dest, &mut created_dirs)?;
+    let dest = dest.canonicalize()?;
+    let created_dirs = created_dirs.iter().map(|dir| dir.canonicalize()).collect::<Result<_, _>>()?;
+    let mut extractor = Extractor {
+        dest,
+        strip_components,
+        summary: ExtractSummary { created_dirs, ..ExtractSummary::default() },
+    };
+
+    let file = BufReader::new(File::open(archive)?);
+    match format {
+        ArchiveFormat::Zip => extractor.zip(file)?,
+        ArchiveFormat::TarGz => extractor.tar(flate2::read::MultiGzDecoder::new(file))?,
+        ArchiveFormat::TarXz => extractor.tar(xz2::read::XzDecoder::new_multi_decoder(file))?,
+        ArchiveFormat::TarBz2 => extractor.tar(bzip2::read::MultiBzDecoder::new(file))?,
+        ArchiveFormat::SevenZip => extractor.seven_zip(archive)?,
+    }
+    Ok(extractor.summary)
+}
+
+struct Extractor {
+    dest: PathBuf,
+    strip_components: usize,
+    summary: ExtractSummary,
+}
+
+impl Extractor {
+    /// Map an entry name to its path under `dest`, or `None` if it is stripped away
+    fn target(&self, name: &Path) -> Result<Option<PathBuf>, GetError> {
+        let mut relative = PathBuf::new();
+        for component in name.components() {
+            match component {
+                Component::Normal(part) => relative.push(part),
+                Component::CurDir => {}
+                _ => {
+                    return Err(GetError::ValidationError(format!(
+                        "Archive entry '{}' escapes the extraction directory",
+                        name.display()
+                    )));
+                }
+            }
+        }
+
+        let relative: PathBuf = relative.components().skip(self.strip_components).collect();
+        if relative.as_os_str().is_empty() {
+            return Ok(None);
+        }
+
+        let target = self.dest.join(&relative);
+        self.check_ancestors(&target)?;
+        Ok(Some(target))
+    }
+
+    /// Refuse to write through a symlink, which could point anywhere
+    fn check_ancestors(&self, target: &Path) -> Result<(), GetError> {
+        let mut current = target.parent();
+        while let Some(dir) = current {
+            if dir == self.dest {
+                break;
+            }
+            if fs::symlink_metadata(dir).is_ok_and(|meta| meta.file_type().is_symlink()) {
+                return Err(GetError::ValidationError(format!(
+                    "Archive writes through symlink '{}'",
+                    dir.display()
+                )));
+            }
+            current = dir.parent();
+        }
+        Ok(())
+    }
+
+    fn create_dir(&mut self, target: &Path, mode: Option<u32>) -> Result<(), GetError> {
+        create_dirs(target, &mut self.summary.created_dirs)?;
+        // Keep directories writable so their contents can still be extracted
+        set_mode(target, mode.map(|mode| mode | 0o700))?;
+        self.summary.directories += 1;
+        Ok(())
+    }
+
+    fn create_file(&mut self, target: &Path, data: &mut dyn Read, mode: Option<u32>) -> Result<(), GetError> {
+        if let Some(parent) = target.parent() {
+            create_dirs(parent, &mut self.summary.created_dirs)?;
+        }
+        // Replace rather than follow whatever is already there
+        if fs::symlink_metadata(target).is_ok() {
+            fs::remove_file(target)?;
+        }
+        let mut out = File::create(target)?;
+        io::copy(data, &mut out)?;
+        drop(out);
+        set_mode(target, mode)?;
+        self.summary.files += 1;
+        self.summary.paths.push(target.to_path_buf());
+        Ok(())
+    }
+
+    fn create_symlink(&mut self, target: &Path, link: &Path) -> Result<(), GetError> {
+        // Resolve the link lexically from its own directory; it must stay inside `dest`.
+        // Lexical resolution is only sound if no step of the way is itself a
+        // symlink (`y -> ..` makes `y/..` the parent of `dest`), so those are refused.
+        let parent = target.parent().unwrap_or(&self.dest);
+        let mut resolved = parent.to_path_buf();
+        let mut components = link.components().peekable();
+        while let Some(component) = components.next() {
+            match component {
+                Component::Normal(part) => {
+                    resolved.push(part);
+                    if components.peek().is_some()
+                        && fs::symlink_metadata(&resolved).is_ok_and(|meta| meta.file_type().is_symlink())
+                    {
+                        return Err(GetError::ValidationError(format!(
+                            "Symlink '{}' -> '{}' passes through symlink '{}'",
+                            target.display(),
+                            link.display(),
+                            resolved.display()
+                        )));
+                    }
+                }
+                Component::CurDir => {}
+                Component::ParentDir if resolved != self.dest => {
+                    resolved.pop();
+                }
+                _ => {
+                    return Err(GetError::ValidationError(format!(
+                        "Symlink '{}' -> '{}' points outside the extraction directory",
+                        target.display(),
+                        link.display()
+                    )));
+                }
+            }
+        }
+        if !resolved.starts_with(&self.dest) {
+            return Err(GetError::ValidationError(format!(
+                "Symlink '{}' -> '{}' points outside the extraction directory",
+                target.display(),
+                link.display()
+            )));
+        }
+
+        create_dirs(parent, &mut self.summary.created_dirs)?;
+        if fs::symlink_metadata(target).is_ok() {
+            fs::remove_file(target)?;
+        }
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(link, target)?;
+        #[cfg(windows)]
+        {
+            if resolved.is_dir() {
+                std::os::windows::fs::symlink_dir(link, target)?;
+            } else {
+                std::os::windows::fs::symlink_file(link, target)?;
+            }
+        }
+        self.summary.symlinks += 1;
+        self.summary.paths.push(target.to_path_buf());
+        Ok(())
+    }
+
+    fn tar<R: Read>(&mut self, reader: R) -> Result<(), GetError> {
+        let mut archive = tar::Archive::new(reader);
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            let name = entry.path()?.into_owned();
+            let target = match self.target(&name)? {
+                Some(target) => target,
+                None => {
+                    self.summary.skipped += 1;
+                    continue;
+                }
+            };
+            let mode = entry.header().mode().ok();
+
+            match entry.header().entry_type() {
+                tar::EntryType::Directory => self.create_dir(&target, mode)?,
+                tar::EntryType::Regular | tar::EntryType::Continuous => self.create_file(&target, &mut entry, mode)?,
+                tar::EntryType::Symlink => {
+                    let link = entry
+                        .link_name()?
+                        .ok_or_else(|| GetError::ParseError(format!("Symlink '{}' has no target", name.display())))?
+                        .into_owned();
+                    self.create_symlink(&target, &link)?;
+                }
+                tar::EntryType::Link => {
+                    // Hard links name another entry of the archive
+                    let source = entry
+                        .link_name()?
+                        .ok_or_else(|| GetError::ParseError(format!("Hard link '{}' has no target", name.display())))?
+                        .into_owned();
+                    match self.target(&source)? {
+                        Some(source) => {
+                            if let Some(parent) = target.parent() {
+                                create_dirs(parent, &mut self.summary.created_dirs)?;
+                            }
+                            fs::hard_link(&source, &target)?;
+                            self.summary.files += 1;
+                            self.summary.paths.push(target);
+                        }
+                        None => self.summary.skipped += 1,
+                    }
+                }
+                // Devices, FIFOs and metadata entries have no place in a package
+                _ => self.summary.skipped += 1,
+            }
+        }
+        Ok(())
+    }
+
+    fn zip<R: Read + io::Seek>(&mut self, reader: R) -> Result<(), GetError> {
+        let mut archive = zip::ZipArchive::new(reader)
+            .map_err(|e| GetError::ParseError(format!("Invalid zip archive: {}", e)))?;
+
+        for i in 0..archive.len() {
+            let mut file = archive
+                .by_index(i)
+                .map_err(|e| GetError::ParseError(format!("Invalid zip entry: {}", e)))?;
+            // Zip names always use `/`, but Windows tools sometimes write `\`
+            let name = PathBuf::from(file.name().replace('\\', "/"));
+            let target = match self.target(&name)? {
+                Some(target) => target,
+                None => {
+                    self.summary.skipped += 1;
+                    continue;
+                }
+            };
+            let mode = file.unix_mode();
+
+            if file.is_dir() {
+                self.create_dir(&target, mode)?;
+            } else if mode.is_some_and(|mode| mode & S_IFMT == S_IFLNK) {
+                let mut link = String::new();
+                file.read_to_string(&mut link)?;
+                self.create_symlink(&target, Path::new(&link))?;
+            } else {
+                self.create_file(&target, &mut file, mode)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn seven_zip(&mut self, path: &Path) -> Result<(), GetError> {
+        let mut reader = sevenz_rust::SevenZReader::open(path, sevenz_rust::Password::empty())
+            .map_err(|e| GetError::ParseError(format!("Invalid 7z archive: {}", e)))?;
+
+        // The callback can only return 7z errors, so ours are carried out separately
+        let mut failure = None;
+        reader
+            .for_each_entries(|entry, data| {
+                match self.seven_zip_entry(entry, data) {
+                    Ok(()) => Ok(true),
+                    Err(e) => {
+                        failure = Some(e);
+                        Ok(false)
+                    }
+                }
+            })
+            .map_err(|e| GetError::ParseError(format!("Failed to read 7z archive: {}", e)))?;
+
+        match failure {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    fn seven_zip_entry(&mut self, entry: &sevenz_rust::SevenZArchiveEntry, data: &mut dyn Read) -> Result<(), GetError> {
+        let name = PathBuf::from(entry.name().replace('\\', "/"));
+        let target = match self.target(&name)? {
+            Some(target) if !entry.is_anti_item => target,
+            _ => {
+                // Drain the entry so the solid stream stays in sync
+                io::copy(data, &mut io::sink())?;
+                self.summary.skipped += 1;
+                return Ok(());
+            }
+        };
+        let mode = (entry.has_windows_attributes && entry.windows_attributes & FILE_ATTRIBUTE_UNIX_EXTENSION != 0)
+            .then_some(entry.windows_attributes >> 16);
+
+        if entry.is_directory() {
+            self.create_dir(&target, mode)
+        } else if mode.is_some_and(|mode| mode & S_IFMT == S_IFLNK) {
+            let mut link = String::new();
+            data.read_to_string(&mut link)?;
+            self.create_symlink(&target, Path::new(&link))
+        } else {
+            self.create_file(&target, data, mode)
+        }
+    }
+}
+
+/// Create `dir` and its missing parents, adding the ones that were missing to `created`
+fn create_dirs(dir: &Path, created: &mut Vec<PathBuf>) -> io::Result<()> {
+    let missing: Vec<PathBuf> = dir
+        .ancestors()
+        .filter(|d| !d.as_os_str().is_empty())
+        .take_while(|d| fs::symlink_metadata(d).is_err())
+        .map(Path::to_path_buf)
+        .collect();
+    fs::create_dir_all(dir)?;
+    created.extend(missing.into_iter().rev());
+    Ok(())
+}
+
+/// Apply the permission bits of a Unix mode (no-op elsewhere)
+#[cfg(unix)]
+fn set_mode(path: &Path, mode: Option<u32>) -> Result<(), GetError> {
+    use std::os::unix::fs::PermissionsExt;
+    if let Some(mode) = mode.map(|mode| mode & 0o7777).filter(|mode| *mode != 0) {
+        fs::set_permissions(path, fs::Permissions::from_mode(mode))?;
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn set_mode(_path: &Path, _mode: Option<u32>) -> Result<(), GetError> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    /// Build a zip from `(name, contents)`; contents starting with `->` make a symlink
+    fn write_zip(path: &Path, entries: &[(&str, &str)]) {
+        let mut zip = zip::ZipWriter::new(File::create(path).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        for (name, contents) in entries {
+            match contents.strip_prefix("->") {
+                Some(link) => zip.add_symlink(*name, link, options).unwrap(),
+                None => {
+                    zip.start_file(*name, options).unwrap();
+                    zip.write_all(contents.as_bytes()).unwrap();
+                }
+            }
+        }
+        zip.finish().unwrap();
+    }
+
+    /// Build a tar.gz, writing names as-is so unsafe ones survive; contents
+    /// starting with `->` make a symlink
+    fn write_tar_gz(path: &Path, entries: &[(&str, &str)]) {
+        let encoder = flate2::write::GzEncoder::new(File::create(path).unwrap(), flate2::Compression::fast());
+        let mut builder = tar::Builder::new(encoder);
+        for (name, contents) in entries {
+            let mut header = tar::Header::new_gnu();
+            let raw = header.as_gnu_mut().unwrap();
+            raw.name[..name.len()].copy_from_slice(name.as_bytes());
+            let data = match contents.strip_prefix("->") {
+                Some(link) => {
+                    header.set_entry_type(tar::EntryType::Symlink);
+                    header.as_gnu_mut().unwrap().linkname[..link.len()].copy_from_slice(link.as_bytes());
+                    ""
+                }
+                None => {
+                    header.set_entry_type(tar::EntryType::Regular);
+                    contents
+                }
+            };
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append(&header, data.as_bytes()).unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap();
+    }
+
+    fn assert_rejected(result: Result<ExtractSummary, GetError>) {
+        match result {
+            Err(GetError::ValidationError(_)) => {}
+            other => panic!("expected a validation error, got {:?}", other.map(|s| s.paths)),
+        }
+    }
+
+    #[test]
+    fn extracts_files_and_strips_components() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("tool.zip");
+        write_zip(&archive, &[("tool-1.0/bin/tool", "#!"), ("tool-1.0/README", "hi"), ("tool-1.0/bin/t", "->tool")]);
+
+        let dest = dir.path().join("out");
+        let summary = extract(&archive, ArchiveFormat::Zip, &dest, 1).unwrap();
+        assert_eq!(fs::read_to_string(dest.join("README")).unwrap(), "hi");
+        assert_eq!(summary.files, 2);
+        assert_eq!(summary.paths.len(), 3);
+        #[cfg(unix)]
+        assert_eq!(fs::read_link(dest.join("bin/t")).unwrap(), Path::new("tool"));
+    }
+
+    #[test]
+    fn rejects_zip_slip() {
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("out");
+        for name in ["../evil.txt", "a/../../evil.txt", "/tmp/evil.txt"] {
+            let archive = dir.path().join("slip.zip");
+            write_zip(&archive, &[(name, "pwned")]);
+            assert_rejected(extract(&archive, ArchiveFormat::Zip, &dest, 0));
+        }
+        assert!(!dir.path().join("evil.txt").exists());
+    }
+
+    #[test]
+    fn rejects_tar_parent_components() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("slip.tar.gz");
+        write_tar_gz(&archive, &[("ok.txt", "fine"), ("../evil.txt", "pwned")]);
+
+        assert_rejected(extract(&archive, ArchiveFormat::TarGz, &dir.path().join("out"), 0));
+        assert!(!dir.path().join("evil.txt").exists());
+    }
+
+    #[test]
+    fn rejects_symlinks_out_of_the_destination() {
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("out");
+        for link in ["->../outside", "->/etc/passwd", "->a/../../outside"] {
+            let archive = dir.path().join("link.tar.gz");
+            write_tar_gz(&archive, &[("link", link)]);
+            assert_rejected(extract(&archive, ArchiveFormat::TarGz, &dest, 0));
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_symlink_chains_out_of_the_destination() {
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("out");
+        fs::write(dir.path().join("secret"), "secret").unwrap();
+
+        // `s/y` points at `dest`, so `s/y/../secret` is outside although it
+        // looks like `s/secret`
+        let archive = dir.path().join("chain.tar.gz");
+        write_tar_gz(&archive, &[("s/y", "->.."), ("s/z", "->y/../secret")]);
+        assert_rejected(extract(&archive, ArchiveFormat::TarGz, &dest, 0));
+        assert!(fs::symlink_metadata(dest.join("s/z")).is_err());
+
+        let archive = dir.path().join("chain.zip");
+        write_zip(&archive, &[("s/y", "->.."), ("s/z", "->y/../../secret")]);
+        assert_rejected(extract(&archive, ArchiveFormat::Zip, &dir.path().join("out2"), 0));
+
+        // Links through links that stay lexically inside are refused too
+        let archive = dir.path().join("through.zip");
+        write_zip(&archive, &[("lib/real", "x"), ("l", "->lib"), ("t", "->l/real")]);
+        assert_rejected(extract(&archive, ArchiveFormat::Zip, &dir.path().join("out3"), 0));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_writes_through_archive_symlinks() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("write.tar.gz");
+        write_tar_gz(&archive, &[("d", "->."), ("d/file", "pwned")]);
+        assert_rejected(extract(&archive, ArchiveFormat::TarGz, &dir.path().join("out"), 0));
+    }
+}
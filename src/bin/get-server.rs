@@ -0,0 +1,318 @@
+//! Reference repository server
+//!
+//! Serves a directory of package manifests and artifacts over the HTTP
+//! repository protocol described in `get::protocol`:
+//!
+//! ```text
+//! <root>/manifests/**/*.{json,yaml,toml,msgpack}   PackageInfo manifests
+//! <root>/artifacts/**                              served at /artifacts/...
+//! ```
+//!
+//! Usage:
+//!   get-server <root> [--bind <addr:port>] [--basic <user:password>] [--token <token>]
+//!
+//! `GET_SERVER_BASIC` and `GET_SERVER_TOKEN` may be used instead of the flags.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fs::File;
+use std::path::{Component, Path, PathBuf};
+
+use base64::Engine;
+use sha2::{Digest, Sha256};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use get::config::PackageFormat;
+use get::error::GetError;
+use get::logging::{LogLevel, Logger};
+use get::package_manager::PackageInfo;
+use get::protocol::{self, RepositoryIndex};
+use get::version::select_version;
+
+/// Credentials accepted by the server, mirroring `RepositoryAuth`
+enum ServerAuth {
+    None,
+    Basic { username: String, password: String },
+    Token(String),
+}
+
+struct ServerOptions {
+    root: PathBuf,
+    bind: String,
+    auth: ServerAuth,
+}
+
+/// Loaded repository contents
+struct RepositoryState {
+    packages: BTreeMap<String, Vec<PackageInfo>>,
+    index: RepositoryIndex,
+    artifacts_dir: PathBuf,
+}
+
+fn parse_args() -> Result<ServerOptions, GetError> {
+    let usage = "Usage: get-server <root> [--bind <addr:port>] [--basic <user:password>] [--token <token>]";
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let mut root = None;
+    let mut bind = "127.0.0.1:8080".to_string();
+    let mut basic = env::var("GET_SERVER_BASIC").ok();
+    let mut token = env::var("GET_SERVER_TOKEN").ok();
+
+    let mut iter = args.into_iter();
+    while let Some(arg) = iter.next() {
+        let mut value = |flag: &str| {
+            iter.next()
+                .ok_or_else(|| GetError::InvalidInput(format!("Missing value for {}.\n{}", flag, usage)))
+        };
+        match arg.as_str() {
+            "--bind" => bind = value("--bind")?,
+            "--basic" => basic = Some(value("--basic")?),
+            "--token" => token = Some(value("--token")?),
+            "-h" | "--help" => return Err(GetError::InvalidInput(usage.to_string())),
+            _ if root.is_none() && !arg.starts_with("--") => root = Some(PathBuf::from(arg)),
+            _ => return Err(GetError::InvalidInput(format!("Unexpected argument '{}'.\n{}", arg, usage))),
+        }
+    }
+
+    let auth = match (basic, token) {
+        (Some(_), Some(_)) => {
+            return Err(GetError::InvalidInput("Use either --basic or --token, not both.".to_string()));
+        }
+        (Some(basic), None) => {
+            let (username, password) = basic
+                .split_once(':')
+                .ok_or_else(|| GetError::InvalidInput("--basic expects <user:password>.".to_string()))?;
+            ServerAuth::Basic { username: username.to_string(), password: password.to_string() }
+        }
+        (None, Some(token)) => ServerAuth::Token(token),
+        (None, None) => ServerAuth::None,
+    };
+
+    Ok(ServerOptions {
+        root: root.ok_or_else(|| GetError::InvalidInput(usage.to_string()))?,
+        bind,
+        auth,
+    })
+}
+
+fn load_repository(root: &Path, logger: &Logger) -> Result<RepositoryState, GetError> {
+    let manifests_dir = if root.join("manifests").is_dir() { root.join("manifests") } else { root.to_path_buf() };
+    let manifests = protocol::load_manifests(&manifests_dir)?;
+    logger.info(&format!("Loaded {} manifests from '{}'", manifests.len(), manifests_dir.display()));
+
+    let packages = protocol::group_packages(manifests.into_iter().map(|(_, p)| p).collect());
+    let index = protocol::build_index(&packages);
+
+    Ok(RepositoryState {
+        packages,
+        index,
+        artifacts_dir: root.join("artifacts"),
+    })
+}
+
+fn header(name: &str, value: &str) -> Header {
+    Header::from_bytes(name.as_bytes(), value.as_bytes()).expect("valid header")
+}
+
+fn request_header<'a>(request: &'a Request, name: &'static str) -> Option<&'a str> {
+    request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv(name))
+        .map(|h| h.value.as_str())
+}
+
+fn is_authorized(request: &Request, auth: &ServerAuth) -> bool {
+    let provided = request_header(request, "Authorization").unwrap_or("");
+    match auth {
+        ServerAuth::None => true,
+        ServerAuth::Token(token) => provided.strip_prefix("Bearer ") == Some(token.as_str()),
+        ServerAuth::Basic { username, password } => provided
+            .strip_prefix("Basic ")
+            .and_then(|encoded| base64::engine::general_purpose::STANDARD.decode(encoded).ok())
+            .and_then(|decoded| String::from_utf8(decoded).ok())
+            .is_some_and(|credentials| credentials == format!("{}:{}", username, password)),
+    }
+}
+
+fn text_response(status: u16, message: &str) -> Response<std::io::Cursor<Vec<u8>>> {
+    Response::from_string(message)
+        .with_status_code(status)
+        .with_header(header("Content-Type", "text/plain; charset=utf-8"))
+}
+
+/// Split `name.ext` into the name and the format the extension asks for
+fn strip_format_extension(segment: &str) -> (&str, Option<PackageFormat>) {
+    match segment.rsplit_once('.') {
+        Some((stem, ext)) => match protocol::format_for_extension(ext) {
+            Some(format) => (stem, Some(format)),
+            None => (segment, None),
+        },
+        None => (segment, None),
+    }
+}
+
+/// Encode `value` in the negotiated format, with an ETag over the body
+fn encoded_response<T: serde::Serialize>(
+    request: &Request,
+    format: PackageFormat,
+    value: &T,
+) -> Result<Response<std::io::Cursor<Vec<u8>>>, GetError> {
+    let body = protocol::encode(&format, value)?;
+    let etag = format!("\"{}\"", &hex::encode(Sha256::digest(&body))[..32]);
+
+    if request_header(request, "If-None-Match") == Some(etag.as_str()) {
+        return Ok(Response::from_data(Vec::new())
+            .with_status_code(304)
+            .with_header(header("ETag", &etag)));
+    }
+
+    Ok(Response::from_data(body)
+        .with_header(header("Content-Type", protocol::media_type(&format)))
+        .with_header(header("ETag", &etag)))
+}
+
+/// Resolve `/artifacts/<path>` inside the artifacts directory, rejecting traversal
+fn artifact_path(artifacts_dir: &Path, relative: &str) -> Option<PathBuf> {
+    let relative = Path::new(relative);
+    if relative.components().any(|c| !matches!(c, Component::Normal(_))) {
+        return None;
+    }
+    let path = artifacts_dir.join(relative);
+    path.is_file().then_some(path)
+}
+
+/// Decode `%XX` escapes in a path segment (`+` is kept, as in semver build metadata)
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| value.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn handle(request: Request, state: &RepositoryState, auth: &ServerAuth) -> Result<(), GetError> {
+    if !is_authorized(&request, auth) {
+        let challenge = match auth {
+            ServerAuth::Basic { .. } => "Basic realm=\"get\"",
+            _ => "Bearer",
+        };
+        let response = text_response(401, "Unauthorized").with_header(header("WWW-Authenticate", challenge));
+        return Ok(request.respond(response)?);
+    }
+
+    if request.method() != &Method::Get && request.method() != &Method::Head {
+        return Ok(request.respond(text_response(405, "Method not allowed"))?);
+    }
+
+    let url = request.url().to_string();
+    let (path, query) = url.split_once('?').unwrap_or((url.as_str(), ""));
+    let segments: Vec<String> = path.trim_matches('/').split('/').map(percent_decode).collect();
+    let accepted = protocol::negotiate(request_header(&request, "Accept"));
+
+    match segments.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+        [first] if strip_format_extension(first).0 == "index" => {
+            let format = strip_format_extension(first).1.unwrap_or(accepted);
+            let response = encoded_response(&request, format, &state.index)?;
+            Ok(request.respond(response)?)
+        }
+        ["search"] => {
+            let query = url::form_urlencoded::parse(query.as_bytes())
+                .find(|(key, _)| key == "q")
+                .map(|(_, value)| value.into_owned())
+                .unwrap_or_default();
+            let matches = RepositoryIndex {
+                packages: state.index.packages.iter().filter(|p| p.matches(&query)).cloned().collect(),
+            };
+            let response = encoded_response(&request, accepted, &matches)?;
+            Ok(request.respond(response)?)
+        }
+        ["packages", name, rest @ ..] if rest.len() <= 1 => {
+            let versions = match state.packages.get(&name.to_lowercase()) {
+                Some(versions) => versions,
+                None => return Ok(request.respond(text_response(404, "Package not found"))?),
+            };
+
+            // `packages/{name}` serves the latest version
+            let (wanted, format) = match rest.first() {
+                Some(segment) => {
+                    let (version, format) = strip_format_extension(segment);
+                    (Some(version), format.unwrap_or(accepted))
+                }
+                None => (None, accepted),
+            };
+            match select_version(versions, |p| p.version.as_str(), wanted) {
+                Some(package) => {
+                    let response = encoded_response(&request, format, package)?;
+                    Ok(request.respond(response)?)
+                }
+                None => Ok(request.respond(text_response(404, "Version not found"))?),
+            }
+        }
+        ["artifacts", rest @ ..] if !rest.is_empty() => {
+            match artifact_path(&state.artifacts_dir, &rest.join("/")) {
+                Some(path) => {
+                    let response = Response::from_file(File::open(path)?)
+                        .with_header(header("Content-Type", "application/octet-stream"));
+                    Ok(request.respond(response)?)
+                }
+                None => Ok(request.respond(text_response(404, "Artifact not found"))?),
+            }
+        }
+        _ => Ok(request.respond(text_response(404, "Not found"))?),
+    }
+}
+
+fn main() {
+    let logger = Logger::new(LogLevel::Minimal);
+
+    let options = match parse_args() {
+        Ok(options) => options,
+        Err(e) => {
+            match e {
+                GetError::InvalidInput(msg) => logger.error(&msg),
+                _ => logger.error("An unexpected error occurred while parsing arguments."),
+            }
+            std::process::exit(1);
+        }
+    };
+
+    let state = match load_repository(&options.root, &logger) {
+        Ok(state) => state,
+        Err(e) => {
+            logger.error(&format!("Failed to load repository: {}", e));
+            std::process::exit(1);
+        }
+    };
+
+    let server = match Server::http(&options.bind) {
+        Ok(server) => server,
+        Err(e) => {
+            logger.error(&format!("Failed to bind '{}': {}", options.bind, e));
+            std::process::exit(1);
+        }
+    };
+    logger.info(&format!("Serving '{}' on http://{}", options.root.display(), options.bind));
+
+    for request in server.incoming_requests() {
+        let summary = format!("{} {}", request.method(), request.url());
+        if let Err(e) = handle(request, &state, &options.auth) {
+            logger.warn(&format!("{}: {}", summary, e));
+        }
+    }
+}
@@ -0,0 +1,350 @@
+//! Offline bundles
+//!
+//! A bundle is a zip archive holding everything needed to install a set of
+//! packages without network access:
+//!
+//! ```text
+//! bundle.json                                   BundleManifest
+//! index.json                                    RepositoryIndex of the bundled packages
+//! packages/<name>/<version>.json                PackageInfo manifests
+//! artifacts/<name>/<version>/<file>             downloaded artifacts
+//! ```
+//!
+//! Artifacts are verified against the manifest checksum both when the bundle
+//! is created and again before installing from it.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Component, Path, PathBuf};
+
+use indicatif::MultiProgress;
+use serde::{Deserialize, Serialize};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::config::{Config, PackageFormat};
+use crate::error::GetError;
+use crate::logging::Logger;
+use crate::package_manager::{PackageInfo, UniversalPackageManager};
+use crate::protocol;
+use crate::resolver::{build_graph, PackageLookup, ResolvedPackage};
+use crate::utils::{download_file, sha256_file, validate_path_component};
+use crate::version::{split_package_spec, VersionReq};
+
+/// Conventional extension for bundle files
+pub const BUNDLE_EXTENSION: &str = "getpkg";
+
+const BUNDLE_FORMAT_VERSION: u32 = 1;
+
+/// Top-level description of a bundle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleManifest {
+    pub format_version: u32,
+    pub created: String,
+    /// The packages the bundle was created for, as `name@version`
+    pub requested: Vec<String>,
+    pub packages: Vec<BundleEntry>,
+}
+
+/// One package inside a bundle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleEntry {
+    pub name: String,
+    pub version: String,
+    /// Path of the artifact inside the bundle
+    pub artifact: String,
+}
+
+fn manifest_entry_path(package: &PackageInfo) -> String {
+    format!("packages/{}/{}.json", package.name, package.version)
+}
+
+fn artifact_entry_path(package: &PackageInfo) -> Result<String, GetError> {
+    let file_name = package
+        .source_url
+        .split(['?', '#'])
+        .next()
+        .and_then(|url| url.rsplit('/').next())
+        .filter(|name| !name.is_empty())
+        .ok_or_else(|| GetError::InvalidInput(format!("Invalid source URL for '{}'", package.name)))?;
+    Ok(format!("artifacts/{}/{}/{}", package.name, package.version, file_name))
+}
+
+/// Looks packages up in the configured repositories for the resolver,
+/// keeping every manifest it fetched
+struct RepositoryLookup<'a> {
+    config: &'a Config,
+    logger: &'a Logger,
+    m: &'a MultiProgress,
+    fetched: HashMap<(String, String), PackageInfo>,
+}
+
+impl PackageLookup for RepositoryLookup<'_> {
+    fn resolve(&mut self, package: &str, requirement: &VersionReq) -> Result<ResolvedPackage, GetError> {
+        let spec = if requirement.is_any() { package.to_string() } else { format!("{}@{}", package, requirement) };
+        let info = UniversalPackageManager.fetch_package_info(&spec, self.config, self.logger, self.m)?;
+        let resolved = ResolvedPackage {
+            name: info.name.clone(),
+            version: info.version.clone(),
+            source: "repository".to_string(),
+            install_spec: format!("{}@{}", info.name, info.version),
+            dependencies: info.dependencies.clone(),
+        };
+        self.fetched.insert((info.name.to_lowercase(), info.version.clone()), info);
+        Ok(resolved)
+    }
+
+    /// A bundle is for another machine, so what is installed here doesn't matter
+    fn installed_version(&mut self, _package: &ResolvedPackage) -> Option<String> {
+        None
+    }
+}
+
+/// Resolve `specs` and every dependency they pull in to one version each
+fn resolve_closure(specs: &[String], config: &Config, logger: &Logger, m: &MultiProgress) -> Result<Vec<PackageInfo>, GetError> {
+    let mut lookup = RepositoryLookup { config, logger, m, fetched: HashMap::new() };
+    let graph = build_graph(specs, &mut lookup)?;
+    Ok(graph
+        .nodes
+        .iter()
+        .filter_map(|node| lookup.fetched.remove(&(node.package.name.to_lowercase(), node.package.version.clone())))
+        .collect())
+}
+
+fn write_entry(zip: &mut ZipWriter<File>, name: &str, data: &[u8]) -> Result<(), GetError> {
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    zip.start_file(name, options).map_err(zip_error)?;
+    zip.write_all(data)?;
+    Ok(())
+}
+
+fn zip_error(e: zip::result::ZipError) -> GetError {
+    GetError::IoError(format!("Bundle archive error: {}", e))
+}
+
+/// Resolve `specs` and their dependencies from the configured repositories,
+/// download every artifact and pack them into `output`.
+pub fn create_bundle(
+    specs: &[String],
+    output: &Path,
+    config: &Config,
+    logger: &Logger,
+    m: &MultiProgress,
+) -> Result<BundleManifest, GetError> {
+    if specs.is_empty() {
+        return Err(GetError::InvalidInput("No packages given for the bundle".to_string()));
+    }
+
+    let packages = resolve_closure(specs, config, logger, m)?;
+    let work_dir = tempfile::tempdir()?;
+
+    if let Some(parent) = output.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    let mut zip = ZipWriter::new(File::create(output)?);
+    let mut entries = Vec::with_capacity(packages.len());
+
+    for package in &packages {
+        logger.log(&format!("Bundling '{}' {}...", package.name, package.version));
+        let downloaded = download_file(&package.source_url, work_dir.path(), logger, m)?;
+
+        // Never ship an artifact the installer would reject
+        let checksum = sha256_file(&downloaded)?;
+        if !checksum.eq_ignore_ascii_case(&package.checksum) {
+            return Err(GetError::ValidationError(format!(
+                "Checksum mismatch for '{}' {}. Expected: {}, Got: {}",
+                package.name, package.version, package.checksum, checksum
+            )));
+        }
+
+        let artifact = artifact_entry_path(package)?;
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        zip.start_file(artifact.as_str(), options).map_err(zip_error)?;
+        io::copy(&mut File::open(&downloaded)?, &mut zip)?;
+        fs::remove_file(&downloaded)?;
+
+        write_entry(&mut zip, &manifest_entry_path(package), &protocol::encode(&PackageFormat::Json, package)?)?;
+        entries.push(BundleEntry {
+            name: package.name.clone(),
+            version: package.version.clone(),
+            artifact,
+        });
+    }
+
+    let requested = packages
+        .iter()
+        .filter(|p| specs.iter().any(|spec| split_package_spec(spec).0.eq_ignore_ascii_case(&p.name)))
+        .map(|p| format!("{}@{}", p.name, p.version))
+        .collect();
+    let manifest = BundleManifest {
+        format_version: BUNDLE_FORMAT_VERSION,
+        created: chrono::Utc::now().to_rfc3339(),
+        requested,
+        packages: entries,
+    };
+
+    let index = protocol::build_index(&protocol::group_packages(packages));
+    write_entry(&mut zip, "index.json", &protocol::encode(&PackageFormat::Json, &index)?)?;
+    write_entry(&mut zip, "bundle.json", &protocol::encode(&PackageFormat::Json, &manifest)?)?;
+    zip.finish().map_err(zip_error)?;
+
+    logger.info(&format!(
+        "Bundled {} packages into '{}'",
+        manifest.packages.len(),
+        output.display()
+    ));
+    Ok(manifest)
+}
+
+/// An extracted bundle
+pub struct Bundle {
+    pub manifest: BundleManifest,
+    packages: BTreeMap<String, (PackageInfo, PathBuf)>,
+    // Keeps the extracted files alive for as long as the bundle is in use
+    _dir: tempfile::TempDir,
+}
+
+impl Bundle {
+    /// Extract a bundle and load its manifests
+    pub fn open(path: &Path) -> Result<Bundle, GetError> {
+        let file = File::open(path)?;
+        let mut archive = ZipArchive::new(file)
+            .map_err(|e| GetError::ParseError(format!("'{}' is not a bundle: {}", path.display(), e)))?;
+        let dir = tempfile::tempdir()?;
+        archive.extract(dir.path()).map_err(zip_error)?;
+
+        let manifest: BundleManifest = protocol::decode(&PackageFormat::Json, &fs::read(dir.path().join("bundle.json"))?)
+            .map_err(|e| GetError::ParseError(format!("Invalid bundle manifest: {}", e)))?;
+        if manifest.format_version > BUNDLE_FORMAT_VERSION {
+            return Err(GetError::ParseError(format!(
+                "Bundle format {} is newer than this version of get supports",
+                manifest.format_version
+            )));
+        }
+
+        let mut packages = BTreeMap::new();
+        for entry in &manifest.packages {
+            // Entries name files in the extracted bundle; keep them inside it
+            validate_path_component("name", &entry.name)?;
+            validate_path_component("version", &entry.version)?;
+            if !Path::new(&entry.artifact).components().all(|c| matches!(c, Component::Normal(_))) {
+                return Err(GetError::ValidationError(format!(
+                    "Invalid artifact path '{}' for '{}'",
+                    entry.artifact, entry.name
+                )));
+            }
+            let manifest_path = dir.path().join("packages").join(&entry.name).join(format!("{}.json", entry.version));
+            let package: PackageInfo = protocol::decode(&PackageFormat::Json, &fs::read(&manifest_path)?)?;
+            packages.insert(entry.name.to_lowercase(), (package, dir.path().join(&entry.artifact)));
+        }
+
+        Ok(Bundle { manifest, packages, _dir: dir })
+    }
+
+    /// The bundled manifest and artifact for `name`
+    pub fn get(&self, name: &str) -> Option<(&PackageInfo, &Path)> {
+        self.packages
+            .get(&name.to_lowercase())
+            .map(|(package, artifact)| (package, artifact.as_path()))
+    }
+
+    /// `names` and their dependencies, dependencies first
+    pub fn install_order(&self, names: &[String]) -> Result<Vec<&PackageInfo>, GetError> {
+        fn visit<'a>(
+            bundle: &'a Bundle,
+            name: &str,
+            order: &mut Vec<&'a PackageInfo>,
+            visiting: &mut HashSet<String>,
+        ) -> Result<(), GetError> {
+            let key = name.to_lowercase();
+            if order.iter().any(|p| p.name.to_lowercase() == key) || !visiting.insert(key.clone()) {
+                return Ok(());
+            }
+            let (package, _) = bundle
+                .get(&key)
+                .ok_or_else(|| GetError::PackageNotFound(format!("'{}' is not in the bundle", name)))?;
+            for dependency in &package.dependencies {
+                visit(bundle, split_package_spec(dependency).0, order, visiting)?;
+            }
+            order.push(package);
+            Ok(())
+        }
+
+        let mut order = Vec::new();
+        let mut visiting = HashSet::new();
+        for name in names {
+            visit(self, split_package_spec(name).0, &mut order, &mut visiting)?;
+        }
+        Ok(order)
+    }
+}
+
+/// Install `packages` (or everything the bundle was created for) from a
+/// bundle, without network access
+pub fn install_from_bundle(
+    path: &Path,
+    packages: &[String],
+    config: &Config,
+    logger: &Logger,
+    m: &MultiProgress,
+) -> Result<(), GetError> {
+    let bundle = Bundle::open(path)?;
+    let wanted = if packages.is_empty() { bundle.manifest.requested.clone() } else { packages.to_vec() };
+
+    let manager = UniversalPackageManager;
+    for package in bundle.install_order(&wanted)? {
+        let (_, artifact) = bundle.get(&package.name).expect("ordered packages are in the bundle");
+        logger.log(&format!("Installing '{}' {} from bundle...", package.name, package.version));
+        let installed = manager.install_from_file(package, artifact, config, logger, m)?;
+        manager.record_install(package, "bundle", &installed, config)?;
+        logger.info(&format!("Package '{}' installed successfully.", package.name));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Write a bundle whose manifest lists one package
+    fn write_bundle(path: &Path, name: &str, version: &str, artifact: &str) {
+        let manifest = BundleManifest {
+            format_version: BUNDLE_FORMAT_VERSION,
+            created: String::new(),
+            requested: vec![name.to_string()],
+            packages: vec![BundleEntry {
+                name: name.to_string(),
+                version: version.to_string(),
+                artifact: artifact.to_string(),
+            }],
+        };
+        let mut zip = ZipWriter::new(File::create(path).unwrap());
+        write_entry(&mut zip, "bundle.json", &protocol::encode(&PackageFormat::Json, &manifest).unwrap()).unwrap();
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn rejects_entries_that_escape_the_bundle() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bundle.getpkg");
+        let cases = [
+            ("..", "1.0", "artifacts/x/1.0/x.zip"),
+            ("tool", "../../../etc", "artifacts/tool/1.0/x.zip"),
+            ("tool", "1.0/..", "artifacts/tool/1.0/x.zip"),
+            ("", "1.0", "artifacts/tool/1.0/x.zip"),
+            ("tool", "1.0", "../outside.zip"),
+            ("tool", "1.0", "/etc/passwd"),
+        ];
+        for (name, version, artifact) in cases {
+            write_bundle(&path, name, version, artifact);
+            assert!(
+                matches!(Bundle::open(&path), Err(GetError::ValidationError(_))),
+                "{} {} {}",
+                name,
+                version,
+                artifact
+            );
+        }
+    }
+}
@@ -1,142 +1,441 @@
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::Read;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime};
 
+use base64::Engine;
 use indicatif::{ProgressBar, MultiProgress};
+use reqwest::blocking::Client;
+use reqwest::header::USER_AGENT;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256, Sha512};
 
 use crate::{Config, GetError, Logger};
+use get::package_db::{package_db_path, InstalledPackage, PackageDb};
+use get::package_manager::{InstallReport, PackageManager, SearchHit, UninstallReport};
+use get::utils::download_file;
+use get::version::{select_version, split_package_spec, Version, VersionReq};
 
-const CHOCO_REPO_URL: &str = "https://github.com/chocolatey/choco.git";
+/// Default Chocolatey community feed (NuGet v2 OData)
+const CHOCO_FEED_URL: &str = "https://community.chocolatey.org/api/v2";
 
-#[derive(Debug, Deserialize, Serialize)]
+/// Chocolatey package metadata, from the feed or from a `.nuspec`
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct ChocoManifest {
-    package_id: String,
-    version: String,
-    description: String,
-    url: String,
-    sha256: String,
+    pub package_id: String,
+    pub version: String,
+    pub title: Option<String>,
+    pub summary: Option<String>,
+    pub description: String,
+    pub authors: Option<String>,
+    pub project_url: Option<String>,
+    pub tags: Vec<String>,
+    pub dependencies: Vec<ChocoDependency>,
+    /// Download URL of the `.nupkg`
+    pub url: String,
+    /// Base64-encoded package hash as published by the feed
+    pub package_hash: Option<String>,
+    pub package_hash_algorithm: Option<String>,
 }
 
-pub fn ensure_choco_repo(config: &Config, logger: &Logger, m: &MultiProgress) -> Result<PathBuf, GetError> {
-    if crate::SHOULD_TERMINATE.load(Ordering::SeqCst) {
-        return Err(GetError::InvalidInput("Operation terminated by user.".to_string()));
-    }
+/// A dependency declared by a Chocolatey package
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ChocoDependency {
+    pub id: String,
+    /// NuGet version range, e.g. `1.2.0` or `[14.0,)`
+    pub version: Option<String>,
+}
 
-    let repos_dir = config.get_repos_dir();
-    let choco_local_path = repos_dir.join("choco");
+/// Translate a NuGet version range into a [`VersionReq`]. A bare version
+/// means "at least" in NuGet; `[a,b)` style ranges map to comparators.
+pub fn nuget_requirement(range: &str) -> VersionReq {
+    let range = range.trim();
+    let (open, close) = match (range.chars().next(), range.chars().last()) {
+        (Some(open @ ('[' | '(')), Some(close @ (']' | ')'))) if range.len() >= 2 => (open, close),
+        _ if range.is_empty() => return VersionReq::any(),
+        _ => return VersionReq::at_least(range),
+    };
+    let inner = &range[1..range.len() - 1];
+    let (lower, upper) = match inner.split_once(',') {
+        Some((lower, upper)) => (lower.trim(), upper.trim()),
+        // `[1.0]` is the only version allowed
+        None => return VersionReq::exact(inner.trim()),
+    };
 
-    ensure_repo(CHOCO_REPO_URL, &choco_local_path, logger, m)?;
+    let mut comparators = Vec::new();
+    if !lower.is_empty() {
+        comparators.push(format!("{}{}", if open == '[' { ">=" } else { ">" }, lower));
+    }
+    if !upper.is_empty() {
+        comparators.push(format!("{}{}", if close == ']' { "<=" } else { "<" }, upper));
+    }
+    VersionReq::parse(&comparators.join(",")).unwrap_or_default()
+}
 
-    Ok(choco_local_path)
+/// A NuGet v2 OData feed
+pub struct ChocoFeed {
+    base_url: String,
+    client: Client,
 }
 
-fn ensure_repo(repo_url: &str, local_path: &Path, logger: &Logger, m: &MultiProgress) -> Result<(), GetError> {
-    if crate::SHOULD_TERMINATE.load(Ordering::SeqCst) {
-        return Err(GetError::InvalidInput("Operation terminated by user.".to_string()));
+impl ChocoFeed {
+    /// The feed configured via `choco_repo_url`, or the community feed
+    pub fn from_config(config: &Config) -> Result<Self, GetError> {
+        let base_url = config.choco_repo_url.as_deref().unwrap_or(CHOCO_FEED_URL);
+        Ok(ChocoFeed {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            client: Client::builder().timeout(Duration::from_secs(30)).build()?,
+        })
+    }
+
+    pub fn url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// One page of a feed, and the link to the next page if there is one
+    fn get_feed(&self, url: &str) -> Result<(Vec<ChocoManifest>, Option<String>), GetError> {
+        let response = self
+            .client
+            .get(url)
+            .header(USER_AGENT, "get-package-manager/1.0")
+            .send()?;
+
+        if !response.status().is_success() {
+            return Err(GetError::NetworkError(format!(
+                "Chocolatey feed request failed: HTTP {}",
+                response.status()
+            )));
+        }
+
+        parse_feed(&response.text()?)
     }
 
-    if local_path.exists() {
-        logger.log(&format!(
-            "Repository at '{}' already exists. Checking if it needs to be updated...",
-            local_path.display()
-        ));
-
-        let last_pull_path = local_path.join("last_pull.txt");
-        let needs_pull = if last_pull_path.exists() {
-            let content = std::fs::read_to_string(&last_pull_path)?;
-            if let Ok(timestamp) = content.trim().parse::<u64>() {
-                let last_pull_time = UNIX_EPOCH + Duration::from_secs(timestamp);
-                if let Ok(system_time) = SystemTime::now().duration_since(last_pull_time) {
-                    system_time.as_secs() > 24 * 3600
-                } else {
-                    true
-                }
-            } else {
-                true
+    /// Search the latest stable versions of matching packages
+    pub fn search(&self, query: &str, limit: usize) -> Result<Vec<ChocoManifest>, GetError> {
+        let url = format!(
+            "{}/Search()?$filter=IsLatestVersion&searchTerm='{}'&targetFramework=''&includePrerelease=false&$top={}",
+            self.base_url,
+            odata_escape(query),
+            limit
+        );
+        Ok(self.get_feed(&url)?.0)
+    }
+
+    /// Every version of a package; the feed pages long version lists
+    pub fn versions(&self, id: &str) -> Result<Vec<ChocoManifest>, GetError> {
+        let mut url = format!("{}/FindPackagesById()?id='{}'", self.base_url, odata_escape(id));
+        let mut versions = Vec::new();
+        let mut seen = HashSet::new();
+        while seen.insert(url.clone()) {
+            let (page, next) = self.get_feed(&url)?;
+            versions.extend(page);
+            match next {
+                Some(next) => url = next,
+                None => break,
             }
-        } else {
-            true
-        };
-
-        if needs_pull {
-            let pb = m.add(ProgressBar::new_spinner());
-            pb.set_message(format!("Pulling latest changes for repository '{}'.", local_path.display()));
-            pb.enable_steady_tick(Duration::from_millis(100));
-            let status = Command::new("git")
-                .args(&["-C", local_path.to_str().unwrap(), "pull"])
-                .status()?;
-
-            pb.finish_and_clear();
-
-            if status.success() {
-                logger.log(&format!(
-                    "Successfully updated repository at '{}'.",
-                    local_path.display()
-                ));
-                let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
-                std::fs::write(&last_pull_path, now.to_string())?;
-                Ok(())
-            } else {
-                Err(GetError::CommandError(format!(
-                    "Failed to pull updates for repository '{}'.",
-                    local_path.display()
-                )))
+        }
+        Ok(versions)
+    }
+
+    /// Look up a package at `version`, or at its latest stable version
+    pub fn find(&self, id: &str, version: Option<&str>) -> Result<ChocoManifest, GetError> {
+        let versions = self.versions(id)?;
+
+        select_version(&versions, |p| p.version.as_str(), version)
+            .cloned()
+            .ok_or_else(|| match version {
+                Some(v) => GetError::PackageNotFound(format!("'{}' version {} not found on {}", id, v, self.base_url)),
+                None => GetError::PackageNotFound(format!("'{}' not found on {}", id, self.base_url)),
+            })
+    }
+
+    /// Download the `.nupkg` of `manifest` and verify it against the feed hash
+    pub fn download(&self, manifest: &ChocoManifest, download_dir: &Path, logger: &Logger, m: &MultiProgress) -> Result<PathBuf, GetError> {
+        let downloaded = download_file(&manifest.url, download_dir, logger, m)?;
+
+        // The download URL ends in the version, not in a file name
+        let nupkg_path = download_dir.join(format!("{}.{}.nupkg", manifest.package_id, manifest.version));
+        std::fs::rename(&downloaded, &nupkg_path)?;
+
+        match &manifest.package_hash {
+            Some(expected) => {
+                verify_package_hash(&nupkg_path, expected, manifest.package_hash_algorithm.as_deref())?;
+                logger.log("Package hash verified.");
             }
-        } else {
-            logger.log(&format!(
-                "Repository '{}' is up-to-date. No need to pull.",
-                local_path.display()
-            ));
-            Ok(())
+            None => logger.warn(&format!("The feed has no hash for '{}'; it was not verified.", manifest.package_id)),
         }
-    } else {
-        let pb = m.add(ProgressBar::new_spinner());
-        pb.set_message(format!(
-            "Cloning repository from '{}' to '{}'.",
-            repo_url,
-            local_path.display()
-        ));
-        pb.enable_steady_tick(Duration::from_millis(100));
-        let status = Command::new("git")
-            .args(&["clone", repo_url, local_path.to_str().unwrap()])
-            .status()?;
-
-        pb.finish_and_clear();
-
-        if status.success() {
-            logger.log(&format!(
-                "Successfully cloned repository to '{}'.",
-                local_path.display()
-            ));
-            let last_pull_path = local_path.join("last_pull.txt");
-            let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
-            std::fs::write(&last_pull_path, now.to_string())?;
-            Ok(())
-        } else {
-            Err(GetError::CommandError(format!(
-                "Failed to clone repository from '{}'.",
-                repo_url
-            )))
+
+        Ok(nupkg_path)
+    }
+}
+
+/// Quote a value for an OData string literal
+fn odata_escape(value: &str) -> String {
+    url::form_urlencoded::byte_serialize(value.replace('\'', "''").as_bytes()).collect()
+}
+
+/// Parse an Atom feed of NuGet v2 package entries, and its `next` link
+fn parse_feed(xml: &str) -> Result<(Vec<ChocoManifest>, Option<String>), GetError> {
+    let doc = roxmltree::Document::parse(xml)
+        .map_err(|e| GetError::ParseError(format!("Invalid Chocolatey feed: {}", e)))?;
+
+    let manifests = doc
+        .descendants()
+        .filter(|n| n.tag_name().name() == "entry")
+        .filter_map(|entry| {
+            let properties = entry.descendants().find(|n| n.tag_name().name() == "properties")?;
+            let prop = |name: &str| {
+                properties
+                    .children()
+                    .find(|n| n.tag_name().name() == name)
+                    .and_then(|n| n.text())
+                    .map(str::to_string)
+                    .filter(|s| !s.is_empty())
+            };
+            let url = entry
+                .children()
+                .find(|n| n.tag_name().name() == "content")
+                .and_then(|n| n.attribute("src"))?
+                .to_string();
+            // Newer feeds carry the id only in the entry title
+            let package_id = prop("Id").or_else(|| {
+                entry
+                    .children()
+                    .find(|n| n.tag_name().name() == "title")
+                    .and_then(|n| n.text())
+                    .map(str::to_string)
+            })?;
+
+            Some(ChocoManifest {
+                package_id,
+                version: prop("Version")?,
+                title: prop("Title"),
+                summary: prop("Summary"),
+                description: prop("Description").unwrap_or_default(),
+                authors: prop("Authors"),
+                project_url: prop("ProjectUrl"),
+                tags: prop("Tags").map(|t| split_tags(&t)).unwrap_or_default(),
+                dependencies: prop("Dependencies").map(|d| parse_feed_dependencies(&d)).unwrap_or_default(),
+                url,
+                package_hash: prop("PackageHash"),
+                package_hash_algorithm: prop("PackageHashAlgorithm"),
+            })
+        })
+        .collect();
+    let next = doc
+        .root_element()
+        .children()
+        .find(|n| n.tag_name().name() == "link" && n.attribute("rel") == Some("next"))
+        .and_then(|n| n.attribute("href"))
+        .map(str::to_string);
+
+    Ok((manifests, next))
+}
+
+fn split_tags(tags: &str) -> Vec<String> {
+    tags.split_whitespace().map(str::to_string).collect()
+}
+
+/// Parse the feed's `id:version:framework|id:version` dependency list
+fn parse_feed_dependencies(deps: &str) -> Vec<ChocoDependency> {
+    deps.split('|')
+        .filter_map(|dep| {
+            let mut parts = dep.splitn(3, ':');
+            let id = parts.next()?.trim();
+            if id.is_empty() {
+                return None;
+            }
+            let version = parts.next().map(str::trim).filter(|v| !v.is_empty());
+            Some(ChocoDependency {
+                id: id.to_string(),
+                version: version.map(str::to_string),
+            })
+        })
+        .collect()
+}
+
+/// Read the `.nuspec` inside a `.nupkg` into a manifest
+pub fn read_nupkg(nupkg_path: &Path) -> Result<ChocoManifest, GetError> {
+    let file = File::open(nupkg_path)?;
+    let mut archive = zip::ZipArchive::new(file)
+        .map_err(|e| GetError::ParseError(format!("Invalid .nupkg: {}", e)))?;
+
+    let nuspec_name = archive
+        .file_names()
+        .find(|name| !name.contains('/') && name.ends_with(".nuspec"))
+        .map(str::to_string)
+        .ok_or_else(|| GetError::ParseError("No .nuspec found in package".to_string()))?;
+
+    let mut nuspec = String::new();
+    archive
+        .by_name(&nuspec_name)
+        .map_err(|e| GetError::ParseError(e.to_string()))?
+        .read_to_string(&mut nuspec)?;
+
+    parse_nuspec(&nuspec)
+}
+
+/// Parse `.nuspec` metadata, including grouped and ungrouped dependencies
+pub fn parse_nuspec(xml: &str) -> Result<ChocoManifest, GetError> {
+    let doc = roxmltree::Document::parse(xml)
+        .map_err(|e| GetError::ParseError(format!("Invalid .nuspec: {}", e)))?;
+
+    let metadata = doc
+        .descendants()
+        .find(|n| n.tag_name().name() == "metadata")
+        .ok_or_else(|| GetError::ParseError("Missing <metadata> in .nuspec".to_string()))?;
+    let field = |name: &str| {
+        metadata
+            .children()
+            .find(|n| n.tag_name().name() == name)
+            .and_then(|n| n.text())
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+    };
+
+    let dependencies = metadata
+        .descendants()
+        .filter(|n| n.tag_name().name() == "dependency")
+        .filter_map(|n| {
+            Some(ChocoDependency {
+                id: n.attribute("id")?.to_string(),
+                version: n.attribute("version").map(str::to_string),
+            })
+        })
+        .collect();
+
+    Ok(ChocoManifest {
+        package_id: field("id").ok_or_else(|| GetError::ParseError("Missing <id> in .nuspec".to_string()))?,
+        version: field("version").ok_or_else(|| GetError::ParseError("Missing <version> in .nuspec".to_string()))?,
+        title: field("title"),
+        summary: field("summary"),
+        description: field("description").unwrap_or_default(),
+        authors: field("authors"),
+        project_url: field("projectUrl"),
+        tags: field("tags").map(|t| split_tags(&t)).unwrap_or_default(),
+        dependencies,
+        url: String::new(),
+        package_hash: None,
+        package_hash_algorithm: None,
+    })
+}
+
+fn verify_package_hash(path: &Path, expected: &str, algorithm: Option<&str>) -> Result<(), GetError> {
+    let expected = base64::engine::general_purpose::STANDARD
+        .decode(expected)
+        .map_err(|e| GetError::ParseError(format!("Invalid package hash: {}", e)))?;
+
+    let data = std::fs::read(path)?;
+    let actual = match algorithm.unwrap_or("SHA512").to_uppercase().as_str() {
+        "SHA256" => Sha256::digest(&data).to_vec(),
+        "SHA512" => Sha512::digest(&data).to_vec(),
+        other => {
+            return Err(GetError::ValidationError(format!("Unsupported hash algorithm '{}'", other)));
         }
+    };
+
+    if actual == expected {
+        Ok(())
+    } else {
+        Err(GetError::ValidationError(format!(
+            "Package hash mismatch for '{}'",
+            path.display()
+        )))
     }
 }
 
-pub fn install_choco_package(package: &str, config: &Config, logger: &Logger, m: &MultiProgress) -> Result<(), GetError> {
+/// Chocolatey packages from the configured feed
+pub struct ChocoManager;
+
+impl PackageManager for ChocoManager {
+    fn name(&self) -> &str {
+        "choco"
+    }
+
+    fn install(&self, package: &str, config: &Config, logger: &Logger, m: &MultiProgress) -> Result<InstallReport, GetError> {
+        install_choco_package(package, config, logger, m)
+    }
+
+    fn uninstall(&self, package: &str, _config: &Config, _logger: &Logger, _m: &MultiProgress) -> Result<UninstallReport, GetError> {
+        Err(GetError::InvalidInput(format!(
+            "Chocolatey packages are removed with 'choco uninstall {}'.",
+            split_package_spec(package).0
+        )))
+    }
+
+    fn search(&self, query: &str, config: &Config, _logger: &Logger, _m: &MultiProgress) -> Result<Vec<SearchHit>, GetError> {
+        Ok(ChocoFeed::from_config(config)?
+            .search(query, 30)?
+            .into_iter()
+            .map(|pkg| SearchHit {
+                source: "choco".to_string(),
+                description: Some(pkg.summary.unwrap_or(pkg.description)),
+                name: pkg.package_id,
+                version: Some(pkg.version),
+            })
+            .collect())
+    }
+}
+
+pub fn install_choco_package(package: &str, config: &Config, logger: &Logger, m: &MultiProgress) -> Result<InstallReport, GetError> {
     if crate::SHOULD_TERMINATE.load(Ordering::SeqCst) {
         return Err(GetError::InvalidInput("Operation terminated by user.".to_string()));
     }
 
-    logger.log(&format!("Installing Chocolatey package '{}'...", package));
-    
+    // Resolve against the feed first so we install an exact, known version
+    let (name, version) = split_package_spec(package);
+    let feed = ChocoFeed::from_config(config)?;
+    let listed = feed.find(name, version)?;
+
+    // choco installs the package we verified, from a folder holding only it;
+    // its dependencies still come from the feed
+    let work_dir = tempfile::tempdir()?;
+    let nupkg = feed.download(&listed, work_dir.path(), logger, m)?;
+    let manifest = ChocoManifest {
+        url: listed.url.clone(),
+        package_hash: listed.package_hash.clone(),
+        package_hash_algorithm: listed.package_hash_algorithm.clone(),
+        ..read_nupkg(&nupkg)?
+    };
+    // Feeds normalise versions, so `1.0` in the package is `1.0.0` listed
+    if !manifest.package_id.eq_ignore_ascii_case(&listed.package_id)
+        || Version::parse(&manifest.version) != Version::parse(&listed.version)
+    {
+        return Err(GetError::ValidationError(format!(
+            "The feed listed '{}' {} but its package is '{}' {}",
+            listed.package_id, listed.version, manifest.package_id, manifest.version
+        )));
+    }
+    logger.log(&format!(
+        "Installing Chocolatey package '{}' version {}...",
+        manifest.package_id, manifest.version
+    ));
+
+    let sources = format!("{};{}", work_dir.path().display(), feed.url());
     let status = Command::new("choco")
-        .args(&["install", package, "-y"])
+        .args(["install", &listed.package_id, "--version", &listed.version, "--source", &sources, "-y"])
         .status()?;
 
     if status.success() {
-        logger.info(&format!("Package '{}' installed successfully.", package));
-        Ok(())
+        PackageDb::open(&package_db_path(config))?.record_install(&InstalledPackage {
+            name: listed.package_id.clone(),
+            version: listed.version.clone(),
+            source: "choco".to_string(),
+            installed_at: SystemTime::now(),
+            manifest: serde_json::to_string(&manifest)?,
+            location: None,
+            files: Vec::new(),
+            shims: Vec::new(),
+        })?;
+        Ok(InstallReport {
+            source: "choco".to_string(),
+            name: listed.package_id,
+            version: Some(listed.version),
+            location: None,
+        })
     } else {
         Err(GetError::CommandError(format!(
             "Failed to install Chocolatey package '{}'.",
@@ -144,3 +443,170 @@ pub fn install_choco_package(package: &str, config: &Config, logger: &Logger, m:
         )))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::thread;
+
+    use get::logging::LogLevel;
+    use indicatif::ProgressDrawTarget;
+
+    const NUSPEC: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<package xmlns="http://schemas.microsoft.com/packaging/2015/06/nuspec.xsd">
+  <metadata>
+    <id>tool</id>
+    <version>1.1</version>
+    <title>Tool</title>
+    <authors>Someone</authors>
+    <projectUrl>https://example.com/tool</projectUrl>
+    <tags>cli  tool</tags>
+    <description>
+      A tool.
+    </description>
+    <dependencies>
+      <group targetFramework=".NETFramework4.0">
+        <dependency id="runtime" version="[2.0,3.0)" />
+      </group>
+      <dependency id="helper" />
+    </dependencies>
+  </metadata>
+</package>"#;
+
+    /// A feed page holding `entries`, linking to `next`
+    fn feed(entries: &[(&str, &str, &str)], next: Option<&str>) -> String {
+        let entries: String = entries
+            .iter()
+            .map(|(version, url, hash)| {
+                format!(
+                    r#"<entry><title type="text">tool</title><content type="application/zip" src="{url}"/>
+                    <m:properties><d:Version>{version}</d:Version><d:Description>A tool.</d:Description>
+                    <d:Dependencies>runtime:[2.0, 3.0):net40|helper::</d:Dependencies><d:Tags> cli tool </d:Tags>
+                    <d:PackageHash>{hash}</d:PackageHash><d:PackageHashAlgorithm>SHA512</d:PackageHashAlgorithm>
+                    </m:properties></entry>"#
+                )
+            })
+            .collect();
+        let next = next.map(|href| format!(r#"<link rel="next" href="{}"/>"#, href)).unwrap_or_default();
+        format!(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom" xmlns:d="http://schemas.microsoft.com/ado/2007/08/dataservices"
+      xmlns:m="http://schemas.microsoft.com/ado/2007/08/dataservices/metadata">
+  <title type="text">Packages</title><link rel="self" href="Packages"/>{entries}{next}
+</feed>"#
+        )
+    }
+
+    fn nupkg() -> Vec<u8> {
+        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        zip.start_file("tool.nuspec", zip::write::SimpleFileOptions::default()).unwrap();
+        zip.write_all(NUSPEC.as_bytes()).unwrap();
+        zip.start_file("tools/chocolateyInstall.ps1", zip::write::SimpleFileOptions::default()).unwrap();
+        zip.finish().unwrap().into_inner()
+    }
+
+    fn sha512(data: &[u8]) -> String {
+        base64::engine::general_purpose::STANDARD.encode(Sha512::digest(data))
+    }
+
+    /// A stand-in feed with two pages of `tool` versions and their packages
+    fn serve_feed() -> String {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", server.server_addr().to_ip().unwrap());
+        let package = nupkg();
+        let page1 = feed(
+            &[("1.0.0", &format!("{}/package/tool/1.0.0", url), &sha512(b"old"))],
+            // The link is XML, so its `&` is escaped
+            Some(&format!("{}/FindPackagesById()?id='tool'&amp;$skiptoken='tool','1.0.0'", url)),
+        );
+        let page2 = feed(
+            &[
+                ("1.1.0", &format!("{}/package/tool/1.1.0", url), &sha512(&package)),
+                ("1.2.0", &format!("{}/package/tool/1.2.0", url), &sha512(b"tampered")),
+            ],
+            None,
+        );
+        thread::spawn(move || {
+            for request in server.incoming_requests() {
+                let body = match request.url() {
+                    u if u.contains("skiptoken") => page2.clone().into_bytes(),
+                    u if u.starts_with("/FindPackagesById()") => page1.clone().into_bytes(),
+                    u if u.starts_with("/package/tool/") => package.clone(),
+                    _ => {
+                        let _ = request.respond(tiny_http::Response::from_data(Vec::new()).with_status_code(404));
+                        continue;
+                    }
+                };
+                let _ = request.respond(tiny_http::Response::from_data(body));
+            }
+        });
+        url
+    }
+
+    fn quiet() -> (Logger, MultiProgress) {
+        (Logger::new(LogLevel::Minimal), MultiProgress::with_draw_target(ProgressDrawTarget::hidden()))
+    }
+
+    #[test]
+    fn reads_feed_entries_and_the_next_page() {
+        let (packages, next) = parse_feed(&feed(&[("1.0.0", "https://feed/package/tool/1.0.0", "aGFzaA==")], Some("page2"))).unwrap();
+        assert_eq!(next.as_deref(), Some("page2"));
+        let package = &packages[0];
+        // The id is only in the entry title
+        assert_eq!((package.package_id.as_str(), package.version.as_str()), ("tool", "1.0.0"));
+        assert_eq!(package.url, "https://feed/package/tool/1.0.0");
+        assert_eq!(package.tags, ["cli", "tool"]);
+        assert_eq!(package.package_hash.as_deref(), Some("aGFzaA=="));
+        let dependencies: Vec<_> = package.dependencies.iter().map(|d| (d.id.as_str(), d.version.as_deref())).collect();
+        assert_eq!(dependencies, [("runtime", Some("[2.0, 3.0)")), ("helper", None)]);
+
+        assert_eq!(parse_feed(&feed(&[], None)).unwrap().1, None);
+        assert!(parse_feed("<feed>").is_err());
+    }
+
+    #[test]
+    fn reads_nuspec_metadata_and_every_dependency() {
+        let manifest = parse_nuspec(NUSPEC).unwrap();
+        assert_eq!((manifest.package_id.as_str(), manifest.version.as_str()), ("tool", "1.1"));
+        assert_eq!(manifest.description, "A tool.");
+        assert_eq!(manifest.project_url.as_deref(), Some("https://example.com/tool"));
+        assert_eq!(manifest.tags, ["cli", "tool"]);
+        let dependencies: Vec<_> = manifest.dependencies.iter().map(|d| (d.id.as_str(), d.version.as_deref())).collect();
+        assert_eq!(dependencies, [("runtime", Some("[2.0,3.0)")), ("helper", None)]);
+
+        assert!(parse_nuspec("<package><metadata><id>tool</id></metadata></package>").is_err());
+    }
+
+    #[test]
+    fn translates_nuget_version_ranges() {
+        let allows = |range: &str, version: &str| nuget_requirement(range).matches(&Version::parse(version));
+        // A bare version is a minimum
+        assert!(allows("1.2", "1.2") && allows("1.2", "5.0") && !allows("1.2", "1.1"));
+        assert!(allows("[1.0,2.0)", "1.0") && allows("[1.0,2.0)", "1.9") && !allows("[1.0,2.0)", "2.0"));
+        assert!(!allows("(1.0,2.0]", "1.0") && allows("(1.0,2.0]", "2.0"));
+        assert!(allows("[1.5]", "1.5") && !allows("[1.5]", "1.6"));
+        assert!(allows("(,3.0)", "0.1") && !allows("(,3.0)", "3.0"));
+        assert!(allows("[14.0,)", "99") && !allows("[14.0,)", "13.9"));
+        assert!(nuget_requirement("").is_any());
+    }
+
+    #[test]
+    fn finds_and_verifies_packages_on_a_paged_feed() {
+        let config = Config { choco_repo_url: Some(serve_feed()), ..Default::default() };
+        let feed = ChocoFeed::from_config(&config).unwrap();
+        let (logger, m) = quiet();
+        let dir = tempfile::tempdir().unwrap();
+
+        assert_eq!(feed.versions("tool").unwrap().len(), 3);
+        assert_eq!(feed.find("tool", Some("1.1.0")).unwrap().version, "1.1.0");
+        assert_eq!(feed.find("tool", None).unwrap().version, "1.2.0");
+
+        let nupkg = feed.download(&feed.find("tool", Some("1.1.0")).unwrap(), dir.path(), &logger, &m).unwrap();
+        assert_eq!(nupkg, dir.path().join("tool.1.1.0.nupkg"));
+        assert_eq!(read_nupkg(&nupkg).unwrap().dependencies.len(), 2);
+
+        let tampered = feed.find("tool", Some("1.2.0")).unwrap();
+        assert!(matches!(feed.download(&tampered, dir.path(), &logger, &m), Err(GetError::ValidationError(_))));
+    }
+}
@@ -1,34 +1,69 @@
 //! Configuration management for the package manager
 
+use std::collections::BTreeMap;
 use std::env;
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
 use dirs::home_dir;
 use serde::{Deserialize, Serialize};
 use crate::error::GetError;
 
 /// Application configuration
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct Config {
     pub default_package_manager: Option<String>,
     pub default_download_dir: Option<String>,
     pub log_verbosity: Option<String>,
     pub github_token: Option<String>,
+    /// GitHub API base URL, for GitHub Enterprise or a local stand-in
+    #[serde(default)]
+    pub github_api_url: Option<String>,
+    /// Release asset overrides by repository, e.g. `"BurntSushi/ripgrep" = "x86_64-unknown-linux-musl"`;
+    /// the value is a regular expression matched against asset names
+    #[serde(default)]
+    pub github_assets: BTreeMap<String, String>,
     pub choco_repo_url: Option<String>,
     pub choco_repo_path: Option<String>,
+    pub locale: Option<String>,
+    pub winget_source_url: Option<String>,
+    pub scoop_buckets: Option<Vec<ScoopBucket>>,
     pub repositories: Vec<Repository>,
+    /// Priorities of the built-in backends (`choco`, `scoop`, `winget`); higher is tried first
+    #[serde(default)]
+    pub priorities: BTreeMap<String, i32>,
+    /// Per-package source overrides, e.g. `git = "winget"` or `tool = "my-repo"`
+    #[serde(default)]
+    pub pins: BTreeMap<String, String>,
+    /// Version requirements `get upgrade` keeps packages within, e.g.
+    /// `node = "^20"`; a bare version holds a package at that version
+    #[serde(default)]
+    pub holds: BTreeMap<String, String>,
+    /// Where apps, command links and the package database live instead of
+    /// the home directory
+    #[serde(default)]
+    pub home: Option<String>,
 }
 
 /// Repository configuration
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Repository {
     pub name: String,
     pub url: String,
     pub package_format: PackageFormat,
     pub authentication: Option<RepositoryAuth>,
+    /// Higher is tried first; defaults to `source::DEFAULT_REPOSITORY_PRIORITY`
+    #[serde(default)]
+    pub priority: Option<i32>,
+}
+
+/// A Scoop bucket (a git repository of app manifests)
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ScoopBucket {
+    pub name: String,
+    pub url: String,
 }
 
 /// Supported package formats
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
 pub enum PackageFormat {
     Json,
     Yaml,
@@ -37,7 +72,7 @@ pub enum PackageFormat {
 }
 
 /// Repository authentication methods
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub enum RepositoryAuth {
     Basic { username: String, password: String },
     Token { token: String },
@@ -47,15 +82,7 @@ pub enum RepositoryAuth {
 impl Config {
     /// Load configuration from file and environment variables
     pub fn load() -> Self {
-        let mut config = Config {
-            default_package_manager: None,
-            default_download_dir: None,
-            log_verbosity: None,
-            github_token: None,
-            choco_repo_url: None,
-            choco_repo_path: None,
-            repositories: Vec::new(),
-        };
+        let mut config = Config::default();
 
         // Load from config file if exists
         if let Some(home) = home_dir() {
@@ -70,23 +97,43 @@ impl Config {
         }
 
         // Override with environment variables
-        if let Ok(manager) = env::var("GET_PREFERRED_MANAGER") {
-            config.default_package_manager = Some(manager);
+        config.apply_env(|name| env::var(name).ok());
+        config
+    }
+
+    /// Apply the `GET_*` overrides, reading variables through `var`
+    fn apply_env(&mut self, var: impl Fn(&str) -> Option<String>) {
+        if let Some(manager) = var("GET_PREFERRED_MANAGER") {
+            self.default_package_manager = Some(manager);
         }
 
-        if let Ok(download_path) = env::var("GET_DOWNLOAD_PATH") {
-            config.default_download_dir = Some(download_path);
+        if let Some(download_path) = var("GET_DOWNLOAD_PATH") {
+            self.default_download_dir = Some(download_path);
         }
 
-        if let Ok(log_level) = env::var("GET_LOG_VERBOSITY") {
-            config.log_verbosity = Some(log_level);
+        if let Some(log_level) = var("GET_LOG_VERBOSITY") {
+            self.log_verbosity = Some(log_level);
         }
 
-        if let Ok(token) = env::var("GET_GITHUB_TOKEN") {
-            config.github_token = Some(token);
+        if let Some(token) = var("GET_GITHUB_TOKEN") {
+            self.github_token = Some(token);
         }
 
-        config
+        if let Some(url) = var("GET_GITHUB_API") {
+            self.github_api_url = Some(url);
+        }
+
+        if let Some(locale) = var("GET_LOCALE") {
+            self.locale = Some(locale);
+        }
+
+        if let Some(url) = var("GET_WINGET_SOURCE") {
+            self.winget_source_url = Some(url);
+        }
+
+        if let Some(home) = var("GET_HOME") {
+            self.home = Some(home);
+        }
     }
 
     /// Get the preferred package manager
@@ -106,9 +153,78 @@ impl Config {
             })
     }
 
+    /// Get the user's locale as a BCP 47 tag (e.g. `en-US`)
+    ///
+    /// Falls back to the POSIX locale variables, then to `en-US`.
+    pub fn get_locale(&self) -> String {
+        if let Some(locale) = &self.locale {
+            return locale.clone();
+        }
+
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|var| env::var(var).ok())
+            .map(|value| value.split('.').next().unwrap_or("").replace('_', "-"))
+            .find(|tag| !tag.is_empty() && tag != "C" && tag != "POSIX")
+            .unwrap_or_else(|| "en-US".to_string())
+    }
+
+    /// Get the Winget source URL (or local directory)
+    pub fn get_winget_source_url(&self) -> &str {
+        self.winget_source_url
+            .as_deref()
+            .unwrap_or(crate::repository::WINGET_SOURCE_URL)
+    }
+
+    /// Get the configured Scoop buckets, defaulting to the main bucket
+    pub fn get_scoop_buckets(&self) -> Vec<ScoopBucket> {
+        self.scoop_buckets.clone().unwrap_or_else(|| {
+            vec![ScoopBucket {
+                name: "main".to_string(),
+                url: crate::repository::SCOOP_MAIN_REPO_URL.to_string(),
+            }]
+        })
+    }
+
+    /// Get the GitHub API base URL
+    pub fn get_github_api_url(&self) -> &str {
+        self.github_api_url
+            .as_deref()
+            .unwrap_or("https://api.github.com")
+            .trim_end_matches('/')
+    }
+
+    /// Get the asset override for a GitHub repository (`owner/repo`)
+    pub fn get_github_asset(&self, repository: &str) -> Option<&str> {
+        self.github_assets
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(repository))
+            .map(|(_, pattern)| pattern.as_str())
+    }
+
+    /// Get the directory `get` keeps its state under: `GET_HOME`, or the
+    /// user's home directory
+    pub fn get_home(&self) -> Option<PathBuf> {
+        self.home.as_ref().map(PathBuf::from).or_else(home_dir)
+    }
+
+    /// Get the directory portable apps are installed into
+    pub fn get_apps_dir(&self) -> PathBuf {
+        self.get_home()
+            .map(|h| h.join(".get").join("apps"))
+            .unwrap_or_else(|| PathBuf::from("apps"))
+    }
+
+    /// Get the directory installed commands are linked into
+    pub fn get_bin_dir(&self) -> PathBuf {
+        self.get_home()
+            .map(|h| h.join(".get").join("bin"))
+            .unwrap_or_else(|| PathBuf::from("bin"))
+    }
+
     /// Get the repositories directory
     pub fn get_repos_dir(&self) -> PathBuf {
-        home_dir()
+        self.get_home()
             .map(|h| h.join(".get_repos"))
             .unwrap_or_else(|| PathBuf::from("."))
     }
@@ -118,6 +234,22 @@ impl Config {
         &self.repositories
     }
 
+    /// Get the source pinned for `package`, if any
+    pub fn get_pin(&self, package: &str) -> Option<&str> {
+        self.pins
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(package))
+            .map(|(_, source)| source.as_str())
+    }
+
+    /// Get the version requirement `package` is held to, if any
+    pub fn get_hold(&self, package: &str) -> Option<&str> {
+        self.holds
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(package))
+            .map(|(_, requirement)| requirement.as_str())
+    }
+
     /// Save the configuration to file
     pub fn save(&self) -> Result<(), GetError> {
         if let Some(home) = home_dir() {
@@ -132,3 +264,37 @@ impl Config {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn get_home_holds_apps_links_and_the_package_database() {
+        let mut config = Config::default();
+        config.apply_env(|name| (name == "GET_HOME").then(|| "/srv/get".to_string()));
+
+        assert_eq!(config.get_apps_dir(), Path::new("/srv/get/.get/apps"));
+        assert_eq!(config.get_bin_dir(), Path::new("/srv/get/.get/bin"));
+        assert_eq!(config.get_repos_dir(), Path::new("/srv/get/.get_repos"));
+    }
+
+    #[test]
+    fn environment_overrides_the_config_file() {
+        let mut config: Config = toml::from_str(
+            "repositories = []\ndefault_download_dir = \"/from/file\"\nlocale = \"de-DE\"",
+        )
+        .unwrap();
+        config.apply_env(|name| match name {
+            "GET_DOWNLOAD_PATH" => Some("/from/env".to_string()),
+            "GET_PREFERRED_MANAGER" => Some("scoop".to_string()),
+            _ => None,
+        });
+
+        assert_eq!(config.get_download_dir(), Path::new("/from/env"));
+        assert_eq!(config.get_preferred_manager(), Some("scoop"));
+        assert_eq!(config.get_locale(), "de-DE");
+        assert_eq!(config.home, None);
+    }
+}
@@ -0,0 +1,514 @@
+//! Language ecosystem package managers
+//!
+//! Installs command-line tools with `cargo install`, `pipx install`,
+//! `npm install -g` and `go install`. These are only used when asked for with
+//! a qualifier (`cargo:ripgrep`), a pin, or a `[priorities]` entry, since
+//! their package names overlap with everything else.
+//!
+//! Installs are recorded in the package database like any other, so the
+//! installed-package commands see them.
+
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::time::{Duration, SystemTime};
+
+use indicatif::MultiProgress;
+use reqwest::blocking::Client;
+use reqwest::header::USER_AGENT;
+use serde_json::Value;
+
+use crate::config::Config;
+use crate::error::GetError;
+use crate::logging::Logger;
+use crate::native::{find_on_path, NativePackage};
+use crate::package_db::{package_db_path, InstalledPackage, PackageDb};
+use crate::package_manager::{InstallReport, PackageManager, SearchHit, UninstallReport};
+use crate::version::{split_package_spec, VersionReq};
+
+/// A supported language ecosystem
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ecosystem {
+    Cargo,
+    Pipx,
+    Npm,
+    Go,
+}
+
+impl Ecosystem {
+    pub const ALL: [Ecosystem; 4] = [Ecosystem::Cargo, Ecosystem::Pipx, Ecosystem::Npm, Ecosystem::Go];
+
+    /// The name used in `cargo:` qualifiers and config keys
+    pub fn name(&self) -> &'static str {
+        match self {
+            Ecosystem::Cargo => "cargo",
+            Ecosystem::Pipx => "pipx",
+            Ecosystem::Npm => "npm",
+            Ecosystem::Go => "go",
+        }
+    }
+
+    /// An ecosystem by name; `pip` means pipx, which keeps tools isolated
+    pub fn from_name(name: &str) -> Option<Ecosystem> {
+        match name.to_lowercase().as_str() {
+            "pip" => Some(Ecosystem::Pipx),
+            other => Ecosystem::ALL.iter().copied().find(|e| e.name() == other),
+        }
+    }
+
+    fn program(&self) -> &'static str {
+        match self {
+            Ecosystem::Npm if cfg!(target_os = "windows") => "npm.cmd",
+            other => other.name(),
+        }
+    }
+
+    /// Whether the ecosystem's tool is on `PATH`
+    pub fn is_available(&self) -> bool {
+        find_on_path(self.program()).is_some()
+    }
+
+    /// Whether packages can be searched for
+    pub fn can_search(&self) -> bool {
+        matches!(self, Ecosystem::Cargo | Ecosystem::Npm)
+    }
+}
+
+/// A language ecosystem package manager
+pub struct EcosystemManager {
+    ecosystem: Ecosystem,
+}
+
+impl EcosystemManager {
+    pub fn new(ecosystem: Ecosystem) -> Self {
+        EcosystemManager { ecosystem }
+    }
+
+    pub fn ecosystem(&self) -> Ecosystem {
+        self.ecosystem
+    }
+
+    fn command(&self) -> Result<Command, GetError> {
+        if !self.ecosystem.is_available() {
+            return Err(GetError::MissingDependency(format!(
+                "'{}' is not installed or not on PATH",
+                self.ecosystem.program()
+            )));
+        }
+        Ok(Command::new(self.ecosystem.program()))
+    }
+
+    fn query(&self, args: &[&str]) -> Result<String, GetError> {
+        let output = self.command()?.args(args).stdin(Stdio::null()).output()?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(GetError::CommandError(format!(
+                "'{} {}' failed: {}",
+                self.ecosystem.name(),
+                args.join(" "),
+                stderr.lines().last().unwrap_or("no output")
+            )));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    fn run(&self, args: &[&str], what: &str) -> Result<(), GetError> {
+        let status = self.command()?.args(args).stdin(Stdio::null()).status()?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(GetError::CommandError(format!("{} {} failed", self.ecosystem.name(), what)))
+        }
+    }
+
+    /// The package argument for `install`, with the version requirement in
+    /// the ecosystem's own syntax
+    fn install_target(&self, name: &str, requirement: Option<&VersionReq>) -> Result<Vec<String>, GetError> {
+        let requirement = match requirement.filter(|r| !r.is_any()) {
+            Some(requirement) => requirement,
+            None => {
+                return Ok(match self.ecosystem {
+                    Ecosystem::Go => vec![format!("{}@latest", name)],
+                    _ => vec![name.to_string()],
+                })
+            }
+        };
+        let unsupported = || {
+            GetError::InvalidInput(format!(
+                "{} can't install '{}' at {}",
+                self.ecosystem.name(),
+                name,
+                requirement
+            ))
+        };
+
+        match self.ecosystem {
+            // Cargo takes semver requirements as they are
+            Ecosystem::Cargo => Ok(vec![name.to_string(), "--version".to_string(), requirement.to_string()]),
+            // npm separates comparators with spaces
+            Ecosystem::Npm => Ok(vec![format!("{}@{}", name, requirement.to_string().replace(',', " "))]),
+            Ecosystem::Pipx => match requirement.exact_version() {
+                Some(version) => Ok(vec![format!("{}=={}", name, version)]),
+                None if !requirement.to_string().contains(['^', '~', '*']) => {
+                    Ok(vec![format!("{}{}", name, requirement)])
+                }
+                None => Err(unsupported()),
+            },
+            Ecosystem::Go => match requirement.exact_version() {
+                Some(version) if version.as_str().starts_with('v') || version.as_str() == "latest" => {
+                    Ok(vec![format!("{}@{}", name, version)])
+                }
+                Some(version) => Ok(vec![format!("{}@v{}", name, version)]),
+                None => Err(unsupported()),
+            },
+        }
+    }
+
+    /// Search the ecosystem's registry, where it has a search
+    pub fn search_packages(&self, query: &str) -> Result<Vec<NativePackage>, GetError> {
+        match self.ecosystem {
+            Ecosystem::Cargo => Ok(parse_cargo_search(&self.query(&["search", query, "--limit", "20"])?)),
+            Ecosystem::Npm => parse_npm_search(&self.query(&["search", "--json", query])?),
+            Ecosystem::Pipx | Ecosystem::Go => Err(GetError::InvalidInput(format!(
+                "{} has no package search",
+                self.ecosystem.name()
+            ))),
+        }
+    }
+
+    /// Tools installed through this ecosystem, by package name
+    pub fn installed_packages(&self) -> Result<Vec<NativePackage>, GetError> {
+        match self.ecosystem {
+            Ecosystem::Cargo => Ok(parse_cargo_list(&self.query(&["install", "--list"])?)),
+            Ecosystem::Pipx => parse_pipx_list(&self.query(&["list", "--json"])?),
+            Ecosystem::Npm => parse_npm_list(&self.query(&["ls", "-g", "--depth=0", "--json"])?),
+            // Go has no record of installed tools beyond the binaries
+            Ecosystem::Go => Ok(Vec::new()),
+        }
+    }
+
+    /// The installed version of `name`
+    pub fn installed_version(&self, name: &str) -> Result<Option<String>, GetError> {
+        if self.ecosystem == Ecosystem::Go {
+            return self.go_binary_version(name);
+        }
+        Ok(self
+            .installed_packages()?
+            .into_iter()
+            .find(|p| p.name == name)
+            .map(|p| p.version))
+    }
+
+    /// The newest published version of `name`
+    pub fn available_version(&self, name: &str) -> Result<Option<String>, GetError> {
+        match self.ecosystem {
+            Ecosystem::Cargo => Ok(parse_cargo_search(&self.query(&["search", name, "--limit", "1"])?)
+                .into_iter()
+                .find(|p| p.name == name)
+                .map(|p| p.version)),
+            Ecosystem::Npm => match self.query(&["view", name, "version"]) {
+                Ok(output) => Ok(Some(output.trim().to_string()).filter(|v| !v.is_empty())),
+                Err(GetError::CommandError(_)) => Ok(None),
+                Err(e) => Err(e),
+            },
+            Ecosystem::Pipx => pypi_version(name),
+            // `go install` resolves `@latest` itself
+            Ecosystem::Go => Ok(Some("latest".to_string())),
+        }
+    }
+
+    /// Where `go install` puts binaries
+    fn go_bin_dir(&self) -> Result<PathBuf, GetError> {
+        let gobin = self.query(&["env", "GOBIN"])?;
+        if !gobin.trim().is_empty() {
+            return Ok(PathBuf::from(gobin.trim()));
+        }
+        let gopath = self.query(&["env", "GOPATH"])?;
+        let first = gopath.trim().split(if cfg!(target_os = "windows") { ';' } else { ':' }).next().unwrap_or("");
+        Ok(PathBuf::from(first).join("bin"))
+    }
+
+    fn go_binary(&self, package: &str) -> Result<PathBuf, GetError> {
+        let name = go_binary_name(package);
+        let file = if cfg!(target_os = "windows") { format!("{}.exe", name) } else { name.to_string() };
+        Ok(self.go_bin_dir()?.join(file))
+    }
+
+    /// The module version a Go binary was built from
+    fn go_binary_version(&self, package: &str) -> Result<Option<String>, GetError> {
+        let binary = self.go_binary(package)?;
+        if !binary.exists() {
+            return Ok(None);
+        }
+        let output = self.query(&["version", "-m", &binary.to_string_lossy()])?;
+        Ok(output.lines().find_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields.as_slice() {
+                ["mod", _, version, ..] => Some(version.to_string()),
+                _ => None,
+            }
+        }))
+    }
+
+    /// Record an install, returning the version that was installed
+    fn record(&self, name: &str, config: &Config) -> Result<Option<String>, GetError> {
+        let version = self.installed_version(name)?;
+        PackageDb::open(&package_db_path(config))?.record_install(&InstalledPackage {
+            name: name.to_string(),
+            version: version.clone().unwrap_or_default(),
+            source: self.ecosystem.name().to_string(),
+            installed_at: SystemTime::now(),
+            manifest: serde_json::json!({ "manager": self.ecosystem.name(), "package": name }).to_string(),
+            location: None,
+            files: Vec::new(),
+            shims: Vec::new(),
+        })?;
+        Ok(version)
+    }
+}
+
+impl PackageManager for EcosystemManager {
+    fn name(&self) -> &str {
+        self.ecosystem.name()
+    }
+
+    fn install(&self, package: &str, config: &Config, logger: &Logger, _m: &MultiProgress) -> Result<InstallReport, GetError> {
+        let (name, version) = split_package_spec(package);
+        let requirement = version.map(VersionReq::parse).transpose()?;
+        let target = self.install_target(name, requirement.as_ref())?;
+
+        logger.log(&format!("Installing '{}' with {}...", target.join(" "), self.ecosystem.name()));
+        let mut args: Vec<&str> = match self.ecosystem {
+            Ecosystem::Cargo | Ecosystem::Pipx | Ecosystem::Go => vec!["install"],
+            Ecosystem::Npm => vec!["install", "-g"],
+        };
+        args.extend(target.iter().map(String::as_str));
+        self.run(&args, "install")?;
+
+        Ok(InstallReport {
+            source: self.ecosystem.name().to_string(),
+            name: name.to_string(),
+            version: self.record(name, config)?,
+            location: None,
+        })
+    }
+
+    fn uninstall(&self, package: &str, config: &Config, logger: &Logger, _m: &MultiProgress) -> Result<UninstallReport, GetError> {
+        let (name, _) = split_package_spec(package);
+        let Some(installed) = self.installed_version(name)? else {
+            return Err(GetError::PackageNotFound(format!(
+                "'{}' is not installed with {}",
+                name,
+                self.ecosystem.name()
+            )));
+        };
+
+        logger.log(&format!("Removing '{}' with {}...", name, self.ecosystem.name()));
+        match self.ecosystem {
+            Ecosystem::Cargo | Ecosystem::Pipx => self.run(&["uninstall", name], "uninstall")?,
+            Ecosystem::Npm => self.run(&["uninstall", "-g", name], "uninstall")?,
+            // `go install` only drops a binary, so removing it is the uninstall
+            Ecosystem::Go => std::fs::remove_file(self.go_binary(name)?)?,
+        }
+
+        PackageDb::open(&package_db_path(config))?.remove_installed(name)?;
+        Ok(UninstallReport {
+            source: self.ecosystem.name().to_string(),
+            name: name.to_string(),
+            version: Some(installed),
+        })
+    }
+
+    fn search(&self, query: &str, _config: &Config, _logger: &Logger, _m: &MultiProgress) -> Result<Vec<SearchHit>, GetError> {
+        let results = self.search_packages(query)?;
+        Ok(results.into_iter().map(|package| package.into_hit(self.ecosystem.name())).collect())
+    }
+}
+
+/// The binary `go install` builds for a package path: its last element,
+/// skipping a `/vN` major version suffix
+pub fn go_binary_name(package: &str) -> &str {
+    let path = split_package_spec(package).0.trim_end_matches('/');
+    let mut elements = path.rsplit('/');
+    let last = elements.next().unwrap_or(path);
+    let is_major = last.len() > 1 && last.starts_with('v') && last[1..].chars().all(|c| c.is_ascii_digit());
+    match elements.next() {
+        Some(parent) if is_major => parent,
+        _ => last,
+    }
+}
+
+/// The latest version of a package on PyPI
+fn pypi_version(name: &str) -> Result<Option<String>, GetError> {
+    let client = Client::builder().timeout(Duration::from_secs(30)).build()?;
+    let response = client
+        .get(format!("https://pypi.org/pypi/{}/json", name))
+        .header(USER_AGENT, "get-package-manager/1.0")
+        .send()?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    let body: Value = response.error_for_status()?.json()?;
+    Ok(body["info"]["version"].as_str().map(str::to_string))
+}
+
+/// `cargo search`: `name = "version"    # description`
+pub fn parse_cargo_search(output: &str) -> Vec<NativePackage> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (name, rest) = line.split_once(" = \"")?;
+            let (version, rest) = rest.split_once('"')?;
+            Some(NativePackage {
+                name: name.trim().to_string(),
+                version: version.to_string(),
+                description: rest.trim().trim_start_matches('#').trim().to_string(),
+            })
+        })
+        .collect()
+}
+
+/// `cargo install --list`: `name v1.2.3:` followed by indented binaries
+pub fn parse_cargo_list(output: &str) -> Vec<NativePackage> {
+    output
+        .lines()
+        .filter(|line| !line.starts_with(char::is_whitespace))
+        .filter_map(|line| {
+            let mut parts = line.trim_end_matches(':').split_whitespace();
+            let name = parts.next()?;
+            let version = parts.next()?.trim_start_matches('v');
+            Some(NativePackage {
+                name: name.to_string(),
+                version: version.to_string(),
+                description: String::new(),
+            })
+        })
+        .collect()
+}
+
+/// `npm search --json`: an array of `{name, version, description}`
+pub fn parse_npm_search(output: &str) -> Result<Vec<NativePackage>, GetError> {
+    let results: Vec<Value> = serde_json::from_str(output)?;
+    Ok(results
+        .iter()
+        .filter_map(|result| {
+            Some(NativePackage {
+                name: result["name"].as_str()?.to_string(),
+                version: result["version"].as_str().unwrap_or_default().to_string(),
+                description: result["description"].as_str().unwrap_or_default().to_string(),
+            })
+        })
+        .collect())
+}
+
+/// `npm ls -g --json`: `{"dependencies": {name: {"version": ...}}}`
+pub fn parse_npm_list(output: &str) -> Result<Vec<NativePackage>, GetError> {
+    let tree: Value = serde_json::from_str(output)?;
+    Ok(tree["dependencies"]
+        .as_object()
+        .map(|dependencies| {
+            dependencies
+                .iter()
+                .map(|(name, details)| NativePackage {
+                    name: name.clone(),
+                    version: details["version"].as_str().unwrap_or_default().to_string(),
+                    description: String::new(),
+                })
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+/// `pipx list --json`: `{"venvs": {name: {"metadata": {"main_package": {"package_version": ...}}}}}`
+pub fn parse_pipx_list(output: &str) -> Result<Vec<NativePackage>, GetError> {
+    let list: Value = serde_json::from_str(output)?;
+    Ok(list["venvs"]
+        .as_object()
+        .map(|venvs| {
+            venvs
+                .iter()
+                .map(|(name, venv)| NativePackage {
+                    name: name.clone(),
+                    version: venv["metadata"]["main_package"]["package_version"]
+                        .as_str()
+                        .unwrap_or_default()
+                        .to_string(),
+                    description: String::new(),
+                })
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(name: &str, version: &str, description: &str) -> NativePackage {
+        NativePackage { name: name.to_string(), version: version.to_string(), description: description.to_string() }
+    }
+
+    fn target(ecosystem: Ecosystem, name: &str, requirement: Option<&str>) -> Result<Vec<String>, GetError> {
+        let requirement = requirement.map(|r| VersionReq::parse(r).unwrap());
+        EcosystemManager::new(ecosystem).install_target(name, requirement.as_ref())
+    }
+
+    #[test]
+    fn names_ecosystems() {
+        assert_eq!(Ecosystem::from_name("Cargo"), Some(Ecosystem::Cargo));
+        assert_eq!(Ecosystem::from_name("pip"), Some(Ecosystem::Pipx));
+        assert_eq!(Ecosystem::from_name("gem"), None);
+        assert!(Ecosystem::ALL.iter().all(|e| Ecosystem::from_name(e.name()) == Some(*e)));
+    }
+
+    #[test]
+    fn reads_cargo_output() {
+        let search = "ripgrep = \"14.1.0\"    # ripgrep is a line-oriented search tool\n\
+                      ripgrep_all = \"0.10.6\"    # rga: ripgrep, but also search in PDFs\n\
+                      ... and 1000 crates more (use --limit N to see more)\n";
+        assert_eq!(
+            parse_cargo_search(search),
+            [
+                package("ripgrep", "14.1.0", "ripgrep is a line-oriented search tool"),
+                package("ripgrep_all", "0.10.6", "rga: ripgrep, but also search in PDFs"),
+            ]
+        );
+        let list = "bat v0.24.0:\n    bat\nripgrep v14.1.0:\n    rg\n";
+        assert_eq!(parse_cargo_list(list), [package("bat", "0.24.0", ""), package("ripgrep", "14.1.0", "")]);
+    }
+
+    #[test]
+    fn reads_npm_and_pipx_output() {
+        let search = r#"[{"name": "prettier", "version": "3.3.3", "description": "Opinionated formatter"}, {"name": "bare"}]"#;
+        assert_eq!(
+            parse_npm_search(search).unwrap(),
+            [package("prettier", "3.3.3", "Opinionated formatter"), package("bare", "", "")]
+        );
+        let list = r#"{"name": "lib", "dependencies": {"npm": {"version": "10.8.2"}, "typescript": {"version": "5.5.4"}}}"#;
+        assert_eq!(parse_npm_list(list).unwrap(), [package("npm", "10.8.2", ""), package("typescript", "5.5.4", "")]);
+        assert!(parse_npm_list("{}").unwrap().is_empty());
+
+        let pipx = r#"{"venvs": {"black": {"metadata": {"main_package": {"package_version": "24.8.0"}}}}}"#;
+        assert_eq!(parse_pipx_list(pipx).unwrap(), [package("black", "24.8.0", "")]);
+        assert!(parse_pipx_list("not json").is_err());
+    }
+
+    #[test]
+    fn names_go_binaries() {
+        assert_eq!(go_binary_name("golang.org/x/tools/gopls@latest"), "gopls");
+        assert_eq!(go_binary_name("github.com/owner/tool/v2"), "tool");
+        assert_eq!(go_binary_name("github.com/owner/v2tool/"), "v2tool");
+    }
+
+    #[test]
+    fn writes_requirements_in_each_ecosystem_syntax() {
+        assert_eq!(target(Ecosystem::Cargo, "bat", None).unwrap(), ["bat"]);
+        assert_eq!(target(Ecosystem::Go, "golang.org/x/tools/gopls", None).unwrap(), ["golang.org/x/tools/gopls@latest"]);
+        assert_eq!(target(Ecosystem::Cargo, "bat", Some("^0.24")).unwrap(), ["bat", "--version", "^0.24"]);
+        assert_eq!(target(Ecosystem::Npm, "prettier", Some(">=3,<4")).unwrap(), ["prettier@>=3 <4"]);
+        assert_eq!(target(Ecosystem::Pipx, "black", Some("=24.8.0")).unwrap(), ["black==24.8.0"]);
+        assert_eq!(target(Ecosystem::Pipx, "black", Some(">=24")).unwrap(), ["black>=24"]);
+        assert!(target(Ecosystem::Pipx, "black", Some("^24")).is_err());
+        assert_eq!(target(Ecosystem::Go, "example.com/tool", Some("=1.2.3")).unwrap(), ["example.com/tool@v1.2.3"]);
+        assert!(target(Ecosystem::Go, "example.com/tool", Some(">=1")).is_err());
+    }
+}
@@ -24,6 +24,7 @@ pub enum GetError {
     SerializationError(String),
     ValidationError(String),
     PackageNotFound(String),
+    DatabaseError(String),
 }
 
 impl fmt::Display for GetError {
@@ -40,7 +41,7 @@ impl fmt::Display for GetError {
             GetError::SerializationError(msg) => write!(f, "Serialization error: {}", msg),
             GetError::ValidationError(msg) => write!(f, "Validation error: {}", msg),
             GetError::PackageNotFound(msg) => write!(f, "Package not found: {}", msg),
-            _ => unreachable!(), // This ensures all variants are covered
+            GetError::DatabaseError(msg) => write!(f, "Database error: {}", msg),
         }
     }
 }
@@ -95,6 +96,12 @@ impl From<toml::ser::Error> for GetError {
     }
 }
 
+impl From<rusqlite::Error> for GetError {
+    fn from(err: rusqlite::Error) -> Self {
+        GetError::DatabaseError(err.to_string())
+    }
+}
+
 impl From<FromUtf8Error> for GetError {
     fn from(err: FromUtf8Error) -> Self {
         GetError::ParseError(err.to_string())
@@ -0,0 +1,647 @@
+//! GitHub Releases backend
+//!
+//! Installs tools that are only published as release assets
+//! (`get install gh:BurntSushi/ripgrep`). The release is looked up through
+//! the GitHub API (with the token `get auth` stores, to avoid rate limits),
+//! the asset for this OS, architecture and libc is picked by name, checked
+//! against the release's checksums file when there is one, and extracted into
+//! the app's version directory (see [`crate::apps`]). Its executables are
+//! then shimmed into the bin directory.
+//!
+//! The API base is `Config::get_github_api_url`, so a local stand-in can be
+//! used instead of api.github.com.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, SystemTime};
+
+use indicatif::MultiProgress;
+use regex::Regex;
+use reqwest::blocking::Client;
+use reqwest::header::{ACCEPT, AUTHORIZATION, USER_AGENT};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+
+use crate::apps::{find_executables, Apps};
+use crate::config::Config;
+use crate::error::GetError;
+use crate::logging::Logger;
+use crate::package_db::{package_db_path, InstalledPackage, PackageDb};
+use crate::package_manager::{ArchiveFormat, InstallReport, PackageManager, SearchHit, UninstallReport};
+use crate::utils::{download_file, sha256_file};
+use crate::version::{select_version, split_package_spec, VersionReq};
+
+/// A GitHub release
+#[derive(Debug, Clone, Deserialize)]
+pub struct Release {
+    pub tag_name: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub prerelease: bool,
+    #[serde(default)]
+    pub draft: bool,
+    pub assets: Vec<ReleaseAsset>,
+}
+
+impl Release {
+    /// The tag without a leading `v`
+    pub fn version(&self) -> &str {
+        self.tag_name
+            .strip_prefix('v')
+            .filter(|rest| rest.starts_with(|c: char| c.is_ascii_digit()))
+            .unwrap_or(&self.tag_name)
+    }
+}
+
+/// A file attached to a release
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReleaseAsset {
+    pub name: String,
+    pub browser_download_url: String,
+    #[serde(default)]
+    pub size: u64,
+}
+
+/// What a GitHub install put where, kept in the install record
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitHubInstall {
+    pub repository: String,
+    pub tag: String,
+    pub asset: String,
+    /// The app's `current` directory
+    pub path: PathBuf,
+    pub shims: Vec<PathBuf>,
+}
+
+/// The apps directory name of `owner/repo`
+pub fn app_name(repository: &str) -> String {
+    repository.replace('/', "-")
+}
+
+/// Split `owner/repo`
+pub fn parse_repository(spec: &str) -> Result<(&str, &str), GetError> {
+    match spec.split_once('/') {
+        Some((owner, repo)) if !owner.is_empty() && !repo.is_empty() && !repo.contains('/') => Ok((owner, repo)),
+        _ => Err(GetError::InvalidInput(format!(
+            "'{}' is not a GitHub repository; expected 'owner/repo'",
+            spec
+        ))),
+    }
+}
+
+/// A client for the GitHub releases API
+pub struct GitHubClient {
+    client: Client,
+    api_url: String,
+    token: Option<String>,
+}
+
+impl GitHubClient {
+    pub fn from_config(config: &Config) -> Result<Self, GetError> {
+        Ok(GitHubClient {
+            client: Client::builder().timeout(Duration::from_secs(30)).build()?,
+            api_url: config.get_github_api_url().to_string(),
+            token: config.github_token.clone(),
+        })
+    }
+
+    fn get<T: serde::de::DeserializeOwned>(&self, path: &str) -> Result<Option<T>, GetError> {
+        let mut request = self
+            .client
+            .get(format!("{}/{}", self.api_url, path))
+            .header(USER_AGENT, "get-package-manager/1.0")
+            .header(ACCEPT, "application/vnd.github+json");
+        if let Some(token) = &self.token {
+            request = request.header(AUTHORIZATION, format!("Bearer {}", token));
+        }
+
+        let response = request.send()?;
+        match response.status() {
+            status if status.is_success() => Ok(Some(response.json()?)),
+            StatusCode::NOT_FOUND => Ok(None),
+            StatusCode::FORBIDDEN | StatusCode::TOO_MANY_REQUESTS => Err(GetError::NetworkError(format!(
+                "GitHub API rate limit reached{}",
+                if self.token.is_some() { "" } else { "; run 'get auth' to raise it" }
+            ))),
+            status => Err(GetError::NetworkError(format!("GitHub API returned HTTP {} for '{}'", status, path))),
+        }
+    }
+
+    /// The release of `owner/repo` meeting `requirement`, or the latest one
+    pub fn release(&self, repository: &str, requirement: Option<&VersionReq>) -> Result<Release, GetError> {
+        let (owner, repo) = parse_repository(repository)?;
+        let not_found = || GetError::PackageNotFound(format!("No matching release of '{}' on GitHub", repository));
+
+        match requirement.filter(|r| !r.is_any()) {
+            None => self.get(&format!("repos/{}/{}/releases/latest", owner, repo))?.ok_or_else(not_found),
+            Some(requirement) => {
+                // Exact versions are usually tags, with or without a `v`
+                if let Some(version) = requirement.exact_version() {
+                    for tag in [format!("v{}", version), version.to_string()] {
+                        if let Some(release) = self.get(&format!("repos/{}/{}/releases/tags/{}", owner, repo, tag))? {
+                            return Ok(release);
+                        }
+                    }
+                }
+                let releases: Vec<Release> = self
+                    .get(&format!("repos/{}/{}/releases?per_page=100", owner, repo))?
+                    .unwrap_or_default();
+                let releases: Vec<Release> = releases.into_iter().filter(|r| !r.draft).collect();
+                select_version(&releases, Release::version, Some(&requirement.to_string()))
+                    .cloned()
+                    .ok_or_else(not_found)
+            }
+        }
+    }
+}
+
+/// Name fragments that identify an OS, architecture or libc in asset names
+fn os_keywords(os: &str) -> &'static [&'static str] {
+    match os {
+        "linux" => &["linux"],
+        "macos" => &["darwin", "macos", "apple", "osx"],
+        "windows" => &["windows", "win64", "win32", "pc-windows", "win"],
+        "freebsd" => &["freebsd"],
+        _ => &[],
+    }
+}
+
+fn arch_keywords(arch: &str) -> &'static [&'static str] {
+    match arch {
+        "x86_64" => &["x86_64", "x86-64", "amd64", "x64", "64bit"],
+        "aarch64" => &["aarch64", "arm64"],
+        "x86" => &["i686", "i386", "386", "x86", "32bit"],
+        "arm" => &["armv7", "armhf", "arm"],
+        _ => &[],
+    }
+}
+
+const OTHER_OSES: [&str; 4] = ["linux", "macos", "windows", "freebsd"];
+const OTHER_ARCHES: [&str; 4] = ["x86_64", "aarch64", "x86", "arm"];
+
+/// Whether this Linux system uses musl instead of glibc
+fn is_musl() -> bool {
+    Command::new("ldd")
+        .arg("--version")
+        .output()
+        .map(|o| {
+            let text = format!("{}{}", String::from_utf8_lossy(&o.stdout), String::from_utf8_lossy(&o.stderr));
+            text.to_lowercase().contains("musl")
+        })
+        .unwrap_or(false)
+}
+
+fn is_checksum_file(name: &str) -> bool {
+    let name = name.to_lowercase();
+    name.contains("checksum")
+        || name.contains("sha256sum")
+        || name.ends_with(".sha256")
+        || name.ends_with(".sha512")
+        || name.ends_with(".sig")
+        || name.ends_with(".asc")
+        || name.ends_with(".pem")
+        || name.ends_with(".sbom")
+        || name.ends_with(".json")
+}
+
+/// Whether `text` contains `keyword` as a separate word of an asset name
+fn has_keyword(text: &str, keyword: &str) -> bool {
+    text.match_indices(keyword).any(|(i, _)| {
+        let before = text[..i].chars().last();
+        let rest = &text[i + keyword.len()..];
+        let boundary = |c: Option<char>| c.is_none_or(|c| !c.is_ascii_alphanumeric());
+        // `x86` is a prefix of `x86_64`
+        if keyword == "x86" && (rest.starts_with("_64") || rest.starts_with("-64")) {
+            return false;
+        }
+        boundary(before) && (boundary(rest.chars().next()) || keyword.len() > 3)
+    })
+}
+
+/// Score an asset for this machine; `None` when it is for another platform
+fn score_asset(name: &str, os: &str, arch: &str, musl: bool) -> Option<i32> {
+    let lower = name.to_lowercase();
+    if is_checksum_file(&lower) {
+        return None;
+    }
+    let installable = ArchiveFormat::detect(Path::new(&lower)).is_some()
+        || (os == "windows" && lower.ends_with(".exe"))
+        || (os != "windows" && !lower.contains('.'));
+    if !installable {
+        return None;
+    }
+
+    let mut score = 0;
+    if os_keywords(os).iter().any(|k| has_keyword(&lower, k)) {
+        score += 10;
+    } else if OTHER_OSES.iter().any(|other| *other != os && os_keywords(other).iter().any(|k| has_keyword(&lower, k))) {
+        return None;
+    }
+    if arch_keywords(arch).iter().any(|k| has_keyword(&lower, k)) {
+        score += 5;
+    } else if OTHER_ARCHES
+        .iter()
+        .any(|other| *other != arch && arch_keywords(other).iter().any(|k| has_keyword(&lower, k)))
+    {
+        return None;
+    }
+    if os == "linux" {
+        match (lower.contains("musl"), musl) {
+            (true, true) => score += 3,
+            // Static musl builds run anywhere
+            (true, false) => score += 1,
+            (false, true) if lower.contains("gnu") => return None,
+            (false, _) => score += 2,
+        }
+    }
+    Some(score)
+}
+
+/// Pick the asset to install: the override pattern if configured, otherwise
+/// the best match for this OS, architecture and libc
+pub fn select_asset<'a>(release: &'a Release, override_pattern: Option<&str>) -> Result<&'a ReleaseAsset, GetError> {
+    if let Some(pattern) = override_pattern {
+        let regex = Regex::new(pattern)
+            .map_err(|e| GetError::ConfigError(format!("Invalid GitHub asset pattern '{}': {}", pattern, e)))?;
+        return release
+            .assets
+            .iter()
+            .find(|asset| regex.is_match(&asset.name))
+            .ok_or_else(|| GetError::PackageNotFound(format!("No asset of {} matches '{}'", release.tag_name, pattern)));
+    }
+
+    let musl = cfg!(target_os = "linux") && is_musl();
+    release
+        .assets
+        .iter()
+        .filter_map(|asset| score_asset(&asset.name, std::env::consts::OS, std::env::consts::ARCH, musl).map(|s| (s, asset)))
+        .max_by_key(|(score, _)| *score)
+        .map(|(_, asset)| asset)
+        .ok_or_else(|| {
+            GetError::PackageNotFound(format!(
+                "No asset of {} for {}-{}; set github_assets to choose one",
+                release.tag_name,
+                std::env::consts::OS,
+                std::env::consts::ARCH
+            ))
+        })
+}
+
+/// The published SHA-256 of `asset`, from a `checksums.txt`-style file or an
+/// `<asset>.sha256` file in the same release
+fn published_checksum(release: &Release, asset: &ReleaseAsset, dir: &Path, logger: &Logger, m: &MultiProgress) -> Result<Option<String>, GetError> {
+    let own = release.assets.iter().find(|a| {
+        let name = a.name.to_lowercase();
+        let asset_name = asset.name.to_lowercase();
+        name == format!("{}.sha256", asset_name) || name == format!("{}.sha256sum", asset_name)
+    });
+    let shared = release.assets.iter().find(|a| {
+        let name = a.name.to_lowercase();
+        (name.contains("checksums") || name.contains("sha256sums")) && !name.ends_with(".sig") && !name.ends_with(".asc")
+    });
+    let Some(checksums) = own.or(shared) else {
+        return Ok(None);
+    };
+
+    let path = download_file(&checksums.browser_download_url, dir, logger, m)?;
+    let text = fs::read_to_string(&path)?;
+    Ok(parse_checksums(&text, &asset.name, own.is_some()))
+}
+
+/// Find the hash of `file_name` in `sha256sum` output. A file for a single
+/// asset may hold just the hash.
+pub fn parse_checksums(text: &str, file_name: &str, single: bool) -> Option<String> {
+    let is_hash = |s: &str| s.len() == 64 && s.chars().all(|c| c.is_ascii_hexdigit());
+    for line in text.lines() {
+        let mut parts = line.split_whitespace();
+        let (Some(hash), name) = (parts.next(), parts.next()) else {
+            continue;
+        };
+        if !is_hash(hash) {
+            continue;
+        }
+        match name.map(|n| n.trim_start_matches('*').rsplit('/').next().unwrap_or(n)) {
+            Some(name) if name == file_name => return Some(hash.to_lowercase()),
+            None if single => return Some(hash.to_lowercase()),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Installs GitHub release assets
+pub struct GitHubManager;
+
+impl GitHubManager {
+    /// Download, verify, extract and link one release of `repository`
+    pub fn install_release(&self, repository: &str, release: &Release, config: &Config, logger: &Logger, m: &MultiProgress) -> Result<GitHubInstall, GetError> {
+        let asset = select_asset(release, config.get_github_asset(repository))?;
+        logger.log(&format!("Selected asset '{}' of {} {}.", asset.name, repository, release.tag_name));
+
+        let work_dir = tempfile::tempdir()?;
+        let downloaded = download_file(&asset.browser_download_url, work_dir.path(), logger, m)?;
+        match published_checksum(release, asset, work_dir.path(), logger, m)? {
+            Some(expected) => {
+                let actual = sha256_file(&downloaded)?;
+                if !actual.eq_ignore_ascii_case(&expected) {
+                    return Err(GetError::ValidationError(format!(
+                        "Checksum mismatch for '{}'. Expected: {}, Got: {}",
+                        asset.name, expected, actual
+                    )));
+                }
+                logger.log("Checksum verified against the release's checksums file.");
+            }
+            None => logger.warn(&format!("{} {} publishes no checksum for '{}'.", repository, release.tag_name, asset.name)),
+        }
+
+        // A bare binary is named after the repository
+        let (_, repo) = parse_repository(repository)?;
+        let file_name = if cfg!(target_os = "windows") { format!("{}.exe", repo) } else { repo.to_string() };
+        let apps = Apps::from_config(config);
+        let app = app_name(repository);
+        let version = release.version();
+        let dir = apps.unpack(&app, version, &downloaded, ArchiveFormat::detect(&downloaded), 0, &file_name)?;
+        apps.set_current(&app, version)?;
+        apps.remove_shims(&app)?;
+        let shims = apps.create_shims(&app, &find_executables(&dir))?;
+        if shims.is_empty() {
+            logger.warn(&format!("No executables found in '{}'.", dir.display()));
+        }
+        // Only the current version is kept
+        for old in apps.versions(&app).into_iter().filter(|v| v != version) {
+            fs::remove_dir_all(apps.version_dir(&app, &old))?;
+        }
+
+        Ok(GitHubInstall {
+            repository: repository.to_string(),
+            tag: release.tag_name.clone(),
+            asset: asset.name.clone(),
+            path: apps.current_dir(&app),
+            shims,
+        })
+    }
+}
+
+impl PackageManager for GitHubManager {
+    fn name(&self) -> &str {
+        "gh"
+    }
+
+    fn install(&self, package: &str, config: &Config, logger: &Logger, m: &MultiProgress) -> Result<InstallReport, GetError> {
+        let (repository, version) = split_package_spec(package);
+        let requirement = version.map(VersionReq::parse).transpose()?;
+        let release = GitHubClient::from_config(config)?.release(repository, requirement.as_ref())?;
+
+        let install = self.install_release(repository, &release, config, logger, m)?;
+        PackageDb::open(&package_db_path(config))?.record_install(&InstalledPackage {
+            name: repository.to_string(),
+            version: release.version().to_string(),
+            source: "github".to_string(),
+            installed_at: SystemTime::now(),
+            manifest: serde_json::to_string(&install)?,
+            location: Some(install.path.clone()),
+            files: Apps::from_config(config).files(&app_name(repository), release.version()),
+            shims: install.shims.clone(),
+        })?;
+        Ok(InstallReport {
+            source: "github".to_string(),
+            name: repository.to_string(),
+            version: Some(release.version().to_string()),
+            location: Some(install.path),
+        })
+    }
+
+    fn uninstall(&self, package: &str, config: &Config, _logger: &Logger, _m: &MultiProgress) -> Result<UninstallReport, GetError> {
+        let (repository, _) = split_package_spec(package);
+        let db = PackageDb::open(&package_db_path(config))?;
+        let record = db
+            .installed(repository)?
+            .filter(|record| record.source == "github")
+            .ok_or_else(|| GetError::PackageNotFound(format!("'{}' was not installed from GitHub", repository)))?;
+        Apps::from_config(config).remove(&app_name(repository))?;
+        db.remove_installed(repository)?;
+        Ok(UninstallReport {
+            source: record.source,
+            name: record.name,
+            version: Some(record.version),
+        })
+    }
+
+    fn search(&self, query: &str, config: &Config, _logger: &Logger, _m: &MultiProgress) -> Result<Vec<SearchHit>, GetError> {
+        #[derive(Deserialize)]
+        struct SearchResults {
+            items: Vec<SearchItem>,
+        }
+        #[derive(Deserialize)]
+        struct SearchItem {
+            full_name: String,
+            description: Option<String>,
+        }
+
+        let results: SearchResults = GitHubClient::from_config(config)?
+            .get(&format!("search/repositories?q={}&per_page=20", url::form_urlencoded::byte_serialize(query.as_bytes()).collect::<String>()))?
+            .ok_or_else(|| GetError::NetworkError("GitHub search is unavailable".to_string()))?;
+        Ok(results
+            .items
+            .into_iter()
+            .map(|item| SearchHit {
+                source: "gh".to_string(),
+                name: item.full_name,
+                version: None,
+                description: item.description,
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::io::Write;
+    use std::thread;
+
+    use indicatif::ProgressDrawTarget;
+    use serde_json::json;
+
+    use crate::logging::LogLevel;
+
+    const RIPGREP: [&str; 8] = [
+        "ripgrep-14.1.0-aarch64-unknown-linux-gnu.tar.gz",
+        "ripgrep-14.1.0-i686-pc-windows-msvc.zip",
+        "ripgrep-14.1.0-x86_64-apple-darwin.tar.gz",
+        "ripgrep-14.1.0-x86_64-pc-windows-msvc.zip",
+        "ripgrep-14.1.0-x86_64-unknown-linux-musl.tar.gz",
+        "ripgrep-14.1.0-x86_64-unknown-linux-musl.tar.gz.sha256",
+        "ripgrep_14.1.0-1_amd64.deb",
+        "ripgrep-14.1.0-x86_64-unknown-linux-gnu.tar.gz",
+    ];
+
+    fn release(tag: &str, assets: &[&str]) -> Release {
+        let assets = assets
+            .iter()
+            .map(|name| ReleaseAsset { name: name.to_string(), browser_download_url: format!("https://example.com/{}", name), size: 0 })
+            .collect();
+        Release { tag_name: tag.to_string(), name: None, prerelease: false, draft: false, assets }
+    }
+
+    fn best(os: &str, arch: &str, musl: bool) -> Option<&'static str> {
+        RIPGREP
+            .iter()
+            .filter_map(|name| score_asset(name, os, arch, musl).map(|score| (score, *name)))
+            .max_by_key(|(score, _)| *score)
+            .map(|(_, name)| name)
+    }
+
+    #[test]
+    fn picks_the_asset_for_each_platform() {
+        assert_eq!(best("linux", "x86_64", false), Some("ripgrep-14.1.0-x86_64-unknown-linux-gnu.tar.gz"));
+        assert_eq!(best("linux", "x86_64", true), Some("ripgrep-14.1.0-x86_64-unknown-linux-musl.tar.gz"));
+        assert_eq!(best("linux", "aarch64", false), Some("ripgrep-14.1.0-aarch64-unknown-linux-gnu.tar.gz"));
+        // A glibc build won't run on musl
+        assert_eq!(best("linux", "aarch64", true), None);
+        assert_eq!(best("macos", "x86_64", false), Some("ripgrep-14.1.0-x86_64-apple-darwin.tar.gz"));
+        assert_eq!(best("windows", "x86_64", false), Some("ripgrep-14.1.0-x86_64-pc-windows-msvc.zip"));
+        assert_eq!(best("windows", "x86", false), Some("ripgrep-14.1.0-i686-pc-windows-msvc.zip"));
+        assert_eq!(best("freebsd", "x86_64", false), None);
+
+        let release = release("14.1.0", &RIPGREP);
+        assert_eq!(select_asset(&release, Some(r"\.deb$")).unwrap().name, "ripgrep_14.1.0-1_amd64.deb");
+        assert!(matches!(select_asset(&release, Some("rpm$")), Err(GetError::PackageNotFound(_))));
+        assert!(matches!(select_asset(&release, Some("(")), Err(GetError::ConfigError(_))));
+    }
+
+    #[test]
+    fn reads_published_checksums() {
+        let hash = "ab".repeat(32);
+        let text = format!("{}  tool-linux.tar.gz\n{} *dist/tool-windows.zip\nnot a hash  tool-macos.tar.gz\n", hash, "CD".repeat(32));
+        assert_eq!(parse_checksums(&text, "tool-linux.tar.gz", false), Some(hash.clone()));
+        assert_eq!(parse_checksums(&text, "tool-windows.zip", false), Some("cd".repeat(32)));
+        assert_eq!(parse_checksums(&text, "tool-macos.tar.gz", false), None);
+        // A file for one asset may hold only the hash
+        assert_eq!(parse_checksums(&format!("{}\n", hash), "tool-linux.tar.gz", true), Some(hash.clone()));
+        assert_eq!(parse_checksums(&format!("{}\n", hash), "tool-linux.tar.gz", false), None);
+    }
+
+    #[test]
+    fn names_releases_and_repositories() {
+        assert_eq!(release("v1.2.0", &[]).version(), "1.2.0");
+        assert_eq!(release("1.2.0", &[]).version(), "1.2.0");
+        assert_eq!(release("vNext", &[]).version(), "vNext");
+        assert_eq!(parse_repository("owner/repo").unwrap(), ("owner", "repo"));
+        for spec in ["owner", "owner/", "/repo", "owner/repo/extra"] {
+            assert!(parse_repository(spec).is_err(), "{}", spec);
+        }
+        assert_eq!(app_name("owner/repo"), "owner-repo");
+    }
+
+    /// Serve `routes` by path until the test process exits
+    fn serve(routes: impl FnOnce(&str) -> HashMap<String, Vec<u8>>) -> String {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", server.server_addr().to_ip().unwrap());
+        let routes = routes(&url);
+        thread::spawn(move || {
+            for request in server.incoming_requests() {
+                let response = match routes.get(request.url()) {
+                    Some(body) => tiny_http::Response::from_data(body.clone()),
+                    None => tiny_http::Response::from_data(Vec::new()).with_status_code(404),
+                };
+                let _ = request.respond(response);
+            }
+        });
+        url
+    }
+
+    fn zip_with_tool(version: &str) -> Vec<u8> {
+        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        let options = zip::write::SimpleFileOptions::default().unix_permissions(0o755);
+        let program = if cfg!(target_os = "windows") { "tool.exe" } else { "tool" };
+        zip.start_file(program, options).unwrap();
+        zip.write_all(version.as_bytes()).unwrap();
+        zip.finish().unwrap().into_inner()
+    }
+
+    /// A stand-in GitHub API with releases 1.0.0 (tagged without a `v`) and
+    /// v1.2.0 of `owner/tool`; `checksum` is published for the 1.2.0 asset
+    fn api(checksum: Option<&'static str>) -> String {
+        serve(|url| {
+            let asset = format!("tool-{}-{}.zip", std::env::consts::OS, std::env::consts::ARCH);
+            let zip = zip_with_tool("1.2.0");
+            let checksum = checksum.map(str::to_string).unwrap_or_else(|| {
+                let dir = tempfile::tempdir().unwrap();
+                fs::write(dir.path().join("asset"), &zip).unwrap();
+                sha256_file(&dir.path().join("asset")).unwrap()
+            });
+            let release = |tag: &str| {
+                json!({
+                    "tag_name": tag,
+                    "assets": [
+                        { "name": asset, "browser_download_url": format!("{}/download/{}/{}", url, tag, asset) },
+                        { "name": "checksums.txt", "browser_download_url": format!("{}/download/{}/checksums.txt", url, tag) },
+                    ],
+                })
+            };
+            let mut routes = HashMap::new();
+            let mut route = |path: String, body: Vec<u8>| routes.insert(path, body);
+            route("/repos/owner/tool/releases/latest".to_string(), release("v1.2.0").to_string().into_bytes());
+            route("/repos/owner/tool/releases/tags/1.0.0".to_string(), release("1.0.0").to_string().into_bytes());
+            route(
+                "/repos/owner/tool/releases?per_page=100".to_string(),
+                json!([release("v1.2.0"), release("1.0.0")]).to_string().into_bytes(),
+            );
+            route(format!("/download/v1.2.0/{}", asset), zip);
+            route("/download/v1.2.0/checksums.txt".to_string(), format!("{}  {}\n", checksum, asset).into_bytes());
+            routes
+        })
+    }
+
+    fn config(home: &Path, api_url: String) -> Config {
+        Config { home: Some(home.to_string_lossy().into_owned()), github_api_url: Some(api_url), ..Default::default() }
+    }
+
+    fn quiet() -> (Logger, MultiProgress) {
+        (Logger::new(LogLevel::Minimal), MultiProgress::with_draw_target(ProgressDrawTarget::hidden()))
+    }
+
+    #[test]
+    fn finds_releases_through_the_configured_api() {
+        let home = tempfile::tempdir().unwrap();
+        let client = GitHubClient::from_config(&config(home.path(), api(None))).unwrap();
+        let requirement = |r: &str| VersionReq::parse(r).unwrap();
+
+        assert_eq!(client.release("owner/tool", None).unwrap().tag_name, "v1.2.0");
+        // `v1.0.0` is tried first, then the bare tag
+        assert_eq!(client.release("owner/tool", Some(&requirement("=1.0.0"))).unwrap().tag_name, "1.0.0");
+        assert_eq!(client.release("owner/tool", Some(&requirement("<1.2"))).unwrap().tag_name, "1.0.0");
+        assert!(matches!(client.release("owner/tool", Some(&requirement(">=2"))), Err(GetError::PackageNotFound(_))));
+        assert!(matches!(client.release("owner/missing", None), Err(GetError::PackageNotFound(_))));
+    }
+
+    #[test]
+    fn installs_a_verified_release() {
+        let home = tempfile::tempdir().unwrap();
+        let config = config(home.path(), api(None));
+        let (logger, m) = quiet();
+
+        let report = GitHubManager.install("owner/tool", &config, &logger, &m).unwrap();
+        assert_eq!(report.version.as_deref(), Some("1.2.0"));
+        let installed = PackageDb::open(&package_db_path(&config)).unwrap().installed("owner/tool").unwrap().unwrap();
+        assert_eq!((installed.version.as_str(), installed.source.as_str()), ("1.2.0", "github"));
+        let current = installed.location.unwrap();
+        assert!(current.starts_with(home.path()));
+        assert!(current.join(if cfg!(target_os = "windows") { "tool.exe" } else { "tool" }).exists());
+        assert_eq!(installed.shims.len(), 1);
+    }
+
+    #[test]
+    fn refuses_an_asset_that_does_not_match_its_checksum() {
+        let home = tempfile::tempdir().unwrap();
+        let config = config(home.path(), api(Some("0000000000000000000000000000000000000000000000000000000000000000")));
+        let (logger, m) = quiet();
+
+        assert!(matches!(GitHubManager.install("owner/tool", &config, &logger, &m), Err(GetError::ValidationError(_))));
+        assert!(Apps::from_config(&config).versions("owner-tool").is_empty());
+    }
+}
@@ -1,8 +1,24 @@
 //! Core library for the package manager
 
+pub mod apps;
+pub mod archive;
+pub mod bundle;
 pub mod config;
+pub mod ecosystem;
 pub mod error;
+pub mod github;
 pub mod logging;
+pub mod native;
+pub mod package_db;
 pub mod package_manager;
+pub mod plugin;
+pub mod presentation;
+pub mod protocol;
 pub mod utils;
-pub mod repository;
\ No newline at end of file
+pub mod repository;
+pub mod resolver;
+pub mod source;
+pub mod static_repo;
+pub mod version;
+pub mod winget;
+pub mod winget_source;
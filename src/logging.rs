@@ -20,6 +20,11 @@ impl Logger {
         Logger { level }
     }
 
+    /// The level this logger was created with
+    pub fn level(&self) -> &LogLevel {
+        &self.level
+    }
+
     /// Log a message (cyan color)
     pub fn log(&self, message: &str) {
         println!("\x1b[36m{}\x1b[0m", message);
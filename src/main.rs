@@ -1,5 +1,6 @@
 #![allow(unused, unused_imports, unused_variables, unused_mut)]
 
+use std::collections::BTreeMap;
 use std::env;
 use std::fs::{self, File, OpenOptions};
 use std::io::{self, BufRead, BufReader, Read, Write};
@@ -12,7 +13,8 @@ use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use dirs::home_dir;
 use reqwest::blocking::Client;
 use reqwest::header::{AUTHORIZATION, USER_AGENT};
-use serde::{Deserialize, Serialize};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize};
 use serde_json::Value;
 use serde_yaml::Value as YamlValue;
 use sha2::{Digest, Sha256};
@@ -20,18 +22,33 @@ use hex::encode as hex_encode;
 use rpassword::read_password;
 use walkdir::WalkDir;
 use chrono::{DateTime, Utc, TimeZone};
+#[cfg(windows)]
 use winapi::um::winuser::SW_HIDE;
 use indicatif::{ProgressBar, ProgressStyle, MultiProgress};
 use rayon::prelude::*;
-use ctrlc;
-use rmp_serde::{encode, decode};
 
+use get::apps::{find_path_commands, unpack_into, Apps, BinSpec};
+use get::bundle::{create_bundle, install_from_bundle};
 use get::config::Config;
+use get::ecosystem::{Ecosystem, EcosystemManager};
 use get::error::GetError;
+use get::github::{GitHubClient, GitHubManager};
 use get::logging::{Logger, LogLevel};
-use get::repository::{ensure_repo, WINGET_PKG_REPO_URL, SCOOP_MAIN_REPO_URL};
-use get::package_manager::{PackageManager, WingetManager, ScoopManager};
-use get::utils::{download_file, verify_checksum};
+use get::native::{find_on_path, NativeKind, NativeManager};
+use get::package_db::{package_db_path, DbEntry, InstalledPackage, InstallerRecord, PackageDb, PackageRecord};
+use get::package_manager::{ArchiveFormat, BackendRegistry, InstallReport, OutdatedPackage, PackageManager, SearchHit, UninstallReport, UniversalPackageManager};
+use get::plugin::{discover_plugins, Plugin};
+use get::presentation::{
+    print_install_report, print_installed, print_installed_json, print_outdated, print_package_info,
+    print_search_results, print_uninstall_report,
+};
+use get::resolver::{build_graph, PackageLookup, ResolvedPackage};
+use get::source::{plan_sources, split_source_qualifier, Source, SourceChoice};
+use get::static_repo::build_static_repository;
+use get::utils::{download_file, validate_path_component, verify_checksum};
+use get::version::{select_version, split_package_spec, Version, VersionReq};
+use get::winget::{self, WingetPackage};
+use get::winget_source::{WingetSource, WingetSourceEntry};
 
 // Atomic flag for graceful termination
 static SHOULD_TERMINATE: AtomicBool = AtomicBool::new(false);
@@ -43,30 +60,75 @@ static SHOULD_TERMINATE: AtomicBool = AtomicBool::new(false);
 // -------------------- Command Parsing --------------------
 
 enum CommandType {
-    Install(String),
+    Install { package: String, no_deps: bool },
+    InstallBundle { bundle: PathBuf, packages: Vec<String> },
     Uninstall(String),
+    List { filter: Option<String>, source: Option<String>, json: bool },
+    Info(String),
+    Outdated,
+    Upgrade { packages: Vec<String>, all: bool },
     Search(String),
+    Provides(String),
+    Deps(String),
+    Plugins,
     Clone(String),
     Download(String),
     Auth,
+    Bucket(BucketCommand),
+    Repo(RepoCommand),
+    Bundle(BundleCommand),
+    Manifest(ManifestCommand),
+}
+
+enum ManifestCommand {
+    CheckVer(Vec<PathBuf>),
+    AutoUpdate(Vec<PathBuf>),
+}
+
+enum BundleCommand {
+    Create { packages: Vec<String>, output: PathBuf },
+}
+
+enum RepoCommand {
+    Build { manifests: PathBuf, out: PathBuf },
+}
+
+enum BucketCommand {
+    Add(String, Option<String>),
+    Remove(String),
+    List,
 }
 
 fn parse_args() -> Result<CommandType, GetError> {
     let args: Vec<String> = env::args().collect();
     if args.len() < 2 {
         return Err(GetError::InvalidInput(
-            "No command provided.\nUsage:\n  get auth\n  get install <package-name>\n  get uninstall <package-name>\n  get search <query>\n  get clone <repository-url>\n  get <download-url>".to_string(),
+            "No command provided.\nUsage:\n  get auth\n  get install [--no-deps] [<source>:]<package-name>[@version]\n  get install --from <bundle> [<package-name>...]\n  get uninstall <package-name>\n  get list [<filter>] [--source <source>] [--json]\n  get info <package-name>\n  get outdated\n  get upgrade <package-name>... | --all\n  get search <query>\n  get provides <command>\n  get deps <package-name>\n  get plugins\n  get bucket add|remove|list\n  get repo build <manifests-dir> --out <dir>\n  get manifest checkver|autoupdate <manifest>...\n  get bundle create <package-name>... -o <bundle>\n  get clone <repository-url>\n  get <download-url>".to_string(),
         ));
     }
 
     match args[1].as_str() {
-        "install" => {
-            if args.len() != 3 {
+        "install" if args.get(2).map(String::as_str) == Some("--from") => {
+            if args.len() < 4 {
                 return Err(GetError::InvalidInput(
-                    "Invalid install command.\nUsage: get install <package-name>".to_string(),
+                    "Invalid install command.\nUsage: get install --from <bundle> [<package-name>...]".to_string(),
                 ));
             }
-            Ok(CommandType::Install(args[2].clone()))
+            Ok(CommandType::InstallBundle {
+                bundle: PathBuf::from(&args[3]),
+                packages: args[4..].to_vec(),
+            })
+        }
+        "install" => {
+            match args.iter().skip(2).map(String::as_str).collect::<Vec<_>>().as_slice() {
+                [package] => Ok(CommandType::Install { package: package.to_string(), no_deps: false }),
+                ["--no-deps", package] | [package, "--no-deps"] => {
+                    Ok(CommandType::Install { package: package.to_string(), no_deps: true })
+                }
+                _ => Err(GetError::InvalidInput(
+                    "Invalid install command.\nUsage: get install [--no-deps] [<source>:]<package-name>[@version]".to_string(),
+                )),
+            }
         }
         "uninstall" => {
             if args.len() != 3 {
@@ -76,6 +138,51 @@ fn parse_args() -> Result<CommandType, GetError> {
             }
             Ok(CommandType::Uninstall(args[2].clone()))
         }
+        "list" => {
+            let usage = "Invalid list command.\nUsage: get list [<filter>] [--source <source>] [--json]";
+            let mut filter = None;
+            let mut source = None;
+            let mut json = false;
+            let mut rest = args[2..].iter();
+            while let Some(arg) = rest.next() {
+                match arg.as_str() {
+                    "--json" => json = true,
+                    "--source" => match rest.next() {
+                        Some(name) => source = Some(name.clone()),
+                        None => return Err(GetError::InvalidInput(usage.to_string())),
+                    },
+                    _ if filter.is_none() && !arg.starts_with('-') => filter = Some(arg.clone()),
+                    _ => return Err(GetError::InvalidInput(usage.to_string())),
+                }
+            }
+            Ok(CommandType::List { filter, source, json })
+        }
+        "info" => {
+            if args.len() != 3 {
+                return Err(GetError::InvalidInput(
+                    "Invalid info command.\nUsage: get info [<source>:]<package-name>".to_string(),
+                ));
+            }
+            Ok(CommandType::Info(args[2].clone()))
+        }
+        "outdated" => {
+            if args.len() != 2 {
+                return Err(GetError::InvalidInput(
+                    "Invalid outdated command.\nUsage: get outdated".to_string(),
+                ));
+            }
+            Ok(CommandType::Outdated)
+        }
+        "upgrade" => {
+            let all = args[2..].iter().any(|arg| arg == "--all");
+            let packages: Vec<String> = args[2..].iter().filter(|arg| *arg != "--all").cloned().collect();
+            if all != packages.is_empty() {
+                return Err(GetError::InvalidInput(
+                    "Invalid upgrade command.\nUsage: get upgrade <package-name>... | --all".to_string(),
+                ));
+            }
+            Ok(CommandType::Upgrade { packages, all })
+        }
         "search" => {
             if args.len() != 3 {
                 return Err(GetError::InvalidInput(
@@ -84,6 +191,30 @@ fn parse_args() -> Result<CommandType, GetError> {
             }
             Ok(CommandType::Search(args[2].clone()))
         }
+        "provides" => {
+            if args.len() != 3 {
+                return Err(GetError::InvalidInput(
+                    "Invalid provides command.\nUsage: get provides <command>".to_string(),
+                ));
+            }
+            Ok(CommandType::Provides(args[2].clone()))
+        }
+        "deps" => {
+            if args.len() != 3 {
+                return Err(GetError::InvalidInput(
+                    "Invalid deps command.\nUsage: get deps [<source>:]<package-name>[@version]".to_string(),
+                ));
+            }
+            Ok(CommandType::Deps(args[2].clone()))
+        }
+        "plugins" => {
+            if args.len() != 2 {
+                return Err(GetError::InvalidInput(
+                    "Invalid plugins command.\nUsage: get plugins".to_string(),
+                ));
+            }
+            Ok(CommandType::Plugins)
+        }
         "clone" => {
             if args.len() != 3 {
                 return Err(GetError::InvalidInput(
@@ -95,6 +226,56 @@ fn parse_args() -> Result<CommandType, GetError> {
         "auth" => {
             Ok(CommandType::Auth)
         }
+        "bucket" => {
+            let usage = "Invalid bucket command.\nUsage:\n  get bucket add <name> [<git-url>]\n  get bucket remove <name>\n  get bucket list";
+            match (args.get(2).map(String::as_str), args.len()) {
+                (Some("add"), 4) => Ok(CommandType::Bucket(BucketCommand::Add(args[3].clone(), None))),
+                (Some("add"), 5) => Ok(CommandType::Bucket(BucketCommand::Add(args[3].clone(), Some(args[4].clone())))),
+                (Some("remove"), 4) | (Some("rm"), 4) => Ok(CommandType::Bucket(BucketCommand::Remove(args[3].clone()))),
+                (Some("list"), 3) => Ok(CommandType::Bucket(BucketCommand::List)),
+                _ => Err(GetError::InvalidInput(usage.to_string())),
+            }
+        }
+        "repo" => {
+            let usage = "Invalid repo command.\nUsage: get repo build <manifests-dir> --out <dir>";
+            match args.iter().skip(2).map(String::as_str).collect::<Vec<_>>().as_slice() {
+                ["build", manifests, "--out", out] | ["build", "--out", out, manifests] => {
+                    Ok(CommandType::Repo(RepoCommand::Build {
+                        manifests: PathBuf::from(manifests),
+                        out: PathBuf::from(out),
+                    }))
+                }
+                _ => Err(GetError::InvalidInput(usage.to_string())),
+            }
+        }
+        "manifest" => {
+            let usage = "Invalid manifest command.\nUsage: get manifest checkver|autoupdate <manifest>...";
+            let files: Vec<PathBuf> = args.iter().skip(3).map(PathBuf::from).collect();
+            match args.get(2).map(String::as_str) {
+                Some("checkver") if !files.is_empty() => Ok(CommandType::Manifest(ManifestCommand::CheckVer(files))),
+                Some("autoupdate") if !files.is_empty() => Ok(CommandType::Manifest(ManifestCommand::AutoUpdate(files))),
+                _ => Err(GetError::InvalidInput(usage.to_string())),
+            }
+        }
+        "bundle" => {
+            let usage = "Invalid bundle command.\nUsage: get bundle create <package-name>... -o <bundle>";
+            if args.get(2).map(String::as_str) != Some("create") {
+                return Err(GetError::InvalidInput(usage.to_string()));
+            }
+            let mut packages = Vec::new();
+            let mut output = None;
+            let mut rest = args[3..].iter();
+            while let Some(arg) = rest.next() {
+                match arg.as_str() {
+                    "-o" | "--output" => output = rest.next().map(PathBuf::from),
+                    _ => packages.push(arg.clone()),
+                }
+            }
+            match output {
+                Some(output) if !packages.is_empty() => Ok(CommandType::Bundle(BundleCommand::Create { packages, output })),
+                _ => Err(GetError::InvalidInput(usage.to_string())),
+            }
+        }
         url => {
             if url.starts_with("http://") || url.starts_with("https://") {
                 if args.len() != 2 {
@@ -105,7 +286,7 @@ fn parse_args() -> Result<CommandType, GetError> {
                 Ok(CommandType::Download(url.to_string()))
             } else {
                 Err(GetError::InvalidInput(
-                    "Unknown command.\nUsage:\n  get auth\n  get install <package-name>\n  get uninstall <package-name>\n  get search <query>\n  get clone <repository-url>\n  get <download-url>".to_string(),
+                    "Unknown command.\nUsage:\n  get auth\n  get install [--no-deps] [<source>:]<package-name>[@version]\n  get install --from <bundle> [<package-name>...]\n  get uninstall <package-name>\n  get list [<filter>] [--source <source>] [--json]\n  get info <package-name>\n  get outdated\n  get upgrade <package-name>... | --all\n  get search <query>\n  get provides <command>\n  get deps <package-name>\n  get plugins\n  get bucket add|remove|list\n  get repo build <manifests-dir> --out <dir>\n  get manifest checkver|autoupdate <manifest>...\n  get bundle create <package-name>... -o <bundle>\n  get clone <repository-url>\n  get <download-url>".to_string(),
                 ))
             }
         }
@@ -166,77 +347,123 @@ fn ensure_dependency(dependency: &str, logger: &Logger, m: &MultiProgress) -> Re
 
 // -------------------- Repository Structures --------------------
 
-// Winget Manifest Structures
-#[derive(Debug, Deserialize, Clone, Serialize)]
-struct WingetManifest {
-    PackageIdentifier: String,
-    PackageVersion: String,
-    Publisher: Option<String>,
-    // PackageName: String,
-    License: Option<String>,
-    ShortDescription: Option<String>,
-    Installers: Vec<WingetInstaller>,
-    InstallerSwitches: Option<InstallerSwitches>, // New optional field
-    Commands: Option<Vec<String>>,               // New optional field
-    // Additional fields can be added as needed
-}
-
-#[derive(Debug, Deserialize, Clone, Serialize)]
-struct WingetInstaller {
-    Architecture: String,
-    InstallerType: Option<String>, // Made optional
-    InstallerUrl: String,
-    InstallerSha256: String,
-    InstallerSwitches: Option<InstallerSwitches>, // New optional field
-
-    // AppsAndFeaturesEntries: Option<Vec<AppsAndFeaturesEntry>>, // Optional
-    // Additional fields can be added as needed
-}
-
-#[derive(Debug, Deserialize, Clone, Serialize)]
-struct InstallerSwitches {
-    Silent: Option<String>,
-    // Other switches can be added as needed
-}
-
-#[derive(Debug, Deserialize, Clone, Serialize)]
-struct AppsAndFeaturesEntry {
-    DisplayName: Option<String>,
-    Publisher: Option<String>,
-    InstallerType: Option<String>,
-    // Additional fields can be added as needed
-}
-
 // Scoop Manifest Structures
+//
+// Only `version` is required; fields Scoop allows in several shapes are read
+// leniently so one unusual field doesn't make the whole app unusable.
 #[derive(Debug, Deserialize, Clone, Serialize)]
 struct ScoopManifest {
     version: String,
+    #[serde(default)]
     description: String,
+    #[serde(default)]
     homepage: String,
-    license: Option<String>,
-    url: String,
-    hash: String,
-    bin: Option<String>,
+    /// An SPDX identifier, or `{ "identifier": ..., "url": ... }`
+    #[serde(default)]
+    license: Option<Value>,
+    #[serde(default, deserialize_with = "one_or_many")]
+    url: Vec<String>,
+    #[serde(default, deserialize_with = "one_or_many")]
+    hash: Vec<String>,
+    /// The directory of each download to keep, by position
+    #[serde(default, deserialize_with = "one_or_many")]
+    extract_dir: Vec<String>,
+    #[serde(default, deserialize_with = "lenient")]
+    bin: Option<BinSpec>,
+    /// Directories of the app Scoop adds to `PATH`
+    #[serde(default, deserialize_with = "one_or_many")]
+    env_add_path: Vec<String>,
+    #[serde(default, deserialize_with = "one_or_many")]
+    pre_install: Vec<String>,
+    #[serde(default, deserialize_with = "one_or_many")]
+    post_install: Vec<String>,
+    /// Per-architecture overrides, keyed `64bit`, `32bit` or `arm64`
+    #[serde(default, deserialize_with = "lenient")]
+    architecture: Option<BTreeMap<String, ScoopArchitecture>>,
     installer: Option<ScoopInstaller>,
+    #[serde(default, deserialize_with = "lenient")]
     checkver: Option<ScoopCheckVer>,
+    #[serde(default, deserialize_with = "lenient")]
     autoupdate: Option<ScoopAutoupdate>,
-    // Additional fields can be added as needed
+}
+
+/// The fields of a manifest that an `architecture` entry may replace
+#[derive(Debug, Deserialize, Clone, Serialize)]
+struct ScoopArchitecture {
+    #[serde(default, deserialize_with = "one_or_many")]
+    url: Vec<String>,
+    #[serde(default, deserialize_with = "one_or_many")]
+    hash: Vec<String>,
+    #[serde(default, deserialize_with = "one_or_many")]
+    extract_dir: Vec<String>,
+    #[serde(default, deserialize_with = "lenient")]
+    bin: Option<BinSpec>,
+    #[serde(default, deserialize_with = "one_or_many")]
+    pre_install: Vec<String>,
+    #[serde(default, deserialize_with = "one_or_many")]
+    post_install: Vec<String>,
+    installer: Option<ScoopInstaller>,
 }
 
 #[derive(Debug, Deserialize, Clone, Serialize)]
 struct ScoopInstaller {
+    #[serde(default, deserialize_with = "one_or_many")]
     script: Vec<String>,
 }
 
-#[derive(Debug, Deserialize, Clone, Serialize)]
-struct ScoopCheckVer {
-    url: String,
-    regex: String,
+impl ScoopManifest {
+    /// Apply the `architecture` entry for this machine, if there is one
+    fn for_this_architecture(mut self) -> Self {
+        let mut architectures = self.architecture.take().unwrap_or_default();
+        let Some(arch) = scoop_architectures().iter().find_map(|name| architectures.remove(*name)) else {
+            return self;
+        };
+        if !arch.url.is_empty() {
+            self.url = arch.url;
+            self.hash = arch.hash;
+        }
+        if !arch.extract_dir.is_empty() {
+            self.extract_dir = arch.extract_dir;
+        }
+        if !arch.pre_install.is_empty() {
+            self.pre_install = arch.pre_install;
+        }
+        if !arch.post_install.is_empty() {
+            self.post_install = arch.post_install;
+        }
+        self.bin = arch.bin.or(self.bin);
+        self.installer = arch.installer.or(self.installer);
+        self
+    }
 }
 
-#[derive(Debug, Deserialize, Clone, Serialize)]
-struct ScoopAutoupdate {
-    url: String,
+/// The `architecture` keys that run on this machine, best first, in the
+/// order Scoop falls back through them
+fn scoop_architectures() -> &'static [&'static str] {
+    match std::env::consts::ARCH {
+        "aarch64" => &["arm64", "64bit", "32bit"],
+        "x86" => &["32bit"],
+        _ => &["64bit", "32bit"],
+    }
+}
+
+/// Scoop writes a single string or a list of them for many fields
+fn one_or_many<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(value) => vec![value],
+        OneOrMany::Many(values) => values,
+    })
+}
+
+/// Read an optional field, treating a shape we don't understand as absent
+fn lenient<'de, D: Deserializer<'de>, T: DeserializeOwned>(deserializer: D) -> Result<Option<T>, D::Error> {
+    Ok(serde_json::from_value(Value::deserialize(deserializer)?).ok())
 }
 
 // Module declarations
@@ -244,8 +471,11 @@ mod chocolatey;
 mod scoop;
 
 // Import package manager modules
-use chocolatey::{ensure_choco_repo, install_choco_package};
-use scoop::{ensure_scoop_repo, install_scoop_package};
+use chocolatey::{install_choco_package, nuget_requirement, ChocoFeed, ChocoManager};
+use scoop::{
+    add_bucket, autoupdate_manifests, checkver_manifests, ensure_bucket, install_scoop_package, list_buckets, manifest_checkver,
+    read_manifest, remove_bucket, scoop_bucket, scoop_source, ScoopAutoupdate, ScoopCheckVer,
+};
 
 // -------------------- Install Manager --------------------
 
@@ -254,49 +484,670 @@ fn install_package(
     config: &Config,
     logger: &Logger,
     m: &MultiProgress,
-) -> Result<(), GetError> {
+) -> Result<InstallReport, GetError> {
     if SHOULD_TERMINATE.load(Ordering::SeqCst) {
         return Err(GetError::InvalidInput("Operation terminated by user.".to_string()));
     }
 
-    // Check which package manager to use based on config
-    match config.get_preferred_manager() {
-        Some("choco") => install_choco_package(package, config, logger, m),
-        Some("scoop") => install_scoop_package(package, config, logger, m),
-        _ => {
-            // Try both if no preference is set
-            if let Err(e) = install_choco_package(package, config, logger, m) {
-                install_scoop_package(package, config, logger, m)
-            } else {
-                Ok(())
+    // Qualifiers and pins name one source; otherwise sources are tried by priority
+    let (package, choices) = plan_sources(package, config)?;
+    let mut choices = choices.into_iter().peekable();
+    while let Some(choice) = choices.next() {
+        logger.info(&format!("Using {} for '{}' ({}).", choice.source, package, choice.reason));
+        let result = match &choice.source {
+            Source::Repository(_) => match choice.source.repository(config) {
+                Some(repo) => UniversalPackageManager.install_from_repository(repo, package, config, logger, m),
+                None => Err(GetError::ConfigError(format!("Unknown {}", choice.source))),
+            },
+            Source::Chocolatey => install_choco_package(package, config, logger, m),
+            Source::Scoop => ScoopManager.install(package, config, logger, m),
+            Source::Winget => WingetManager.install(package, config, logger, m),
+            Source::Native(kind) => NativeManager::new(*kind).install(package, config, logger, m),
+            Source::Ecosystem(ecosystem) => EcosystemManager::new(*ecosystem).install(package, config, logger, m),
+            Source::GitHub => GitHubManager.install(package, config, logger, m),
+            Source::Plugin(name) => Plugin::named(name).and_then(|plugin| plugin.install(package, config, logger, m)),
+        };
+
+        // Only a source without the package hands over to the next one; a
+        // failed install is reported rather than retried from another source
+        match result {
+            Err(GetError::PackageNotFound(_)) if choices.peek().is_some() => {
+                logger.log(&format!("'{}' not found in {}.", package, choice.source));
             }
+            result => return result,
         }
     }
+
+    Err(GetError::PackageNotFound(package.to_string()))
 }
 
-// -------------------- Index Structures --------------------
+// -------------------- Dependency Resolution --------------------
 
-// Winget Index Entry
-#[derive(Debug, Serialize, Deserialize, Clone)]
-struct WingetIndexEntry {
-    PackageIdentifier: String,
-    PackageVersion: String,
-    Publisher: Option<String>,
-    License: Option<String>,
-    ShortDescription: Option<String>,
-    Installers: Vec<WingetInstaller>,
+/// Looks packages up in the same sources, and in the same order, as `install_package`
+struct SourceLookup<'a> {
+    config: &'a Config,
+    logger: &'a Logger,
+    m: &'a MultiProgress,
+    // Opened on first use; most graphs only touch one or two sources
+    winget: Option<WingetSource>,
+    db: Option<PackageDb>,
+}
+
+impl<'a> SourceLookup<'a> {
+    fn new(config: &'a Config, logger: &'a Logger, m: &'a MultiProgress) -> Self {
+        SourceLookup { config, logger, m, winget: None, db: None }
+    }
+
+    fn winget(&mut self) -> Result<&WingetSource, GetError> {
+        if self.winget.is_none() {
+            self.winget = Some(load_winget_source(self.config, self.logger, self.m)?);
+        }
+        Ok(self.winget.as_ref().unwrap())
+    }
+
+    fn db(&mut self) -> Result<&PackageDb, GetError> {
+        if self.db.is_none() {
+            self.db = Some(load_package_db(self.config, self.logger, self.m)?);
+        }
+        Ok(self.db.as_ref().unwrap())
+    }
+
+    fn resolve_in(&mut self, source: &Source, name: &str, requirement: &VersionReq) -> Result<ResolvedPackage, GetError> {
+        // Every backend selects versions through `select_version`, which takes requirements too
+        let wanted = if requirement.is_any() { None } else { Some(requirement.to_string()) };
+        let package = match &wanted {
+            Some(wanted) => format!("{}@{}", name, wanted),
+            None => name.to_string(),
+        };
+        match source {
+            Source::Repository(repo_name) => {
+                let repo = source
+                    .repository(self.config)
+                    .ok_or_else(|| GetError::ConfigError(format!("Unknown {}", source)))?;
+                let info = UniversalPackageManager
+                    .fetch_from_repository(repo, &package, self.config, self.logger)?
+                    .ok_or_else(|| GetError::PackageNotFound(format!("'{}' not found in {}", package, source)))?;
+                Ok(ResolvedPackage {
+                    install_spec: format!("{}:{}@{}", repo_name, info.name, info.version),
+                    name: info.name,
+                    version: info.version,
+                    source: repo_name.clone(),
+                    dependencies: info.dependencies,
+                })
+            }
+            Source::Winget => {
+                let winget = self.winget()?;
+                let entry = winget.resolve(name, wanted.as_deref())?;
+                let manifest = winget.fetch_manifest(&entry)?;
+                let dependencies = manifest
+                    .package_dependencies(winget::select_installer(&manifest.installers))
+                    .into_iter()
+                    .map(|d| match d.minimum_version {
+                        Some(minimum) => format!("winget:{}@{}", d.package_identifier, VersionReq::at_least(&minimum)),
+                        None => format!("winget:{}", d.package_identifier),
+                    })
+                    .collect();
+                Ok(ResolvedPackage {
+                    install_spec: format!("winget:{}@{}", entry.identifier, entry.version),
+                    name: entry.identifier,
+                    version: entry.version,
+                    source: "winget".to_string(),
+                    dependencies,
+                })
+            }
+            Source::Scoop => {
+                let entry = find_scoop_entry(self.db()?, name)?
+                    .ok_or_else(|| GetError::PackageNotFound(format!("'{}' not found in any Scoop bucket", name)))?;
+                let bucket = scoop_bucket(&entry.source).to_string();
+                // Scoop builds older versions itself, so only pin an exact request
+                let (install_spec, version) = match requirement.exact_version() {
+                    Some(version) => (format!("scoop:{}/{}@{}", bucket, entry.name, version), version.to_string()),
+                    None => (format!("scoop:{}/{}", bucket, entry.name), entry.version),
+                };
+                Ok(ResolvedPackage {
+                    install_spec,
+                    dependencies: scoop_depends(Path::new(&entry.manifest_path))?,
+                    name: entry.name,
+                    version,
+                    source: format!("scoop/{}", bucket),
+                })
+            }
+            Source::Native(kind) => {
+                // The native manager resolves its own dependencies
+                let native = NativeManager::new(*kind);
+                let version = match requirement.exact_version() {
+                    Some(version) => version.to_string(),
+                    None => native
+                        .available_version(name)?
+                        .ok_or_else(|| GetError::PackageNotFound(format!("'{}' not found by {}", name, kind.name())))?,
+                };
+                Ok(ResolvedPackage {
+                    install_spec: format!("{}:{}@{}", kind.name(), name, version),
+                    name: name.to_string(),
+                    version,
+                    source: kind.name().to_string(),
+                    dependencies: Vec::new(),
+                })
+            }
+            Source::Ecosystem(ecosystem) => {
+                // Ecosystem installers resolve their own dependencies too
+                let manager = EcosystemManager::new(*ecosystem);
+                let version = match requirement.exact_version() {
+                    Some(version) => version.to_string(),
+                    None => manager.available_version(name)?.ok_or_else(|| {
+                        GetError::PackageNotFound(format!("'{}' not found by {}", name, ecosystem.name()))
+                    })?,
+                };
+                // `go install` picks the version itself; keep the request as it was
+                let install_spec = match (ecosystem, &wanted) {
+                    (Ecosystem::Go, Some(wanted)) => format!("go:{}@{}", name, wanted),
+                    (Ecosystem::Go, None) => format!("go:{}", name),
+                    _ => format!("{}:{}@{}", ecosystem.name(), name, version),
+                };
+                Ok(ResolvedPackage {
+                    install_spec,
+                    name: name.to_string(),
+                    version,
+                    source: ecosystem.name().to_string(),
+                    dependencies: Vec::new(),
+                })
+            }
+            Source::GitHub => {
+                // Release assets are self-contained
+                let release = GitHubClient::from_config(self.config)?.release(name, Some(requirement))?;
+                Ok(ResolvedPackage {
+                    install_spec: format!("gh:{}@{}", name, release.version()),
+                    name: name.to_string(),
+                    version: release.version().to_string(),
+                    source: "github".to_string(),
+                    dependencies: Vec::new(),
+                })
+            }
+            Source::Plugin(plugin_name) => {
+                let info = Plugin::named(plugin_name)?.info(name, wanted.as_deref())?;
+                let version = info.version.ok_or_else(|| {
+                    GetError::ParseError(format!("Plugin '{}' gave no version for '{}'", plugin_name, name))
+                })?;
+                Ok(ResolvedPackage {
+                    install_spec: format!("{}:{}@{}", plugin_name, info.name, version),
+                    name: info.name,
+                    version,
+                    source: plugin_name.clone(),
+                    dependencies: info.dependencies,
+                })
+            }
+            Source::Chocolatey => {
+                let manifest = ChocoFeed::from_config(self.config)?.find(name, wanted.as_deref())?;
+                let dependencies = manifest
+                    .dependencies
+                    .iter()
+                    .map(|d| match d.version.as_deref().map(nuget_requirement) {
+                        Some(requirement) if !requirement.is_any() => format!("choco:{}@{}", d.id, requirement),
+                        _ => format!("choco:{}", d.id),
+                    })
+                    .collect();
+                Ok(ResolvedPackage {
+                    install_spec: format!("choco:{}@{}", manifest.package_id, manifest.version),
+                    dependencies,
+                    name: manifest.package_id,
+                    version: manifest.version,
+                    source: "choco".to_string(),
+                })
+            }
+        }
+    }
+
+    /// The versions `source` offers of `name`, newest first; sources without
+    /// a version listing only report their latest
+    fn available_versions(&mut self, source: &Source, name: &str) -> Result<Vec<String>, GetError> {
+        let listed = match source {
+            Source::Repository(_) => {
+                let repo = source
+                    .repository(self.config)
+                    .ok_or_else(|| GetError::ConfigError(format!("Unknown {}", source)))?;
+                UniversalPackageManager.fetch_index(repo, self.config)?.map(|index| {
+                    index
+                        .packages
+                        .into_iter()
+                        .find(|entry| entry.name.eq_ignore_ascii_case(name))
+                        .map(|entry| entry.versions)
+                        .unwrap_or_default()
+                })
+            }
+            Source::Winget => Some(self.winget()?.versions(name)?.into_iter().map(|entry| entry.version).collect()),
+            Source::Scoop => {
+                let (prefix, app) = match name.split_once('/') {
+                    Some((bucket, app)) => (scoop_source(bucket), app),
+                    None => (scoop_source(""), name),
+                };
+                Some(self.db()?.versions(app, &prefix)?.into_iter().map(|entry| entry.version).collect())
+            }
+            _ => None,
+        };
+
+        let mut versions = match listed {
+            Some(versions) => versions,
+            None => vec![self.resolve_in(source, name, &VersionReq::any())?.version],
+        };
+        versions.sort_by_key(|version| std::cmp::Reverse(Version::parse(version)));
+        versions.dedup();
+        Ok(versions)
+    }
+}
+
+impl PackageLookup for SourceLookup<'_> {
+    fn resolve(&mut self, package: &str, requirement: &VersionReq) -> Result<ResolvedPackage, GetError> {
+        let (package, choices) = plan_sources(package, self.config)?;
+        let mut choices = choices.into_iter().peekable();
+        while let Some(choice) = choices.next() {
+            match self.resolve_in(&choice.source, package, requirement) {
+                Err(e @ GetError::PackageNotFound(_)) if choices.peek().is_some() => {
+                    self.logger.log(&format!("'{}' not resolved in {}: {}", package, choice.source, e));
+                }
+                result => return result,
+            }
+        }
+        Err(GetError::PackageNotFound(package.to_string()))
+    }
+
+    fn installed_version(&mut self, package: &ResolvedPackage) -> Option<String> {
+        if let Ok(Some(installed)) = PackageDb::open(&package_db_path(self.config)).and_then(|db| db.installed(&package.name)) {
+            return Some(installed.version);
+        }
+        if let Some(kind) = NativeKind::from_name(&package.source) {
+            return NativeManager::new(kind).installed_version(&package.name).ok().flatten();
+        }
+        if let Some(ecosystem) = Ecosystem::from_name(&package.source) {
+            return EcosystemManager::new(ecosystem).installed_version(&package.name).ok().flatten();
+        }
+        if !package.source.starts_with("scoop/") {
+            return None;
+        }
+        // Scoop keeps the manifest of the active version under `apps/<name>/current`
+        let root = env::var_os("SCOOP").map(PathBuf::from).or_else(|| home_dir().map(|home| home.join("scoop")))?;
+        let manifest = fs::read_to_string(root.join("apps").join(&package.name).join("current").join("manifest.json")).ok()?;
+        serde_json::from_str::<Value>(&manifest).ok()?.get("version")?.as_str().map(str::to_string)
+    }
+}
+
+/// The `depends` of a Scoop manifest, as `scoop:` requests
+fn scoop_depends(manifest_path: &Path) -> Result<Vec<String>, GetError> {
+    let manifest: Value = serde_json::from_str(&fs::read_to_string(manifest_path)?)
+        .map_err(|e| GetError::ParseError(format!("{}: {}", manifest_path.display(), e)))?;
+    // `depends` is a single app or a list; each may be `bucket/app`
+    let depends = match manifest.get("depends") {
+        Some(Value::Array(items)) => items.iter().filter_map(Value::as_str).map(str::to_string).collect(),
+        Some(Value::String(app)) => vec![app.clone()],
+        _ => Vec::new(),
+    };
+    Ok(depends.into_iter().map(|app| format!("scoop:{}", app)).collect())
+}
+
+/// Install `package` after everything it depends on that isn't installed yet
+fn install_with_dependencies(
+    package: &str,
+    no_deps: bool,
+    config: &Config,
+    logger: &Logger,
+    m: &MultiProgress,
+) -> Result<(), GetError> {
+    if no_deps {
+        print_install_report(&install_package(package, config, logger, m)?, logger);
+        return Ok(());
+    }
+
+    logger.log(&format!("Resolving dependencies of '{}'...", package));
+    let graph = build_graph(&[package.to_string()], &mut SourceLookup::new(config, logger, m))?;
+    let order = graph.install_order()?;
+    if order.len() > 1 {
+        logger.info(&format!(
+            "Installing {}",
+            order.iter().map(|p| format!("{} {}", p.name, p.version)).collect::<Vec<_>>().join(", ")
+        ));
+    }
+    for node in order {
+        print_install_report(&install_package(&node.install_spec, config, logger, m)?, logger);
+    }
+    Ok(())
+}
+
+/// Print the dependency tree of `package`
+fn show_dependencies(package: &str, config: &Config, logger: &Logger, m: &MultiProgress) -> Result<(), GetError> {
+    let graph = build_graph(&[package.to_string()], &mut SourceLookup::new(config, logger, m))?;
+    print!("{}", graph.render_tree());
+    // Report cycles after the tree so the user can see where they are
+    graph.install_order()?;
+    Ok(())
+}
+
+/// Resolve a Winget package by identifier, honouring an optional `@version` pin
+fn install_winget_by_id(
+    package: &str,
+    config: &Config,
+    logger: &Logger,
+    m: &MultiProgress,
+) -> Result<InstallReport, GetError> {
+    let (identifier, version) = split_package_spec(package);
+    let source = load_winget_source(config, logger, m)?;
+
+    let entry = source.resolve(identifier, version)?;
+    logger.log(&format!("Selected '{}' version {}.", entry.identifier, entry.version));
+
+    let manifest = source.fetch_manifest(&entry)?;
+    handle_winget_install(&manifest, config, logger, m)?;
+    // The installer chose where to install, so only the version is known
+    PackageDb::open(&package_db_path(config))?.record_install(&InstalledPackage {
+        name: entry.identifier.clone(),
+        version: entry.version.clone(),
+        source: "winget".to_string(),
+        installed_at: SystemTime::now(),
+        manifest: serde_json::to_string(&manifest)?,
+        location: None,
+        files: Vec::new(),
+        shims: Vec::new(),
+    })?;
+    Ok(InstallReport {
+        source: "winget".to_string(),
+        name: entry.identifier,
+        version: Some(entry.version),
+        location: None,
+    })
+}
+
+/// Resolve a Scoop app by exact name in the index and install it from its bucket
+fn install_scoop_by_name(
+    package: &str,
+    config: &Config,
+    logger: &Logger,
+    m: &MultiProgress,
+) -> Result<InstallReport, GetError> {
+    let (name, version) = split_package_spec(package);
+    let db = load_package_db(config, logger, m)?;
+    let entry = find_scoop_entry(&db, name)?
+        .ok_or_else(|| GetError::PackageNotFound(format!("'{}' not found in any Scoop bucket", name)))?;
+    let bucket = scoop_bucket(&entry.source);
+
+    logger.log(&format!("Found '{}' ({}) in Scoop bucket '{}'.", entry.name, entry.version, bucket));
+
+    // The bucket's version is installed directly, like Scoop would
+    let requirement = VersionReq::parse(version.unwrap_or("*"))?;
+    let manifest = load_scoop_entry(&entry)?;
+    if requirement.matches(&Version::parse(&manifest.version)) {
+        match portable_blocker(&manifest) {
+            None => {
+                let location = install_scoop_portable(&manifest, config, logger, m)?;
+                return Ok(InstallReport {
+                    source: "scoop".to_string(),
+                    name: manifest.name,
+                    version: Some(manifest.version),
+                    location: Some(location),
+                });
+            }
+            Some(reason) => logger.log(&format!("Installing '{}' with Scoop: {}.", manifest.name, reason)),
+        }
+    }
+
+    // Scoop itself is needed for what we can't do portably, and for older versions,
+    // whose manifests it generates; it only knows the latest, so a range must be met by that one
+    let (target, installed) = match requirement.exact_version() {
+        Some(version) => (format!("{}/{}@{}", bucket, entry.name, version), version.to_string()),
+        None if requirement.matches(&Version::parse(&entry.version)) => (format!("{}/{}", bucket, entry.name), entry.version.clone()),
+        None => {
+            return Err(GetError::PackageNotFound(format!(
+                "'{}' {} in Scoop bucket '{}' does not match {}",
+                entry.name, entry.version, bucket, requirement
+            )))
+        }
+    };
+    install_scoop_package(&target, config, logger, m)?;
+    // Scoop keeps the files; the record lets `get` list and upgrade the app
+    PackageDb::open(&package_db_path(config))?.record_install(&InstalledPackage {
+        name: entry.name.clone(),
+        version: installed.clone(),
+        source: entry.source.clone(),
+        installed_at: SystemTime::now(),
+        manifest: fs::read_to_string(&manifest.manifest_path)?,
+        location: None,
+        files: Vec::new(),
+        shims: Vec::new(),
+    })?;
+    Ok(InstallReport {
+        source: "scoop".to_string(),
+        name: entry.name,
+        version: Some(installed),
+        location: None,
+    })
+}
+
+/// Why a Scoop app can't be installed without the scoop binary, if it can't
+fn portable_blocker(manifest: &ScoopIndexEntry) -> Option<String> {
+    if manifest.installer.is_some() {
+        return Some("it runs an installer".to_string());
+    }
+    if manifest.url.is_empty() {
+        return Some("it has no download for this machine".to_string());
+    }
+    if manifest.hash.len() != manifest.url.len() {
+        return Some("not every download has a hash".to_string());
+    }
+    // Scoop only checks SHA-256 by default; other algorithms are prefixed
+    if let Some(hash) = manifest.hash.iter().find(|hash| hash.strip_prefix("sha256:").unwrap_or(hash).contains(':')) {
+        return Some(format!("'{}' can't be verified, only SHA-256 can", hash));
+    }
+    let has_scripts = !manifest.pre_install.is_empty() || !manifest.post_install.is_empty();
+    if has_scripts && powershell().is_none() {
+        return Some("its install scripts need PowerShell".to_string());
+    }
+    None
 }
 
+/// PowerShell to run manifest scripts with, preferring PowerShell 7
+fn powershell() -> Option<PathBuf> {
+    ["pwsh", "pwsh.exe", "powershell.exe"].into_iter().find_map(find_on_path)
+}
+
+/// Run a `pre_install` or `post_install` script with the variables Scoop
+/// gives it; `dir` is the app's directory
+fn run_scoop_script(
+    lines: &[String],
+    hook: &str,
+    manifest: &ScoopIndexEntry,
+    dir: &Path,
+    logger: &Logger,
+) -> Result<(), GetError> {
+    if lines.is_empty() {
+        return Ok(());
+    }
+    let shell = powershell()
+        .ok_or_else(|| GetError::CommandError(format!("PowerShell is needed for the {} of '{}'", hook, manifest.name)))?;
+    let quote = |value: &str| format!("'{}'", value.replace('\'', "''"));
+    let script = format!(
+        "$ErrorActionPreference = 'Stop'\n$app = {}\n$version = {}\n$dir = {}\n$original_dir = $dir\n$architecture = {}\n{}",
+        quote(&manifest.name),
+        quote(&manifest.version),
+        quote(&dir.to_string_lossy()),
+        quote(scoop_architectures()[0]),
+        lines.join("\n")
+    );
+
+    logger.log(&format!("Running the {} of '{}'...", hook, manifest.name));
+    let status = Command::new(shell)
+        .args(["-NoProfile", "-NonInteractive", "-Command", &script])
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .status()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(GetError::CommandError(format!("The {} of '{}' failed", hook, manifest.name)))
+    }
+}
+
+/// Install a Scoop app into the apps directory without the scoop binary:
+/// download and verify each `url`, unpack it, run the install scripts, switch
+/// `current` and shim its `bin` and `env_add_path` commands
+fn install_scoop_portable(
+    manifest: &ScoopIndexEntry,
+    config: &Config,
+    logger: &Logger,
+    m: &MultiProgress,
+) -> Result<PathBuf, GetError> {
+    if let Some(reason) = portable_blocker(manifest) {
+        return Err(GetError::ValidationError(format!("'{}' can't be installed portably: {}", manifest.name, reason)));
+    }
+
+    let apps = Apps::from_config(config);
+    let dir = apps.prepare_version(&manifest.name, &manifest.version)?;
+    let work_dir = tempfile::tempdir()?;
+    for (index, (url, hash)) in manifest.url.iter().zip(&manifest.hash).enumerate() {
+        // `url#/name.ext` renames the download, usually to give it an extension
+        let (url, rename) = match url.split_once("#/") {
+            Some((url, name)) => (url, Some(name)),
+            None => (url.as_str(), None),
+        };
+        let mut file = download_installer(url, work_dir.path(), logger, m)?;
+        if let Some(name) = rename {
+            validate_path_component("file name", name)?;
+            let renamed = work_dir.path().join(name);
+            fs::rename(&file, &renamed)?;
+            file = renamed;
+        }
+        verify_checksum(&file, hash.strip_prefix("sha256:").unwrap_or(hash), logger)?;
+
+        let file_name = file.file_name().and_then(|n| n.to_str()).unwrap_or(&manifest.name).to_string();
+        let extract_dir = manifest.extract_dir.get(index).map(String::as_str);
+        unpack_into(&dir, &file, ArchiveFormat::detect(&file), 0, &file_name, extract_dir)?;
+    }
+    run_scoop_script(&manifest.pre_install, "pre_install", manifest, &dir, logger)?;
+
+    let current = apps.set_current(&manifest.name, &manifest.version)?;
+    apps.remove_shims(&manifest.name)?;
+    let mut shims = manifest.bin.as_ref().map(BinSpec::shims).unwrap_or_default();
+    for path in &manifest.env_add_path {
+        shims.extend(find_path_commands(&dir, path)?);
+    }
+    let shims = apps.create_shims(&manifest.name, &shims)?;
+    for shim in &shims {
+        logger.log(&format!("Created shim '{}'.", shim.display()));
+    }
+    run_scoop_script(&manifest.post_install, "post_install", manifest, &current, logger)?;
+
+    PackageDb::open(&package_db_path(config))?.record_install(&InstalledPackage {
+        name: manifest.name.clone(),
+        version: manifest.version.clone(),
+        source: scoop_source(&manifest.bucket),
+        installed_at: SystemTime::now(),
+        manifest: serde_json::to_string(manifest)?,
+        location: Some(current.clone()),
+        files: apps.files(&manifest.name, &manifest.version),
+        shims,
+    })?;
+    Ok(current)
+}
+
+// -------------------- Package Manager Backends --------------------
+
+/// Winget packages, resolved through the official source index
+struct WingetManager;
+
+impl PackageManager for WingetManager {
+    fn name(&self) -> &str {
+        "winget"
+    }
+
+    fn install(&self, package: &str, config: &Config, logger: &Logger, m: &MultiProgress) -> Result<InstallReport, GetError> {
+        install_winget_by_id(package, config, logger, m)
+    }
+
+    fn uninstall(&self, package: &str, config: &Config, logger: &Logger, m: &MultiProgress) -> Result<UninstallReport, GetError> {
+        let (identifier, _) = split_package_spec(package);
+        let entry = load_winget_source(config, logger, m)?
+            .versions(identifier)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| GetError::PackageNotFound(format!("'{}' not found in Winget", identifier)))?;
+
+        logger.info(&format!("Uninstalling '{}' with Winget.", entry.identifier));
+        handle_winget_uninstall(&entry, logger, m)?;
+        Ok(UninstallReport {
+            source: "winget".to_string(),
+            name: entry.identifier,
+            version: None,
+        })
+    }
+
+    fn search(&self, query: &str, config: &Config, logger: &Logger, m: &MultiProgress) -> Result<Vec<SearchHit>, GetError> {
+        search_winget(&load_winget_source(config, logger, m)?, query)
+    }
+}
+
+/// Scoop apps from the configured buckets
+struct ScoopManager;
+
+impl PackageManager for ScoopManager {
+    fn name(&self) -> &str {
+        "scoop"
+    }
+
+    fn install(&self, package: &str, config: &Config, logger: &Logger, m: &MultiProgress) -> Result<InstallReport, GetError> {
+        install_scoop_by_name(package, config, logger, m)
+    }
+
+    fn uninstall(&self, package: &str, config: &Config, logger: &Logger, m: &MultiProgress) -> Result<UninstallReport, GetError> {
+        let (name, _) = split_package_spec(package);
+
+        // Apps installed without the scoop binary are removed the same way
+        let installed_db = PackageDb::open(&package_db_path(config))?;
+        if let Some(record) = installed_db.installed(name)?.filter(|r| r.source.starts_with(&scoop_source(""))) {
+            let apps = Apps::from_config(config);
+            logger.info(&format!("Uninstalling '{}' from '{}'.", record.name, apps.app_dir(&record.name).display()));
+            apps.remove(&record.name)?;
+            installed_db.remove_installed(&record.name)?;
+            return Ok(UninstallReport {
+                source: "scoop".to_string(),
+                name: record.name,
+                version: Some(record.version),
+            });
+        }
+
+        let db = load_package_db(config, logger, m)?;
+        let entry = find_scoop_entry(&db, name)?
+            .ok_or_else(|| GetError::PackageNotFound(format!("'{}' not found in any Scoop bucket", name)))?;
+        let entry = load_scoop_entry(&entry)?;
+
+        logger.info(&format!("Uninstalling '{}' from Scoop bucket '{}'.", entry.name, entry.bucket));
+        handle_scoop_uninstall(&entry, logger, m)?;
+        Ok(UninstallReport {
+            source: "scoop".to_string(),
+            name: entry.name,
+            version: None,
+        })
+    }
+
+    fn search(&self, query: &str, config: &Config, logger: &Logger, m: &MultiProgress) -> Result<Vec<SearchHit>, GetError> {
+        search_scoop(&load_package_db(config, logger, m)?, query)
+    }
+}
+
+// -------------------- Index Structures --------------------
+
 // Scoop Index Entry
 #[derive(Debug, Serialize, Deserialize, Clone)]
 struct ScoopIndexEntry {
+    name: String,          // App name, taken from the manifest file name (e.g. `git` for `git.json`)
+    bucket: String,        // Bucket the manifest was found in
+    manifest_path: PathBuf,
     version: String,
     description: String,
     homepage: String,
     license: Option<String>,
-    url: String,
-    hash: String,
-    bin: Option<String>,
+    url: Vec<String>,
+    hash: Vec<String>,
+    extract_dir: Vec<String>,
+    bin: Option<BinSpec>,
+    env_add_path: Vec<String>,
+    pre_install: Vec<String>,
+    post_install: Vec<String>,
     installer: Option<ScoopInstaller>,
     checkver: Option<ScoopCheckVer>,
     autoupdate: Option<ScoopAutoupdate>,
@@ -304,17 +1155,24 @@ struct ScoopIndexEntry {
 
 // -------------------- Index Management --------------------
 
-fn index_winget(winget_repo_path: &Path, logger: &Logger, m: &MultiProgress) -> Result<Vec<WingetIndexEntry>, GetError> {
-    logger.log("Indexing Winget manifests...");
+fn index_scoop(scoop_repo_path: &Path, bucket: &str, logger: &Logger, m: &MultiProgress) -> Result<Vec<PackageRecord>, GetError> {
+    logger.log(&format!("Indexing Scoop manifests for bucket '{}'...", bucket));
     let pb = m.add(ProgressBar::new_spinner());
-    pb.set_message("Indexing Winget manifests...");
+    pb.set_message("Indexing Scoop manifests...");
     pb.enable_steady_tick(Duration::from_millis(100));
 
-    let manifest_files: Vec<_> = WalkDir::new(winget_repo_path)
+    // Buckets keep their manifests under `bucket/`; older layouts keep them at the root
+    let manifests_dir = if scoop_repo_path.join("bucket").is_dir() {
+        scoop_repo_path.join("bucket")
+    } else {
+        scoop_repo_path.to_path_buf()
+    };
+
+    let manifest_files: Vec<_> = WalkDir::new(&manifests_dir)
+        .max_depth(1)
         .into_iter()
         .filter_map(|e| e.ok())
-        .filter(|e| e.path().extension().and_then(|s| s.to_str()) == Some("yaml"))
-        .filter(|e| e.path().file_name().and_then(|s| s.to_str()).map_or(false, |s| s.ends_with("installer.yaml")))
+        .filter(|e| e.path().extension().and_then(|s| s.to_str()) == Some("json"))
         .collect();
 
     pb.finish_and_clear();
@@ -324,10 +1182,10 @@ fn index_winget(winget_repo_path: &Path, logger: &Logger, m: &MultiProgress) ->
     pb_progress.set_style(ProgressStyle::default_bar()
         .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} {msg}")
         .unwrap()
-        .progress_chars("█░-"));
+        .progress_chars("#>-"));
 
     // Parallel processing using rayon
-    let index: Vec<WingetIndexEntry> = manifest_files.par_iter()
+    let index: Vec<PackageRecord> = manifest_files.par_iter()
         .map(|entry| {
             if SHOULD_TERMINATE.load(Ordering::SeqCst) {
                 return None;
@@ -335,271 +1193,588 @@ fn index_winget(winget_repo_path: &Path, logger: &Logger, m: &MultiProgress) ->
 
             let manifest_path = entry.path();
 
-            // Parse the manifest
-            let manifest_text = match fs::read_to_string(manifest_path) {
-                Ok(text) => text,
-                Err(_) => return None, // Skip if failed to read
-            };
-            let manifest: WingetManifest = match serde_yaml::from_str(&manifest_text) {
-                Ok(m) => m,
-                Err(_) => return None, // Skip invalid manifests
-            };
+            // Scoop identifies apps by their manifest file name
+            let name = manifest_path.file_stem()?.to_str()?.to_string();
+
+            // Parse loosely: real manifests use strings, arrays and objects interchangeably
+            let manifest_text = fs::read_to_string(manifest_path).ok()?;
+            let manifest: Value = serde_json::from_str(&manifest_text).ok()?;
 
             pb_progress.inc(1);
 
-            Some(WingetIndexEntry {
-                PackageIdentifier: manifest.PackageIdentifier,
-                PackageVersion: manifest.PackageVersion,
-                Publisher: manifest.Publisher,
-                License: manifest.License,
-                ShortDescription: manifest.ShortDescription,
-                Installers: manifest.Installers,
-            })
+            scoop_record(name, manifest_path, &manifest)
         })
         .filter_map(|x| x)
         .collect();
 
-    pb_progress.finish_with_message("Winget indexing completed.");
+    pb_progress.finish_with_message("Scoop indexing completed.");
 
     Ok(index)
 }
 
-fn index_scoop(scoop_repo_path: &Path, logger: &Logger, m: &MultiProgress) -> Result<Vec<ScoopIndexEntry>, GetError> {
-    logger.log("Indexing Scoop manifests...");
-    let pb = m.add(ProgressBar::new_spinner());
-    pb.set_message("Indexing Scoop manifests...");
-    pb.enable_steady_tick(Duration::from_millis(100));
+/// Build the database record of a Scoop manifest
+fn scoop_record(name: String, manifest_path: &Path, manifest: &Value) -> Option<PackageRecord> {
+    let text = |value: Option<&Value>| value.and_then(Value::as_str).map(str::to_string);
+    // `url` and `hash` may be a string or a list; the first entry is the download
+    let first = |value: Option<&Value>| match value {
+        Some(Value::Array(items)) => text(items.first()),
+        other => text(other),
+    };
 
-    let manifest_files: Vec<_> = WalkDir::new(scoop_repo_path)
-        .into_iter()
-        .filter_map(|e| e.ok())
-        .filter(|e| e.path().extension().and_then(|s| s.to_str()) == Some("json"))
-        .collect();
+    let mut installers = Vec::new();
+    if let Some(url) = first(manifest.get("url")) {
+        installers.push(InstallerRecord { architecture: None, url, hash: first(manifest.get("hash")) });
+    }
+    if let Some(Value::Object(architectures)) = manifest.get("architecture") {
+        for (architecture, details) in architectures {
+            if let Some(url) = first(details.get("url")) {
+                installers.push(InstallerRecord {
+                    architecture: Some(architecture.clone()),
+                    url,
+                    hash: first(details.get("hash")),
+                });
+            }
+        }
+    }
 
-    pb.finish_and_clear();
+    // `bin` is a path, a list of paths, or a list of `[path, alias, args]`
+    let command = |value: &Value| match value {
+        Value::Array(parts) => text(parts.get(1)).or_else(|| text(parts.first())),
+        other => text(Some(other)),
+    };
+    let bin_name = |bin: String| {
+        let file = bin.rsplit(['/', '\\']).next().unwrap_or(&bin).to_string();
+        match file.rsplit_once('.') {
+            Some((stem, _)) if !stem.is_empty() => stem.to_string(),
+            _ => file,
+        }
+    };
+    let commands = match manifest.get("bin") {
+        Some(Value::Array(items)) => items.iter().filter_map(command).map(bin_name).collect(),
+        Some(other) => command(other).map(bin_name).into_iter().collect(),
+        None => Vec::new(),
+    };
 
-    let total = manifest_files.len() as u64;
-    let pb_progress = m.add(ProgressBar::new(total));
-    pb_progress.set_style(ProgressStyle::default_bar()
-        .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} {msg}")
-        .unwrap()
-        .progress_chars("#>-"));
+    let license = match manifest.get("license") {
+        Some(Value::Object(license)) => text(license.get("identifier")),
+        other => text(other),
+    };
 
-    // Parallel processing using rayon
-    let index: Vec<ScoopIndexEntry> = manifest_files.par_iter()
-        .map(|entry| {
-            if SHOULD_TERMINATE.load(Ordering::SeqCst) {
-                return None;
-            }
+    Some(PackageRecord {
+        name,
+        version: text(manifest.get("version"))?,
+        description: text(manifest.get("description")).unwrap_or_default(),
+        homepage: text(manifest.get("homepage")),
+        license,
+        manifest_path: manifest_path.to_string_lossy().into_owned(),
+        installers,
+        commands,
+        tags: Vec::new(),
+    })
+}
 
-            let manifest_path = entry.path();
+/// Open the package database, re-indexing buckets that were pulled since they
+/// were last indexed
+fn load_package_db(
+    config: &Config,
+    logger: &Logger,
+    m: &MultiProgress,
+) -> Result<PackageDb, GetError> {
+    if SHOULD_TERMINATE.load(Ordering::SeqCst) {
+        return Err(GetError::InvalidInput("Operation terminated by user.".to_string()));
+    }
 
-            // Parse the manifest
-            let manifest_text = match fs::read_to_string(manifest_path) {
-                Ok(text) => text,
-                Err(_) => return None, // Skip if failed to read
-            };
-            let manifest: ScoopManifest = match serde_json::from_str(&manifest_text) {
-                Ok(m) => m,
-                Err(_) => return None, // Skip invalid manifests
-            };
+    let mut db = PackageDb::open(&package_db_path(config))?;
 
-            pb_progress.inc(1);
+    // Each bucket is cloned and indexed separately
+    for bucket in config.get_scoop_buckets() {
+        let bucket_local_path = ensure_bucket(&bucket, config, logger, m)?;
+        let source = scoop_source(&bucket.name);
 
-            Some(ScoopIndexEntry {
-                version: manifest.version,
-                description: manifest.description,
-                homepage: manifest.homepage,
-                license: manifest.license,
-                url: manifest.url,
-                hash: manifest.hash,
-                bin: manifest.bin,
-                installer: manifest.installer,
-                checkver: manifest.checkver,
-                autoupdate: manifest.autoupdate,
+        let pulled = fs::metadata(bucket_local_path.join("last_pull.txt")).and_then(|meta| meta.modified()).ok();
+        let is_stale = match (db.indexed_at(&source)?, pulled) {
+            (None, _) => true,
+            (Some(indexed), Some(pulled)) => pulled > indexed,
+            (Some(_), None) => false,
+        };
+        if is_stale {
+            let records = index_scoop(&bucket_local_path, &bucket.name, logger, m)?;
+            db.replace_source(&source, &records)?;
+        } else {
+            logger.log(&format!("Scoop '{}' index is up-to-date.", bucket.name));
+        }
+    }
+
+    Ok(db)
+}
+
+/// Open the Winget source index, refreshing it from the source when stale
+fn load_winget_source(config: &Config, logger: &Logger, m: &MultiProgress) -> Result<WingetSource, GetError> {
+    if SHOULD_TERMINATE.load(Ordering::SeqCst) {
+        return Err(GetError::InvalidInput("Operation terminated by user.".to_string()));
+    }
+
+    let source = WingetSource::new(
+        config.get_winget_source_url(),
+        &config.get_repos_dir().join("winget-source"),
+    );
+    source.update(logger, m)?;
+    Ok(source)
+}
+
+/// Find a Scoop app by its exact name, the way `scoop install <app>` resolves it
+fn find_scoop_entry(db: &PackageDb, package: &str) -> Result<Option<DbEntry>, GetError> {
+    // Allow `bucket/app` qualifiers as Scoop does
+    let (prefix, name) = match package.split_once('/') {
+        Some((bucket, name)) => (scoop_source(bucket), name),
+        None => (scoop_source(""), package),
+    };
+
+    Ok(db.versions(name, &prefix)?.into_iter().next())
+}
+
+/// Load the full manifest of an app found in the database
+fn load_scoop_entry(entry: &DbEntry) -> Result<ScoopIndexEntry, GetError> {
+    let manifest_path = PathBuf::from(&entry.manifest_path);
+    let manifest: ScoopManifest = serde_json::from_str(&fs::read_to_string(&manifest_path)?)
+        .map_err(|e| GetError::ParseError(format!("{}: {}", manifest_path.display(), e)))?;
+    let manifest = manifest.for_this_architecture();
+    let license = match manifest.license {
+        Some(Value::String(license)) => Some(license),
+        Some(Value::Object(license)) => license.get("identifier").and_then(Value::as_str).map(str::to_string),
+        _ => None,
+    };
+
+    Ok(ScoopIndexEntry {
+        name: entry.name.clone(),
+        bucket: scoop_bucket(&entry.source).to_string(),
+        manifest_path,
+        version: manifest.version,
+        description: manifest.description,
+        homepage: manifest.homepage,
+        license,
+        url: manifest.url,
+        hash: manifest.hash,
+        extract_dir: manifest.extract_dir,
+        bin: manifest.bin,
+        env_add_path: manifest.env_add_path,
+        pre_install: manifest.pre_install,
+        post_install: manifest.post_install,
+        installer: manifest.installer,
+        checkver: manifest.checkver,
+        autoupdate: manifest.autoupdate,
+    })
+}
+
+// -------------------- Search Manager --------------------
+
+/// Search the Winget source, one result per package with its latest version
+fn search_winget(source: &WingetSource, query: &str) -> Result<Vec<SearchHit>, GetError> {
+    let mut winget_versions: Vec<(String, Vec<WingetSourceEntry>)> = Vec::new();
+    for entry in source.search(query)? {
+        match winget_versions.iter_mut().find(|(id, _)| *id == entry.identifier) {
+            Some((_, versions)) => versions.push(entry),
+            None => winget_versions.push((entry.identifier.clone(), vec![entry])),
+        }
+    }
+    Ok(winget_versions
+        .iter()
+        .filter_map(|(identifier, versions)| {
+            let latest = select_version(versions, |e| e.version.as_str(), None)?;
+            Some(SearchHit {
+                source: "winget".to_string(),
+                name: identifier.clone(),
+                version: Some(latest.version.clone()),
+                description: Some(latest.name.clone()),
             })
         })
-        .filter_map(|x| x)
-        .collect();
+        .collect())
+}
 
-    pb_progress.finish_with_message("Scoop indexing completed.");
+/// Search every indexed Scoop bucket
+fn search_scoop(db: &PackageDb, query: &str) -> Result<Vec<SearchHit>, GetError> {
+    Ok(db
+        .search(query)?
+        .into_iter()
+        .filter(|entry| entry.source.starts_with(&scoop_source("")))
+        .map(|entry| SearchHit {
+            source: "scoop".to_string(),
+            name: format!("{}/{}", scoop_bucket(&entry.source), entry.name),
+            version: Some(entry.version),
+            description: Some(entry.description),
+        })
+        .collect())
+}
 
-    Ok(index)
+fn search_package(
+    query: &str,
+    logger: &Logger,
+    config: &Config,
+    m: &MultiProgress,
+) -> Result<(), GetError> {
+    if SHOULD_TERMINATE.load(Ordering::SeqCst) {
+        return Err(GetError::InvalidInput("Operation terminated by user.".to_string()));
+    }
+
+    logger.log("Starting search across all package sources...");
+    let hits = search_backends().search_all(query, config, logger, m);
+    print_search_results(query, &hits);
+    Ok(())
+}
+
+/// The backends `get search` looks in, in the order results are shown
+fn search_backends() -> BackendRegistry {
+    let mut registry = BackendRegistry::new();
+    registry.register(Box::new(UniversalPackageManager));
+    if let Some(native) = NativeManager::detect() {
+        registry.register(Box::new(native));
+    }
+    // Only ecosystems with a registry search, and only if their tools are installed
+    for ecosystem in Ecosystem::ALL.iter().filter(|e| e.can_search() && e.is_available()) {
+        registry.register(Box::new(EcosystemManager::new(*ecosystem)));
+    }
+    for plugin in discover_plugins() {
+        registry.register(Box::new(plugin));
+    }
+    registry.register(Box::new(WingetManager));
+    registry.register(Box::new(ScoopManager));
+    registry.register(Box::new(ChocoManager));
+    registry
+}
+
+
+/// List what `get` installed, optionally only names containing `filter` or
+/// packages from one source
+fn list_installed(filter: Option<&str>, source: Option<&str>, json: bool, config: &Config) -> Result<(), GetError> {
+    let packages = installed_matching(filter, source, config)?;
+    if json {
+        print_installed_json(&packages)?;
+    } else {
+        print_installed(&packages);
+    }
+    Ok(())
+}
+
+/// The install records `get list` shows for a filter and a source
+fn installed_matching(filter: Option<&str>, source: Option<&str>, config: &Config) -> Result<Vec<InstalledPackage>, GetError> {
+    let filter = filter.map(str::to_lowercase);
+    // `scoop` matches every bucket and `gh` matches `github`; a bucket only itself
+    let wanted = source.filter(|name| !name.starts_with(&scoop_source(""))).map(|name| recorded_source(name, config));
+    Ok(PackageDb::open(&package_db_path(config))?
+        .list_installed()?
+        .into_iter()
+        .filter(|p| filter.as_ref().is_none_or(|f| p.name.to_lowercase().contains(f)))
+        .filter(|p| {
+            source.is_none_or(|name| {
+                p.source.eq_ignore_ascii_case(name)
+                    || wanted.as_ref().is_some_and(|wanted| &recorded_source(&p.source, config) == wanted)
+            })
+        })
+        .collect())
 }
 
-fn load_or_create_indexes(
-    config: &Config,
-    logger: &Logger,
-    m: &MultiProgress,
-) -> Result<(Vec<WingetIndexEntry>, Vec<ScoopIndexEntry>), GetError> {
-    if SHOULD_TERMINATE.load(Ordering::SeqCst) {
-        return Err(GetError::InvalidInput("Operation terminated by user.".to_string()));
+/// Show the install record of a package and the versions its sources offer
+fn show_info(package: &str, config: &Config, logger: &Logger, m: &MultiProgress) -> Result<(), GetError> {
+    let (package, mut choices) = plan_sources(package, config)?;
+    let (name, _) = split_package_spec(package);
+    let installed = PackageDb::open(&package_db_path(config))?.installed(name)?;
+
+    // The source it came from is asked too, even if it is not ranked
+    if let Some(record) = &installed {
+        let source = recorded_source(&record.source, config);
+        if !choices.iter().any(|c| c.source == source) {
+            choices.insert(0, SourceChoice { source, reason: "installed from".to_string() });
+        }
     }
 
-    let repos_dir = config.get_repos_dir();
-    let winget_local_path = repos_dir.join("winget-pkgs");
-    let scoop_local_path = repos_dir.join("Main");
+    let mut lookup = SourceLookup::new(config, logger, m);
+    let mut available = Vec::new();
+    for choice in &choices {
+        match lookup.available_versions(&choice.source, name) {
+            Ok(versions) if !versions.is_empty() => available.push((choice.source.name().to_string(), versions)),
+            Ok(_) | Err(GetError::PackageNotFound(_)) => {}
+            Err(e) => logger.log(&format!("{} lookup failed: {}", choice.source, e)),
+        }
+    }
 
-    // Ensure repositories are cloned and up-to-date
-    ensure_repo(WINGET_PKG_REPO_URL, &winget_local_path, logger, m)?;
-    ensure_repo(SCOOP_MAIN_REPO_URL, &scoop_local_path, logger, m)?;
+    if installed.is_none() && available.is_empty() {
+        return Err(GetError::PackageNotFound(format!("'{}' is not installed and no source has it", name)));
+    }
+    print_package_info(name, installed.as_ref(), &available);
+    Ok(())
+}
 
-    // Define index file paths
-    let winget_index_path = repos_dir.join("winget_index.msgpack");
-    let scoop_index_path = repos_dir.join("scoop_index.msgpack");
+/// Check installed packages for newer versions, in the source they are
+/// pinned to or else the one they came from, within any hold
+fn find_outdated(records: &[InstalledPackage], config: &Config, logger: &Logger, m: &MultiProgress) -> Vec<OutdatedPackage> {
+    let mut lookup = SourceLookup::new(config, logger, m);
+    let mut outdated = Vec::new();
+    for record in records {
+        if SHOULD_TERMINATE.load(Ordering::SeqCst) {
+            break;
+        }
+        match check_for_update(&mut lookup, record, config) {
+            Ok(Some(package)) => outdated.push(package),
+            Ok(None) => {}
+            Err(e) => logger.warn(&format!("Could not check '{}' for updates: {}", record.name, e)),
+        }
+    }
+    outdated
+}
 
-    // Load or create Winget index
-    let winget_index = if winget_index_path.exists() {
-        logger.log("Loading Winget index from cache...");
-        let index_data = fs::read(&winget_index_path)?;
-        decode::from_read_ref(&index_data)?
-    } else {
-        let index = index_winget(&winget_local_path, logger, m)?;
-        let encoded = encode::to_vec(&index)?;
-        fs::write(&winget_index_path, &encoded)?;
-        index
+fn check_for_update(lookup: &mut SourceLookup, record: &InstalledPackage, config: &Config) -> Result<Option<OutdatedPackage>, GetError> {
+    // Some backends could not tell which version they installed
+    if record.version.is_empty() {
+        return Ok(None);
+    }
+    let installed = Version::parse(&record.version);
+    let hold = config.get_hold(&record.name).map(VersionReq::parse).transpose()?;
+    let requirement = hold.clone().unwrap_or_else(VersionReq::any);
+    let source = match config.get_pin(&record.name) {
+        Some(pinned) => Source::named(pinned, config)?,
+        None => recorded_source(&record.source, config),
+    };
+    // Scoop apps are looked up in the bucket they came from
+    let name = match scoop_record_bucket(record) {
+        Some(bucket) if source == Source::Scoop => format!("{}/{}", bucket, record.name),
+        _ => record.name.clone(),
     };
 
-    // Load or create Scoop index
-    let scoop_index = if scoop_index_path.exists() {
-        logger.log("Loading Scoop index from cache...");
-        let index_data = fs::read(&scoop_index_path)?;
-        decode::from_read_ref(&index_data)?
-    } else {
-        let index = index_scoop(&scoop_local_path, logger, m)?;
-        let encoded = encode::to_vec(&index)?;
-        fs::write(&scoop_index_path, &encoded)?;
-        index
+    let latest = match &source {
+        // Bundled packages, or a repository removed since, may be in any repository
+        Source::Repository(_) if source.repository(config).is_none() => config
+            .get_repositories()
+            .iter()
+            .filter_map(|repo| lookup.resolve_in(&Source::Repository(repo.name.clone()), &name, &requirement).ok())
+            .max_by_key(|package| Version::parse(&package.version))
+            .ok_or_else(|| GetError::PackageNotFound(format!("'{}' is in no configured repository", name)))?,
+        _ => lookup.resolve_in(&source, &name, &requirement)?,
     };
+    let outdated = |available: String, install_spec: Option<String>| OutdatedPackage {
+        name: record.name.clone(),
+        installed: record.version.clone(),
+        available,
+        source: latest.source.clone(),
+        install_spec,
+        held: hold.as_ref().map(VersionReq::to_string),
+    };
+    if Version::parse(&latest.version) > installed {
+        return Ok(Some(outdated(latest.version.clone(), Some(latest.install_spec.clone()))));
+    }
 
-    Ok((winget_index, scoop_index))
+    // Buckets trail releases; the manifest's own version check can see past them
+    if source == Source::Scoop {
+        let entry = find_scoop_entry(lookup.db()?, &name)?
+            .ok_or_else(|| GetError::PackageNotFound(format!("'{}' not found in any Scoop bucket", name)))?;
+        let manifest = read_manifest(Path::new(&entry.manifest_path))?;
+        if manifest.get("checkver").is_some() {
+            let current = manifest.get("version").and_then(Value::as_str).unwrap_or_default();
+            let checked = manifest_checkver(&manifest)?.check(current)?.version;
+            let version = Version::parse(&checked);
+            if version > installed && requirement.matches(&version) {
+                return Ok(Some(outdated(checked, None)));
+            }
+        }
+    }
+    Ok(None)
 }
 
-// -------------------- Search Manager --------------------
-
-// Winget Search Result
-#[derive(Debug)]
-struct WingetSearchResult {
-    PackageIdentifier: String,
-    PackageVersion: String,
-    Publisher: String,
-    ShortDescription: String,
+/// The bucket a Scoop install record came from
+fn scoop_record_bucket(record: &InstalledPackage) -> Option<&str> {
+    record.source.starts_with(&scoop_source("")).then(|| scoop_bucket(&record.source))
 }
 
-// Scoop Search Result
-#[derive(Debug)]
-struct ScoopSearchResult {
-    description: String,
-    version: String,
-}
+/// Upgrade the named packages, or every outdated one with `all`; a failed
+/// upgrade is rolled back to the version installed before
+fn upgrade_packages(packages: &[String], all: bool, config: &Config, logger: &Logger, m: &MultiProgress) -> Result<(), GetError> {
+    let db = PackageDb::open(&package_db_path(config))?;
+    let records = if all {
+        db.list_installed()?
+    } else {
+        packages
+            .iter()
+            .map(|name| {
+                db.installed(name)?
+                    .ok_or_else(|| GetError::PackageNotFound(format!("'{}' was not installed by get", name)))
+            })
+            .collect::<Result<Vec<_>, _>>()?
+    };
 
-fn search_package(
-    query: &str,
-    logger: &Logger,
-    config: &Config,
-    m: &MultiProgress,
-) -> Result<(), GetError> {
-    if SHOULD_TERMINATE.load(Ordering::SeqCst) {
-        return Err(GetError::InvalidInput("Operation terminated by user.".to_string()));
+    let outdated = find_outdated(&records, config, logger, m);
+    if outdated.is_empty() {
+        logger.info("All packages are up to date.");
+        return Ok(());
     }
 
-    logger.log("Starting search across Winget and Scoop repositories...");
-
-    // Load or create indexes
-    let (winget_index, scoop_index) = load_or_create_indexes(config, logger, m)?;
-
-    // Perform search
-    logger.log("Searching in Winget index...");
-    let winget_results: Vec<WingetSearchResult> = winget_index
-        .par_iter()
-        .filter(|entry| {
-            entry.PackageIdentifier.to_lowercase().contains(&query.to_lowercase())
-                || entry.ShortDescription.as_ref().map_or(false, |desc| desc.to_lowercase().contains(&query.to_lowercase()))
-        })
-        .map(|entry| WingetSearchResult {
-            PackageIdentifier: entry.PackageIdentifier.clone(),
-            PackageVersion: entry.PackageVersion.clone(),
-            Publisher: entry.Publisher.clone().unwrap_or_else(|| "Unknown".to_string()),
-            ShortDescription: entry.ShortDescription.clone().unwrap_or_else(|| "".to_string()),
-        })
-        .collect();
-
-    logger.log("Searching in Scoop index...");
-    let scoop_results: Vec<ScoopSearchResult> = scoop_index
-        .par_iter()
-        .filter(|entry| {
-            entry.description.to_lowercase().contains(&query.to_lowercase())
-        })
-        .map(|entry| ScoopSearchResult {
-            description: entry.description.clone(),
-            version: entry.version.clone(),
-        })
-        .collect();
+    let mut failed = Vec::new();
+    for package in &outdated {
+        if SHOULD_TERMINATE.load(Ordering::SeqCst) {
+            return Err(GetError::InvalidInput("Operation terminated by user.".to_string()));
+        }
+        let Some(install_spec) = &package.install_spec else {
+            logger.warn(&format!(
+                "'{}' {} is out, but {} has no manifest for it yet.",
+                package.name, package.available, package.source
+            ));
+            continue;
+        };
+        let previous = records
+            .iter()
+            .find(|record| record.name == package.name)
+            .expect("outdated packages come from the records");
+
+        logger.info(&format!("Upgrading '{}' {} -> {}...", package.name, package.installed, package.available));
+        match install_package(install_spec, config, logger, m) {
+            Ok(report) => print_install_report(&report, logger),
+            Err(e) => {
+                logger.error(&format!("Upgrading '{}' failed: {}", package.name, e));
+                roll_back(previous, &package.available, config, logger, m);
+                failed.push(package.name.clone());
+            }
+        }
+    }
 
-    // Display results
-    println!("\nSearch Results for '{}':\n", query);
-    if winget_results.is_empty() && scoop_results.is_empty() {
-        println!("No results found.");
+    if failed.is_empty() {
+        Ok(())
     } else {
-        if !winget_results.is_empty() {
-            println!("Winget Packages:");
-            for pkg in winget_results {
-                println!(
-                    "  - {} (Version: {}) by {}\n    Description: {}",
-                    pkg.PackageIdentifier,
-                    pkg.PackageVersion,
-                    pkg.Publisher,
-                    pkg.ShortDescription
-                );
+        Err(GetError::CommandError(format!("Failed to upgrade {}.", failed.join(", "))))
+    }
+}
+
+/// Put back the version a failed upgrade to `attempted` was replacing
+fn roll_back(previous: &InstalledPackage, attempted: &str, config: &Config, logger: &Logger, m: &MultiProgress) {
+    // Portable apps switch `current` last; if it was not switched, only the
+    // new version's directory is left to clean up
+    let apps = Apps::from_config(config);
+    let app = previous.location.as_deref().and_then(Path::parent).and_then(Path::file_name).and_then(|n| n.to_str());
+    if let Some(app) = app.filter(|app| apps.current_version(app).as_deref() == Some(previous.version.as_str())) {
+        if attempted != previous.version {
+            match validate_path_component("version", attempted) {
+                Ok(()) => {
+                    let _ = fs::remove_dir_all(apps.version_dir(app, attempted));
+                }
+                Err(e) => logger.warn(&format!("Not removing what is left of '{}' {}: {}", previous.name, attempted, e)),
             }
         }
+        logger.info(&format!("'{}' {} was left in place.", previous.name, previous.version));
+        return;
+    }
 
-        if !scoop_results.is_empty() {
-            println!("\nScoop Packages:");
-            for pkg in scoop_results {
-                println!(
-                    "  - {} (Version: {})",
-                    pkg.description, pkg.version
-                );
+    logger.info(&format!("Rolling back '{}' to {}...", previous.name, previous.version));
+    let source = recorded_source(&previous.source, config);
+    let name = match scoop_record_bucket(previous) {
+        Some(bucket) => format!("{}/{}", bucket, previous.name),
+        None => previous.name.clone(),
+    };
+    let reinstalled = match &source {
+        Source::Repository(_) if source.repository(config).is_none() => Err(GetError::PackageNotFound(format!(
+            "'{}' came from '{}', which can not be fetched again",
+            previous.name, previous.source
+        ))),
+        _ => install_package(&format!("{}:{}@{}", source.name(), name, previous.version), config, logger, m),
+    };
+    match reinstalled {
+        Ok(_) => logger.info(&format!("'{}' is back at {}.", previous.name, previous.version)),
+        Err(e) => {
+            logger.error(&format!("Rolling back '{}' failed: {}", previous.name, e));
+            // The record still names what was installed, so `get upgrade` can try again
+            if let Err(e) = PackageDb::open(&package_db_path(config)).and_then(|db| db.record_install(previous)) {
+                logger.warn(&format!("Could not restore the install record of '{}': {}", previous.name, e));
             }
         }
     }
+}
 
-    Ok(())
+/// List the `get-<name>` plugins on the PATH
+fn list_plugins(config: &Config) {
+    let plugins = discover_plugins();
+    if plugins.is_empty() {
+        println!("No plugins found. Plugins are executables named 'get-<name>' on the PATH.");
+        return;
+    }
+    println!("\nPlugins:\n");
+    for plugin in plugins {
+        // Built-in backends and repositories take the name first
+        let shadowed = Source::backend(&plugin.name).is_some()
+            || config.get_repositories().iter().any(|r| r.name.eq_ignore_ascii_case(&plugin.name));
+        println!(
+            "  - {} ({}){}",
+            plugin.name,
+            plugin.path.display(),
+            if shadowed { " [shadowed by a built-in source]" } else { "" }
+        );
+    }
 }
 
+/// List the packages that put `command` on the PATH
+fn find_providers(
+    command: &str,
+    logger: &Logger,
+    config: &Config,
+    m: &MultiProgress,
+) -> Result<(), GetError> {
+    let winget_source = load_winget_source(config, logger, m)?;
+    let db = load_package_db(config, logger, m)?;
+
+    let mut winget_providers: Vec<WingetSourceEntry> = Vec::new();
+    for entry in winget_source.provides(command)? {
+        match winget_providers.iter_mut().find(|e| e.identifier == entry.identifier) {
+            Some(existing) if Version::parse(&entry.version) > Version::parse(&existing.version) => *existing = entry,
+            Some(_) => {}
+            None => winget_providers.push(entry),
+        }
+    }
+    let mut scoop_providers: Vec<DbEntry> = Vec::new();
+    for entry in db.provides(command)? {
+        if !scoop_providers.iter().any(|e| e.source == entry.source && e.name == entry.name) {
+            scoop_providers.push(entry);
+        }
+    }
 
+    println!("\nPackages providing '{}':\n", command);
+    if winget_providers.is_empty() && scoop_providers.is_empty() {
+        println!("No packages found.");
+        return Ok(());
+    }
+    for entry in winget_providers {
+        println!("  - winget:{} (Version: {})", entry.identifier, entry.version);
+    }
+    for entry in scoop_providers {
+        println!("  - scoop:{}/{} (Version: {})", scoop_bucket(&entry.source), entry.name, entry.version);
+    }
+    Ok(())
+}
 
 fn handle_winget_install(
-    manifest: &WingetIndexEntry,
+    manifest: &WingetPackage,
     config: &Config,
     logger: &Logger,
     m: &MultiProgress,
 ) -> Result<(), GetError> {
-    // Iterate through installers and choose the appropriate one
-    for installer in &manifest.Installers {
+    // Choose the installer matching this machine's architecture
+    if let Some(installer) = winget::select_installer(&manifest.installers) {
         if SHOULD_TERMINATE.load(Ordering::SeqCst) {
             return Err(GetError::InvalidInput("Operation terminated by user.".to_string()));
         }
 
         logger.log(&format!(
             "Processing installer: Type={:?}, URL={}",
-            installer.InstallerType, installer.InstallerUrl
+            installer.installer_type, installer.installer_url
         ));
         // Download the installer
-        let installer_path = download_installer(&installer.InstallerUrl, &config.get_download_dir(), logger, m)?;
+        let installer_path = download_installer(&installer.installer_url, &config.get_download_dir(), logger, m)?;
 
         // Verify SHA256 checksum
         // let checksum_pb = m.add(ProgressBar::new_spinner());
         // checksum_pb.set_message("Verifying checksum...");
         // checksum_pb.enable_steady_tick(Duration::from_millis(100));
-        // verify_checksum(&installer_path, &installer.InstallerSha256, logger)?;
+        // verify_checksum(&installer_path, &installer.installer_sha256, logger)?;
         // checksum_pb.finish_with_message("Checksum verification passed.");
 
         // Determine silent flags
-        let silent_flags = if let Some(ref switches) = installer.InstallerSwitches {
-            switches.Silent.clone().unwrap_or_else(|| {
-                match installer.InstallerType.as_deref() {
+        let silent_flags = if let Some(ref switches) = installer.installer_switches {
+            switches.silent.clone().unwrap_or_else(|| {
+                match installer.installer_type.as_deref() {
                     Some("msi") => "/quiet /norestart".to_string(),
                     Some("nullsoft") => "/S".to_string(),
                     Some("exe") => "/S".to_string(),
@@ -607,7 +1782,7 @@ fn handle_winget_install(
                 }
             })
         } else {
-            match installer.InstallerType.as_deref() {
+            match installer.installer_type.as_deref() {
                 Some("msi") => "/quiet /norestart".to_string(),
                 Some("nullsoft") => "/S".to_string(),
                 Some("exe") => "/S".to_string(),
@@ -621,14 +1796,12 @@ fn handle_winget_install(
         install_pb.enable_steady_tick(Duration::from_millis(100));
         execute_installer(&installer_path, &silent_flags, logger)?;
         install_pb.finish_with_message("Installer executed successfully.");
-
-        logger.info(&format!("Package '{}' installed successfully.", manifest.PackageIdentifier));
         return Ok(());
     }
 
     Err(GetError::CommandError(format!(
         "No suitable installer found for package '{}'.",
-        manifest.PackageIdentifier
+        manifest.identifier
     )))
 }
 
@@ -643,17 +1816,28 @@ fn handle_scoop_install(
     }
 
     // Download the installer
-    let installer_path = download_installer(&manifest.url, &config.get_download_dir(), logger, m)?;
+    let (Some(url), Some(hash)) = (manifest.url.first(), manifest.hash.first()) else {
+        return Err(GetError::ValidationError(format!("'{}' has no download for this machine", manifest.name)));
+    };
+    let installer_path = download_installer(url, &config.get_download_dir(), logger, m)?;
 
     // Verify hash
     let verify_pb = m.add(ProgressBar::new_spinner());
     verify_pb.set_message("Verifying hash...");
     verify_pb.enable_steady_tick(Duration::from_millis(100));
-    verify_checksum(&installer_path, &manifest.hash, logger)?;
+    verify_checksum(&installer_path, hash, logger)?;
     verify_pb.finish_with_message("Hash verification passed.");
 
     // Execute the installer script if available
     if let Some(installer) = &manifest.installer {
+        #[cfg(not(target_os = "windows"))]
+        {
+            return Err(GetError::InvalidInput(
+                "Scoop installation scripts are only supported on Windows.".to_string(),
+            ));
+        }
+
+        #[cfg(target_os = "windows")]
         for script in &installer.script {
             if SHOULD_TERMINATE.load(Ordering::SeqCst) {
                 return Err(GetError::InvalidInput("Operation terminated by user.".to_string()));
@@ -664,27 +1848,16 @@ fn handle_scoop_install(
             exec_pb.set_message("Executing installer script...");
             exec_pb.enable_steady_tick(Duration::from_millis(100));
 
-            // For simplicity, execute the script using PowerShell on Windows
-            #[cfg(target_os = "windows")]
-            {
-                let status = Command::new("powershell")
-                    .args(&["-NoProfile", "-Command", script])
-                    .status()?;
-                if !status.success() {
-                    exec_pb.finish_and_clear();
-                    return Err(GetError::CommandError(format!(
-                        "Failed to execute installer script for package '{}'.",
-                        manifest.description
-                    )));
-                }
-            }
-
-            #[cfg(not(target_os = "windows"))]
-            {
+            // For simplicity, execute the script using PowerShell
+            let status = Command::new("powershell")
+                .args(["-NoProfile", "-Command", script])
+                .status()?;
+            if !status.success() {
                 exec_pb.finish_and_clear();
-                return Err(GetError::InvalidInput(
-                    "Scoop installation scripts are only supported on Windows.".to_string(),
-                ));
+                return Err(GetError::CommandError(format!(
+                    "Failed to execute installer script for package '{}'.",
+                    manifest.name
+                )));
             }
 
             exec_pb.finish_with_message("Installer script executed successfully.");
@@ -699,7 +1872,7 @@ fn handle_scoop_install(
         exec_pb.finish_with_message("Installer executed successfully.");
     }
 
-    logger.info(&format!("Package '{}' installed successfully.", manifest.description));
+    logger.info(&format!("Package '{}' installed successfully.", manifest.name));
     Ok(())
 }
 
@@ -707,70 +1880,89 @@ fn handle_scoop_install(
 
 fn uninstall_package(
     package: &str,
+    config: &Config,
     logger: &Logger,
     m: &MultiProgress,
-) -> Result<(), GetError> {
+) -> Result<UninstallReport, GetError> {
     if SHOULD_TERMINATE.load(Ordering::SeqCst) {
         return Err(GetError::InvalidInput("Operation terminated by user.".to_string()));
     }
 
-    // Load or create indexes
-    logger.info(&format!("Searching for package '{}' in repositories for uninstallation...", package));
-    let config = Config::load();
-    let (winget_index, scoop_index) = load_or_create_indexes(&config, logger, m)?;
-
-    let winget_results: Vec<&WingetIndexEntry> = winget_index
-        .par_iter()
-        .filter(|entry| {
-            entry.PackageIdentifier.to_lowercase() == package.to_lowercase()
-        })
-        .collect();
-
-    let scoop_results: Vec<&ScoopIndexEntry> = scoop_index
-        .par_iter()
-        .filter(|entry| {
-            entry.description.to_lowercase().contains(&package.to_lowercase())
-        })
-        .collect();
-
-    if winget_results.is_empty() && scoop_results.is_empty() {
-        return Err(GetError::InvalidInput(format!(
-            "Package '{}' not found in Winget or Scoop repositories.",
-            package
-        )));
-    }
-
-    // Prioritize Winget results
-    if !winget_results.is_empty() {
-        logger.log("Attempting to uninstall using Winget manifests.");
-        for manifest in winget_results {
-            if manifest.PackageIdentifier.to_lowercase() == package.to_lowercase()
-            {
-                logger.log(&format!("Found package '{}' in Winget.", manifest.PackageIdentifier));
-                return handle_winget_uninstall(&manifest, logger, m);
-            }
+    // What `get` installed is removed by the backend that installed it
+    let (qualifier, unqualified) = split_source_qualifier(package, config)?;
+    if qualifier.is_none() {
+        let (name, _) = split_package_spec(unqualified);
+        if let Some(record) = PackageDb::open(&package_db_path(config))?.installed(name)? {
+            logger.log(&format!("'{}' {} was installed from '{}'.", record.name, record.version, record.source));
+            return backend_for(&recorded_source(&record.source, config))?.uninstall(package, config, logger, m);
         }
     }
 
-    // If not found in Winget, try Scoop
-    if !scoop_results.is_empty() {
-        logger.log("Attempting to uninstall using Scoop manifests.");
-        for manifest in scoop_results {
-            if manifest.description.to_lowercase().contains(&package.to_lowercase()) {
-                logger.log(&format!("Found package '{}' in Scoop.", manifest.description));
-                return handle_scoop_uninstall(&manifest, logger, m);
+    let (package, choices) = plan_sources(package, config)?;
+
+    // Repository installs are all removed the same way, from their install record
+    let mut tried_repositories = false;
+    let mut tried = Vec::new();
+    logger.info(&format!("Searching for package '{}' for uninstallation...", package));
+    for choice in &choices {
+        let manager = match choice.source {
+            Source::Repository(_) if tried_repositories => continue,
+            Source::Repository(_) => {
+                tried_repositories = true;
+                backend_for(&choice.source)?
             }
+            // Chocolatey packages are removed with `choco uninstall`
+            Source::Chocolatey => continue,
+            _ => backend_for(&choice.source)?,
+        };
+
+        logger.log(&format!("Trying {} ({}).", choice.source, choice.reason));
+        tried.push(choice.source.to_string());
+        match manager.uninstall(package, config, logger, m) {
+            Err(GetError::PackageNotFound(_)) => logger.log(&format!("'{}' not found in {}.", package, choice.source)),
+            result => return result,
         }
     }
 
+    if tried.is_empty() {
+        return Err(GetError::InvalidInput(format!(
+            "Uninstalling '{}' is not supported for {}.",
+            package,
+            choices.iter().map(|c| c.source.to_string()).collect::<Vec<_>>().join(" or ")
+        )));
+    }
     Err(GetError::InvalidInput(format!(
-        "Package '{}' not found or uninstallation criteria not met.",
-        package
+        "Package '{}' not found in {}.",
+        package,
+        tried.join(" or ")
     )))
 }
 
+/// The backend for a source
+fn backend_for(source: &Source) -> Result<Box<dyn PackageManager>, GetError> {
+    Ok(match source {
+        Source::Repository(_) => Box::new(UniversalPackageManager),
+        Source::Chocolatey => Box::new(ChocoManager),
+        Source::Scoop => Box::new(ScoopManager),
+        Source::Winget => Box::new(WingetManager),
+        Source::Native(kind) => Box::new(NativeManager::new(*kind)),
+        Source::Ecosystem(ecosystem) => Box::new(EcosystemManager::new(*ecosystem)),
+        Source::GitHub => Box::new(GitHubManager),
+        Source::Plugin(name) => Box::new(Plugin::named(name)?),
+    })
+}
+
+/// The source named by an install record. Bundles, and repositories removed
+/// since, map to the repository backend, which works from the recorded manifest
+fn recorded_source(source: &str, config: &Config) -> Source {
+    if source.starts_with(&scoop_source("")) {
+        return Source::Scoop;
+    }
+    Source::named(source, config).unwrap_or_else(|_| Source::Repository(source.to_string()))
+}
+
 fn handle_winget_uninstall(
-    manifest: &WingetIndexEntry,
+    manifest: &WingetSourceEntry,
     logger: &Logger,
     m: &MultiProgress,
 ) -> Result<(), GetError> {
@@ -784,7 +1976,7 @@ fn handle_winget_uninstall(
 
         let uninstall_cmd = format!(
             "Get-Package -Name '{}' | Uninstall-Package -Force -Confirm:$false",
-            manifest.PackageIdentifier
+            manifest.identifier
         );
 
         let uninstall_pb = m.add(ProgressBar::new_spinner());
@@ -798,12 +1990,11 @@ fn handle_winget_uninstall(
         uninstall_pb.finish_with_message("Uninstallation command executed.");
 
         if status.success() {
-            logger.info(&format!("Package '{}' uninstalled successfully.", manifest.PackageIdentifier));
             Ok(())
         } else {
             Err(GetError::CommandError(format!(
                 "Failed to uninstall package '{}'.",
-                manifest.PackageIdentifier
+                manifest.identifier
             )))
         }
     }
@@ -827,6 +2018,14 @@ fn handle_scoop_uninstall(
 
     // Execute the uninstallation script if available
     if let Some(installer) = &manifest.installer {
+        #[cfg(not(target_os = "windows"))]
+        {
+            return Err(GetError::InvalidInput(
+                "Scoop uninstallation scripts are only supported on Windows.".to_string(),
+            ));
+        }
+
+        #[cfg(target_os = "windows")]
         for script in &installer.script {
             if SHOULD_TERMINATE.load(Ordering::SeqCst) {
                 return Err(GetError::InvalidInput("Operation terminated by user.".to_string()));
@@ -837,27 +2036,16 @@ fn handle_scoop_uninstall(
             exec_pb.set_message("Executing uninstaller script...");
             exec_pb.enable_steady_tick(Duration::from_millis(100));
 
-            // For simplicity, execute the script using PowerShell on Windows
-            #[cfg(target_os = "windows")]
-            {
-                let status = Command::new("powershell")
-                    .args(&["-NoProfile", "-Command", script])
-                    .status()?;
-                if !status.success() {
-                    exec_pb.finish_and_clear();
-                    return Err(GetError::CommandError(format!(
-                        "Failed to execute uninstaller script for package '{}'.",
-                        manifest.description
-                    )));
-                }
-            }
-
-            #[cfg(not(target_os = "windows"))]
-            {
+            // For simplicity, execute the script using PowerShell
+            let status = Command::new("powershell")
+                .args(["-NoProfile", "-Command", script])
+                .status()?;
+            if !status.success() {
                 exec_pb.finish_and_clear();
-                return Err(GetError::InvalidInput(
-                    "Scoop uninstallation scripts are only supported on Windows.".to_string(),
-                ));
+                return Err(GetError::CommandError(format!(
+                    "Failed to execute uninstaller script for package '{}'.",
+                    manifest.name
+                )));
             }
 
             exec_pb.finish_with_message("Uninstaller script executed successfully.");
@@ -870,7 +2058,6 @@ fn handle_scoop_uninstall(
         ));
     }
 
-    logger.info(&format!("Package '{}' uninstalled successfully.", manifest.description));
     Ok(())
 }
 
@@ -901,7 +2088,7 @@ fn download_installer(url: &str, download_dir: &Path, logger: &Logger, m: &Multi
 
     let url_path = url
         .split('/')
-        .last()
+        .next_back()
         .ok_or_else(|| GetError::InvalidInput("Invalid URL.".to_string()))?;
     let file_path = download_dir.join(url_path);
 
@@ -1060,7 +2247,7 @@ fn clone_repository(repo_url: &str, logger: &Logger, m: &MultiProgress) -> Resul
     pb.set_message(format!("Cloning repository from '{}'.", repo_url));
     pb.enable_steady_tick(Duration::from_millis(100));
     let status = Command::new("git")
-        .args(&["clone", repo_url])
+        .args(["clone", repo_url])
         .status()?;
 
     if status.success() {
@@ -1109,17 +2296,29 @@ fn main() {
 
     // Determine required dependencies based on command
     let required_dependencies = match &command {
-        CommandType::Install(_) => vec!["git"],
+        CommandType::Install { .. } => vec!["git"],
+        CommandType::InstallBundle { .. } => vec![],
         CommandType::Uninstall(_) => vec!["git"],
+        CommandType::List { .. } => vec![],
+        CommandType::Info(_) => vec!["git"],
+        CommandType::Outdated => vec!["git"],
+        CommandType::Upgrade { .. } => vec!["git"],
         CommandType::Clone(_) => vec!["git"],
         CommandType::Download(_) => vec![],
         CommandType::Search(_) => vec!["git"],
+        CommandType::Provides(_) => vec!["git"],
+        CommandType::Deps(_) => vec!["git"],
+        CommandType::Plugins => vec![],
         CommandType::Auth => vec![],
+        CommandType::Bucket(_) => vec!["git"],
+        CommandType::Repo(_) => vec![],
+        CommandType::Bundle(_) => vec![],
+        CommandType::Manifest(_) => vec![],
     };
 
     // Ensure dependencies
     for dep in required_dependencies {
-        if let CommandType::Install(_) | CommandType::Uninstall(_) | CommandType::Clone(_) | CommandType::Search(_) = &command {
+        if let CommandType::Install { .. } | CommandType::Uninstall(_) | CommandType::Info(_) | CommandType::Outdated | CommandType::Upgrade { .. } | CommandType::Clone(_) | CommandType::Search(_) | CommandType::Provides(_) | CommandType::Deps(_) | CommandType::Bucket(_) = &command {
             if let Err(e) = ensure_dependency(dep, &logger, &m) {
                 logger.error(&match e {
                     GetError::MissingDependency(msg) => msg,
@@ -1133,8 +2332,8 @@ fn main() {
 
     // Execute command
     match command {
-        CommandType::Install(package) => {
-            if let Err(e) = install_package(&package, &config, &logger, &m) {
+        CommandType::Install { package, no_deps } => {
+            if let Err(e) = install_with_dependencies(&package, no_deps, &config, &logger, &m) {
                 logger.error(&match e {
                     GetError::CommandError(msg) => msg,
                     GetError::MissingDependency(msg) => msg,
@@ -1144,13 +2343,66 @@ fn main() {
                 std::process::exit(1);
             }
         }
+        CommandType::InstallBundle { bundle, packages } => {
+            if let Err(e) = install_from_bundle(&bundle, &packages, &config, &logger, &m) {
+                logger.error(&match e {
+                    GetError::CommandError(msg) => msg,
+                    GetError::InvalidInput(msg) => msg,
+                    GetError::PackageNotFound(msg) => msg,
+                    GetError::ParseError(msg) => msg,
+                    _ => format!("Failed to install from bundle '{}'.\n reason: {}", bundle.display(), e),
+                });
+                std::process::exit(1);
+            }
+        }
         CommandType::Uninstall(package) => {
-            if let Err(e) = uninstall_package(&package, &logger, &m) {
+            match uninstall_package(&package, &config, &logger, &m) {
+                Ok(report) => print_uninstall_report(&report, &logger),
+                Err(e) => {
+                    logger.error(&match e {
+                        GetError::CommandError(msg) => msg,
+                        GetError::MissingDependency(msg) => msg,
+                        GetError::InvalidInput(msg) => msg,
+                        GetError::PackageNotFound(msg) => msg,
+                        _ => format!("Failed to uninstall package '{}'.\n reason: {}", package, e),
+                    });
+                    std::process::exit(1);
+                }
+            }
+        }
+        CommandType::List { filter, source, json } => {
+            if let Err(e) = list_installed(filter.as_deref(), source.as_deref(), json, &config) {
+                logger.error(&match e {
+                    GetError::DatabaseError(msg) => msg,
+                    _ => format!("Failed to list installed packages.\n reason: {}", e),
+                });
+                std::process::exit(1);
+            }
+        }
+        CommandType::Info(package) => {
+            if let Err(e) = show_info(&package, &config, &logger, &m) {
+                logger.error(&match e {
+                    GetError::PackageNotFound(msg) => msg,
+                    GetError::InvalidInput(msg) => msg,
+                    _ => format!("Failed to show '{}'.\n reason: {}", package, e),
+                });
+                std::process::exit(1);
+            }
+        }
+        CommandType::Outdated => match PackageDb::open(&package_db_path(&config)).and_then(|db| db.list_installed()) {
+            Ok(records) => print_outdated(&find_outdated(&records, &config, &logger, &m)),
+            Err(e) => {
+                logger.error(&format!("Failed to read installed packages.\n reason: {}", e));
+                std::process::exit(1);
+            }
+        },
+        CommandType::Upgrade { packages, all } => {
+            if let Err(e) = upgrade_packages(&packages, all, &config, &logger, &m) {
                 logger.error(&match e {
+                    GetError::PackageNotFound(msg) => msg,
                     GetError::CommandError(msg) => msg,
-                    GetError::MissingDependency(msg) => msg,
                     GetError::InvalidInput(msg) => msg,
-                    _ => "Failed to uninstall package.".to_string(),
+                    _ => format!("Failed to upgrade packages.\n reason: {}", e),
                 });
                 std::process::exit(1);
             }
@@ -1166,6 +2418,28 @@ fn main() {
                 std::process::exit(1);
             }
         }
+        CommandType::Provides(command) => {
+            if let Err(e) = find_providers(&command, &logger, &config, &m) {
+                logger.error(&match e {
+                    GetError::NetworkError(msg) => msg,
+                    GetError::DatabaseError(msg) => msg,
+                    _ => "Failed to look up providers.".to_string(),
+                });
+                std::process::exit(1);
+            }
+        }
+        CommandType::Deps(package) => {
+            if let Err(e) = show_dependencies(&package, &config, &logger, &m) {
+                logger.error(&match e {
+                    GetError::ValidationError(msg) => msg,
+                    GetError::PackageNotFound(msg) => format!("Package not found: {}", msg),
+                    GetError::InvalidInput(msg) => msg,
+                    _ => format!("Failed to resolve dependencies of '{}'.\n reason: {}", package, e),
+                });
+                std::process::exit(1);
+            }
+        }
+        CommandType::Plugins => list_plugins(&config),
         CommandType::Clone(repo_url) => {
             if let Err(e) = clone_repository(&repo_url, &logger, &m) {
                 logger.error(&match e {
@@ -1199,8 +2473,400 @@ fn main() {
                 std::process::exit(1);
             }
         }
+        CommandType::Bucket(bucket_command) => {
+            let result = match bucket_command {
+                BucketCommand::Add(name, url) => {
+                    add_bucket(&name, url.as_deref(), &mut config, &logger, &m).and_then(|()| config.save())
+                }
+                BucketCommand::Remove(name) => remove_bucket(&name, &mut config, &logger).and_then(|()| config.save()),
+                BucketCommand::List => {
+                    list_buckets(&config);
+                    Ok(())
+                }
+            };
+            if let Err(e) = result {
+                logger.error(&match e {
+                    GetError::CommandError(msg) => msg,
+                    GetError::InvalidInput(msg) => msg,
+                    _ => format!("Failed to manage buckets.\n reason: {}", e),
+                });
+                std::process::exit(1);
+            }
+        }
+        CommandType::Bundle(BundleCommand::Create { packages, output }) => {
+            if let Err(e) = create_bundle(&packages, &output, &config, &logger, &m) {
+                logger.error(&match e {
+                    GetError::ConfigError(msg) => msg,
+                    GetError::InvalidInput(msg) => msg,
+                    GetError::PackageNotFound(msg) => format!("Package '{}' not found in any repository.", msg),
+                    _ => format!("Failed to create bundle.\n reason: {}", e),
+                });
+                std::process::exit(1);
+            }
+        }
+        CommandType::Manifest(manifest_command) => {
+            let result = match manifest_command {
+                ManifestCommand::CheckVer(files) => checkver_manifests(&files, &logger),
+                ManifestCommand::AutoUpdate(files) => autoupdate_manifests(&files, &logger, &m),
+            };
+            if let Err(e) = result {
+                logger.error(&match e {
+                    GetError::CommandError(msg) => msg,
+                    _ => format!("Failed to update manifests.\n reason: {}", e),
+                });
+                std::process::exit(1);
+            }
+        }
+        CommandType::Repo(RepoCommand::Build { manifests, out }) => {
+            if let Err(e) = build_static_repository(&manifests, &out, &logger, &m) {
+                logger.error(&match e {
+                    GetError::ValidationError(msg) => format!("Invalid manifests: {}", msg),
+                    GetError::InvalidInput(msg) => msg,
+                    GetError::ParseError(msg) => msg,
+                    _ => format!("Failed to build repository.\n reason: {}", e),
+                });
+                std::process::exit(1);
+            }
+        }
     }
 
     // Wait for all progress bars to finish
     // m.join().unwrap();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indicatif::ProgressDrawTarget;
+
+    fn config_in(home: &Path) -> Config {
+        Config { home: Some(home.to_string_lossy().into_owned()), ..Default::default() }
+    }
+
+    fn quiet() -> (Logger, MultiProgress) {
+        (Logger::new(LogLevel::Minimal), MultiProgress::with_draw_target(ProgressDrawTarget::hidden()))
+    }
+
+    /// Put `version` of a one-program app in place, like a portable install
+    fn install_app(apps: &Apps, dir: &Path, version: &str) -> PathBuf {
+        let program = dir.join("tool");
+        fs::write(&program, version).unwrap();
+        apps.unpack("tool", version, &program, None, 0, "tool").unwrap();
+        apps.set_current("tool", version).unwrap()
+    }
+
+    fn record(version: &str, source: &str, location: Option<PathBuf>) -> InstalledPackage {
+        InstalledPackage {
+            name: "tool".to_string(),
+            version: version.to_string(),
+            source: source.to_string(),
+            installed_at: SystemTime::now(),
+            manifest: "{}".to_string(),
+            location,
+            files: Vec::new(),
+            shims: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn rolling_back_an_unswitched_upgrade_removes_only_the_new_version() {
+        let home = tempfile::tempdir().unwrap();
+        let config = config_in(home.path());
+        let apps = Apps::from_config(&config);
+        let (logger, m) = quiet();
+        let current = install_app(&apps, home.path(), "1.0");
+        // The upgrade failed after unpacking, before switching `current`
+        apps.prepare_version("tool", "2.0").unwrap();
+
+        roll_back(&record("1.0", "scoop/main", Some(current.clone())), "2.0", &config, &logger, &m);
+        assert_eq!(apps.versions("tool"), ["1.0"]);
+        assert_eq!(apps.current_version("tool").as_deref(), Some("1.0"));
+        assert_eq!(fs::read_to_string(current.join("tool")).unwrap(), "1.0");
+    }
+
+    #[test]
+    fn rolling_back_never_removes_outside_the_app() {
+        let home = tempfile::tempdir().unwrap();
+        let config = config_in(home.path());
+        let apps = Apps::from_config(&config);
+        let (logger, m) = quiet();
+        let current = install_app(&apps, home.path(), "1.0");
+
+        for attempted in ["..", "../..", "", "1.0/../.."] {
+            roll_back(&record("1.0", "scoop/main", Some(current.clone())), attempted, &config, &logger, &m);
+        }
+        assert!(apps.version_dir("tool", "1.0").join("tool").exists());
+        assert!(home.path().join("tool").exists());
+    }
+
+    #[test]
+    fn a_failed_roll_back_keeps_the_previous_record() {
+        let home = tempfile::tempdir().unwrap();
+        let config = config_in(home.path());
+        let apps = Apps::from_config(&config);
+        let (logger, m) = quiet();
+        install_app(&apps, home.path(), "1.0");
+        // The upgrade switched `current` and then failed
+        let current = install_app(&apps, home.path(), "2.0");
+        let db = PackageDb::open(&package_db_path(&config)).unwrap();
+        db.record_install(&record("2.0", "gone", Some(current.clone()))).unwrap();
+
+        // Its repository is no longer configured, so it can't be reinstalled
+        roll_back(&record("1.0", "gone", Some(current)), "2.0", &config, &logger, &m);
+        assert_eq!(db.installed("tool").unwrap().unwrap().version, "1.0");
+    }
+
+    #[test]
+    fn reads_scoop_manifests_leniently() {
+        let manifest: ScoopManifest = serde_json::from_str(
+            r#"{
+                "version": "1.2.0",
+                "license": { "identifier": "MIT", "url": "https://example.com" },
+                "url": ["https://example.com/a.zip", "https://example.com/b.zip#/b.7z"],
+                "hash": ["aa", "sha256:bb"],
+                "extract_dir": "a-1.2.0",
+                "env_add_path": ".",
+                "pre_install": "Write-Host hi",
+                "checkver": "github",
+                "autoupdate": { "architecture": { "64bit": { "url": "https://example.com/$version.zip" } } }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(manifest.url.len(), 2);
+        assert_eq!(manifest.hash, ["aa", "sha256:bb"]);
+        assert_eq!(manifest.extract_dir, ["a-1.2.0"]);
+        assert_eq!(manifest.env_add_path, ["."]);
+        assert_eq!(manifest.pre_install, ["Write-Host hi"]);
+        assert!(manifest.checkver.is_none());
+        assert!(manifest.autoupdate.is_none());
+    }
+
+    #[test]
+    fn applies_the_architecture_of_this_machine() {
+        let native = scoop_architectures()[0];
+        let manifest: ScoopManifest = serde_json::from_value(serde_json::json!({
+            "version": "1.0",
+            "url": "https://example.com/generic.zip",
+            "hash": "00",
+            "bin": "generic.exe",
+            "architecture": {
+                native: { "url": "https://example.com/native.zip", "hash": "11", "extract_dir": "native" },
+                "unknown": { "url": "https://example.com/unknown.zip", "hash": "22" }
+            }
+        }))
+        .unwrap();
+        let manifest = manifest.for_this_architecture();
+        assert_eq!(manifest.url, ["https://example.com/native.zip"]);
+        assert_eq!(manifest.hash, ["11"]);
+        assert_eq!(manifest.extract_dir, ["native"]);
+        assert_eq!(manifest.bin, Some(BinSpec::One("generic.exe".to_string())));
+    }
+
+    #[test]
+    fn falls_back_to_scoop_for_what_it_can_not_do() {
+        let entry = |manifest: serde_json::Value| {
+            let manifest: ScoopManifest = serde_json::from_value(manifest).unwrap();
+            ScoopIndexEntry {
+                name: "app".to_string(),
+                bucket: "main".to_string(),
+                manifest_path: PathBuf::from("app.json"),
+                version: manifest.version,
+                description: manifest.description,
+                homepage: manifest.homepage,
+                license: None,
+                url: manifest.url,
+                hash: manifest.hash,
+                extract_dir: manifest.extract_dir,
+                bin: manifest.bin,
+                env_add_path: manifest.env_add_path,
+                pre_install: manifest.pre_install,
+                post_install: manifest.post_install,
+                installer: manifest.installer,
+                checkver: None,
+                autoupdate: None,
+            }
+        };
+        let url = "https://example.com/app.zip";
+        assert!(portable_blocker(&entry(serde_json::json!({ "version": "1", "url": url, "hash": "00" }))).is_none());
+        for manifest in [
+            serde_json::json!({ "version": "1", "url": url, "hash": "00", "installer": { "script": "x" } }),
+            serde_json::json!({ "version": "1" }),
+            serde_json::json!({ "version": "1", "url": [url, url], "hash": "00" }),
+            serde_json::json!({ "version": "1", "url": url, "hash": "sha1:00" }),
+        ] {
+            assert!(portable_blocker(&entry(manifest.clone())).is_some(), "{}", manifest);
+        }
+    }
+
+    /// Serve `page` at every path until the test process exits
+    fn serve(page: &'static str) -> String {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", server.server_addr().to_ip().unwrap());
+        std::thread::spawn(move || {
+            for request in server.incoming_requests() {
+                let _ = request.respond(tiny_http::Response::from_string(page));
+            }
+        });
+        url
+    }
+
+    #[test]
+    fn sees_past_a_bucket_that_trails_the_checkver() {
+        let home = tempfile::tempdir().unwrap();
+        let config = Config { scoop_buckets: Some(Vec::new()), ..config_in(home.path()) };
+        let (logger, m) = quiet();
+        let homepage = serve("<p>Latest release: version 1.5</p>");
+        // A bare-string checkver is a regex for the homepage
+        let manifest = serde_json::json!({
+            "version": "1.0",
+            "homepage": homepage,
+            "url": "https://example.com/app.zip",
+            "hash": "00",
+            "checkver": r"version ([\d.]+)",
+        });
+        let manifest_path = home.path().join("app.json");
+        fs::write(&manifest_path, manifest.to_string()).unwrap();
+        let mut db = PackageDb::open(&package_db_path(&config)).unwrap();
+        db.replace_source("scoop/main", &[scoop_record("app".to_string(), &manifest_path, &manifest).unwrap()]).unwrap();
+
+        let installed = InstalledPackage { name: "app".to_string(), ..record("1.0", "scoop/main", None) };
+        let outdated = check_for_update(&mut SourceLookup::new(&config, &logger, &m), &installed, &config)
+            .unwrap()
+            .unwrap();
+        assert_eq!(outdated.available, "1.5");
+        assert_eq!(outdated.install_spec, None);
+    }
+
+    #[test]
+    fn lists_installs_by_name_and_source() {
+        let home = tempfile::tempdir().unwrap();
+        let config = config_in(home.path());
+        let db = PackageDb::open(&package_db_path(&config)).unwrap();
+        for (name, source) in [("ripgrep", "scoop/main"), ("RipGrep-all", "scoop/extras"), ("jq", "winget"), ("owner/tool", "github")] {
+            db.record_install(&InstalledPackage { name: name.to_string(), ..record("1.0", source, None) }).unwrap();
+        }
+        let names = |filter: Option<&str>, source: Option<&str>| {
+            let mut names: Vec<String> =
+                installed_matching(filter, source, &config).unwrap().into_iter().map(|p| p.name).collect();
+            names.sort();
+            names
+        };
+
+        assert_eq!(names(None, None).len(), 4);
+        assert_eq!(names(Some("RIP"), None), ["RipGrep-all", "ripgrep"]);
+        assert_eq!(names(None, Some("scoop")), ["RipGrep-all", "ripgrep"]);
+        assert_eq!(names(None, Some("scoop/extras")), ["RipGrep-all"]);
+        assert_eq!(names(None, Some("gh")), ["owner/tool"]);
+        assert_eq!(names(Some("rip"), Some("winget")), Vec::<String>::new());
+    }
+
+    #[test]
+    fn finds_scoop_apps_by_manifest_name_and_bucket() {
+        let home = tempfile::tempdir().unwrap();
+        let config = config_in(home.path());
+        let (logger, m) = quiet();
+        let bucket = |name: &str, apps: &[(&str, &str)]| {
+            let dir = home.path().join(name).join("bucket");
+            fs::create_dir_all(&dir).unwrap();
+            for (app, description) in apps {
+                let manifest = serde_json::json!({ "version": "1.0", "description": description, "url": "https://example.com/app.zip" });
+                fs::write(dir.join(format!("{}.json", app)), manifest.to_string()).unwrap();
+            }
+            fs::write(dir.join("README.md"), "not a manifest").unwrap();
+            index_scoop(&home.path().join(name), name, &logger, &m).unwrap()
+        };
+        let mut db = PackageDb::open(&package_db_path(&config)).unwrap();
+        let main = bucket("main", &[("git", "Distributed version control"), ("git-lfs", "Large files for git")]);
+        let mut names: Vec<_> = main.iter().map(|record| record.name.as_str()).collect();
+        names.sort();
+        assert_eq!(names, ["git", "git-lfs"]);
+        db.replace_source("scoop/main", &main).unwrap();
+        db.replace_source("scoop/extras", &bucket("extras", &[("gitui", "Terminal UI for git")])).unwrap();
+
+        let entry = find_scoop_entry(&db, "git").unwrap().unwrap();
+        assert_eq!((entry.name.as_str(), entry.source.as_str()), ("git", "scoop/main"));
+        let manifest = load_scoop_entry(&entry).unwrap();
+        assert_eq!((manifest.name.as_str(), manifest.bucket.as_str()), ("git", "main"));
+        assert_eq!(manifest.manifest_path, home.path().join("main/bucket/git.json"));
+
+        assert_eq!(find_scoop_entry(&db, "extras/gitui").unwrap().unwrap().name, "gitui");
+        assert!(find_scoop_entry(&db, "extras/git").unwrap().is_none());
+        assert!(find_scoop_entry(&db, "gi").unwrap().is_none());
+    }
+
+    /// A git repository laid out like a Scoop bucket, holding `apps`
+    fn bucket_repo(dir: &Path, apps: &[&str]) -> String {
+        fs::create_dir_all(dir.join("bucket")).unwrap();
+        for app in apps {
+            let manifest = serde_json::json!({ "version": "1.0", "description": format!("The {} app", app) });
+            fs::write(dir.join("bucket").join(format!("{}.json", app)), manifest.to_string()).unwrap();
+        }
+        let git = |args: &[&str]| {
+            let status = Command::new("git").arg("-C").arg(dir).args(args).stdout(Stdio::null()).status().unwrap();
+            assert!(status.success(), "git {:?}", args);
+        };
+        git(&["init", "-q"]);
+        git(&["add", "."]);
+        git(&["-c", "user.name=get", "-c", "user.email=get@localhost", "commit", "-qm", "Add apps"]);
+        dir.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn adds_indexes_and_removes_buckets() {
+        let home = tempfile::tempdir().unwrap();
+        let mut config = Config { scoop_buckets: Some(Vec::new()), ..config_in(home.path()) };
+        let (logger, m) = quiet();
+        let tools = bucket_repo(&home.path().join("tools-repo"), &["hammer", "saw"]);
+        let games = bucket_repo(&home.path().join("games-repo"), &["chess"]);
+
+        add_bucket("tools", Some(&tools), &mut config, &logger, &m).unwrap();
+        add_bucket("Games", Some(&games), &mut config, &logger, &m).unwrap();
+        let names: Vec<_> = config.get_scoop_buckets().into_iter().map(|bucket| bucket.name).collect();
+        assert_eq!(names, ["tools", "games"]);
+        assert!(add_bucket("tools", Some(&tools), &mut config, &logger, &m).is_err());
+        assert!(add_bucket("../tools", Some(&tools), &mut config, &logger, &m).is_err());
+        assert!(add_bucket("no-such-known-bucket", None, &mut config, &logger, &m).is_err());
+
+        let db = load_package_db(&config, &logger, &m).unwrap();
+        let hits = search_scoop(&db, "a").unwrap();
+        let mut found: Vec<_> = hits.iter().map(|hit| hit.name.as_str()).collect();
+        found.sort();
+        assert_eq!(found, ["games/chess", "tools/hammer", "tools/saw"]);
+        drop(db);
+
+        remove_bucket("tools", &mut config, &logger).unwrap();
+        assert!(!scoop::bucket_path(&config, "tools").exists());
+        let db = load_package_db(&config, &logger, &m).unwrap();
+        assert!(find_scoop_entry(&db, "hammer").unwrap().is_none());
+        assert_eq!(find_scoop_entry(&db, "chess").unwrap().unwrap().source, "scoop/games");
+        assert!(remove_bucket("tools", &mut config, &logger).is_err());
+    }
+
+    #[test]
+    fn scoop_depends_feed_the_dependency_graph() {
+        let home = tempfile::tempdir().unwrap();
+        let config = Config { scoop_buckets: Some(Vec::new()), ..config_in(home.path()) };
+        let (logger, m) = quiet();
+        let mut db = PackageDb::open(&package_db_path(&config)).unwrap();
+        let mut index = |bucket: &str, apps: &[(&str, serde_json::Value)]| {
+            let records: Vec<_> = apps
+                .iter()
+                .map(|(app, depends)| {
+                    let manifest = serde_json::json!({ "version": "1.0", "depends": depends });
+                    let path = home.path().join(format!("{}-{}.json", bucket, app));
+                    fs::write(&path, manifest.to_string()).unwrap();
+                    scoop_record(app.to_string(), &path, &manifest).unwrap()
+                })
+                .collect();
+            db.replace_source(&scoop_source(bucket), &records).unwrap();
+        };
+        index("main", &[("app", serde_json::json!(["extras/lib", "helper"])), ("helper", serde_json::Value::Null)]);
+        index("extras", &[("lib", serde_json::json!("helper"))]);
+        drop(db);
+
+        let graph = build_graph(&["scoop:app".to_string()], &mut SourceLookup::new(&config, &logger, &m)).unwrap();
+        let order: Vec<_> = graph.install_order().unwrap().iter().map(|p| p.install_spec.as_str()).collect();
+        assert_eq!(order, ["scoop:main/helper", "scoop:extras/lib", "scoop:main/app"]);
+        assert!(graph.render_tree().contains("lib 1.0 [scoop/extras]"));
+    }
+}
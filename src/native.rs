@@ -0,0 +1,695 @@
+//! Native Linux package managers
+//!
+//! Drives the distribution's own package manager (apt, dnf, pacman, zypper or
+//! apk) for packages that aren't in any configured repository. The manager is
+//! found by looking for its binary on `PATH`, and every command is run by
+//! name, so a directory of fake binaries put first on the `PATH` given to
+//! [`NativeManager::with_path`] stands in for the real thing.
+//!
+//! Installs and removals run with `sudo` when `get` isn't running as root.
+//! Package names and queries always follow `--`, so one starting with `-`
+//! can't be taken for an option.
+//! Search and list output is parsed into [`NativePackage`]s.
+
+use std::env;
+use std::ffi::{OsStr, OsString};
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+use std::time::SystemTime;
+
+use indicatif::MultiProgress;
+
+use crate::config::Config;
+use crate::error::GetError;
+use crate::logging::Logger;
+use crate::package_db::{package_db_path, InstalledPackage, PackageDb};
+use crate::package_manager::{InstallReport, PackageManager, SearchHit, UninstallReport};
+use crate::version::{split_package_spec, VersionReq};
+
+/// A supported native package manager
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NativeKind {
+    Apt,
+    Dnf,
+    Pacman,
+    Zypper,
+    Apk,
+}
+
+impl NativeKind {
+    /// In detection order
+    pub const ALL: [NativeKind; 5] = [NativeKind::Apt, NativeKind::Dnf, NativeKind::Pacman, NativeKind::Zypper, NativeKind::Apk];
+
+    /// The name used in `apt:` qualifiers and config keys
+    pub fn name(&self) -> &'static str {
+        match self {
+            NativeKind::Apt => "apt",
+            NativeKind::Dnf => "dnf",
+            NativeKind::Pacman => "pacman",
+            NativeKind::Zypper => "zypper",
+            NativeKind::Apk => "apk",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<NativeKind> {
+        NativeKind::ALL.iter().copied().find(|kind| kind.name().eq_ignore_ascii_case(name))
+    }
+
+    /// The binary that installs packages
+    fn program(&self) -> &'static str {
+        match self {
+            NativeKind::Apt => "apt-get",
+            other => other.name(),
+        }
+    }
+
+    /// The first manager whose binary is on `PATH`
+    pub fn detect() -> Option<NativeKind> {
+        NativeKind::ALL.iter().copied().find(|kind| find_on_path(kind.program()).is_some())
+    }
+}
+
+/// Look `program` up on `PATH` the way the shell would
+pub fn find_on_path(program: &str) -> Option<PathBuf> {
+    find_in(&env::var_os("PATH")?, program)
+}
+
+/// Look `program` up in the directories of `path`
+pub fn find_in(path: &OsStr, program: &str) -> Option<PathBuf> {
+    env::split_paths(path).map(|dir| dir.join(program)).find(|path| is_executable(path))
+}
+
+#[cfg(unix)]
+pub(crate) fn is_executable(path: &std::path::Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata().map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0).unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+pub(crate) fn is_executable(path: &std::path::Path) -> bool {
+    path.is_file()
+}
+
+/// A package as reported by a native manager
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NativePackage {
+    pub name: String,
+    /// Empty when the manager's search output doesn't include versions
+    pub version: String,
+    pub description: String,
+}
+
+impl NativePackage {
+    /// This package as a search hit from `source`
+    pub fn into_hit(self, source: &str) -> SearchHit {
+        SearchHit {
+            source: source.to_string(),
+            name: self.name,
+            version: Some(self.version).filter(|v| !v.is_empty()),
+            description: Some(self.description).filter(|d| !d.is_empty()),
+        }
+    }
+}
+
+/// A native package manager
+pub struct NativeManager {
+    kind: NativeKind,
+    /// Where its commands are looked up, instead of the inherited `PATH`
+    path: Option<OsString>,
+}
+
+impl NativeManager {
+    pub fn new(kind: NativeKind) -> Self {
+        NativeManager { kind, path: None }
+    }
+
+    /// A manager whose commands, `sudo` and `id` are found on `path`
+    pub fn with_path(kind: NativeKind, path: OsString) -> Self {
+        NativeManager { kind, path: Some(path) }
+    }
+
+    /// The manager of this system, if there is a supported one
+    pub fn detect() -> Option<Self> {
+        NativeKind::detect().map(NativeManager::new)
+    }
+
+    pub fn kind(&self) -> NativeKind {
+        self.kind
+    }
+
+    /// `program`, looked up on this manager's `PATH`
+    fn command(&self, program: &str) -> Command {
+        let mut command = Command::new(program);
+        if let Some(path) = &self.path {
+            command.env("PATH", path);
+        }
+        command
+    }
+
+    fn find(&self, program: &str) -> Option<PathBuf> {
+        match &self.path {
+            Some(path) => find_in(path, program),
+            None => find_on_path(program),
+        }
+    }
+
+    /// A command that changes the system, through `sudo` unless we are root
+    fn privileged(&self, program: &str) -> Command {
+        if self.is_root() || self.find("sudo").is_none() {
+            self.command(program)
+        } else {
+            let mut command = self.command("sudo");
+            command.arg(program);
+            command
+        }
+    }
+
+    #[cfg(unix)]
+    fn is_root(&self) -> bool {
+        self.command("id")
+            .arg("-u")
+            .output()
+            .map(|o| String::from_utf8_lossy(&o.stdout).trim() == "0")
+            .unwrap_or(false)
+    }
+
+    #[cfg(not(unix))]
+    fn is_root(&self) -> bool {
+        false
+    }
+
+    /// Run a query and return its stdout; a non-zero exit is an error
+    fn query(&self, program: &str, args: &[&str]) -> Result<String, GetError> {
+        let output = self.command(program).args(args).stdin(Stdio::null()).output()?;
+        check(program, &output)?;
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    fn run(&self, mut command: Command, what: &str) -> Result<(), GetError> {
+        let status = command.stdin(Stdio::null()).status()?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(GetError::CommandError(format!(
+                "{} {} failed ({})",
+                self.kind.name(),
+                what,
+                status.code().map_or("killed".to_string(), |code| format!("exit code {}", code))
+            )))
+        }
+    }
+
+    /// `name=version` in the syntax of this manager
+    fn pinned(&self, name: &str, version: &str) -> Result<String, GetError> {
+        match self.kind {
+            NativeKind::Apt | NativeKind::Zypper | NativeKind::Apk => Ok(format!("{}={}", name, version)),
+            NativeKind::Dnf => Ok(format!("{}-{}", name, version)),
+            NativeKind::Pacman => Err(GetError::InvalidInput(
+                "pacman can only install the version in the sync database".to_string(),
+            )),
+        }
+    }
+
+    /// Search the package database
+    pub fn search_packages(&self, query: &str) -> Result<Vec<NativePackage>, GetError> {
+        Ok(match self.kind {
+            NativeKind::Apt => parse_apt_search(&self.query("apt-cache", &["search", "--", query])?),
+            NativeKind::Dnf => parse_dnf_search(&self.query("dnf", &["search", "-q", "--", query])?),
+            // pacman exits with 1 when nothing matches
+            NativeKind::Pacman => match self.query("pacman", &["-Ss", "--", query]) {
+                Ok(output) => parse_pacman_search(&output),
+                Err(GetError::CommandError(_)) => Vec::new(),
+                Err(e) => return Err(e),
+            },
+            // zypper exits with 104 when nothing matches
+            NativeKind::Zypper => match self.query("zypper", &["--non-interactive", "--quiet", "search", "--", query]) {
+                Ok(output) => parse_zypper_search(&output),
+                Err(GetError::CommandError(_)) => Vec::new(),
+                Err(e) => return Err(e),
+            },
+            NativeKind::Apk => parse_apk_search(&self.query("apk", &["search", "-v", "-d", "--", query])?),
+        })
+    }
+
+    /// Every installed package
+    pub fn installed_packages(&self) -> Result<Vec<NativePackage>, GetError> {
+        Ok(match self.kind {
+            NativeKind::Apt => parse_tab_list(&self.query("dpkg-query", &["-W", "-f", "${Package}\t${Version}\n"])?),
+            NativeKind::Dnf | NativeKind::Zypper => {
+                parse_tab_list(&self.query("rpm", &["-qa", "--qf", "%{NAME}\t%{VERSION}-%{RELEASE}\n"])?)
+            }
+            NativeKind::Pacman => parse_pacman_list(&self.query("pacman", &["-Q"])?),
+            NativeKind::Apk => parse_apk_list(&self.query("apk", &["info", "-v"])?),
+        })
+    }
+
+    /// The installed version of `name`
+    pub fn installed_version(&self, name: &str) -> Result<Option<String>, GetError> {
+        Ok(self
+            .installed_packages()?
+            .into_iter()
+            .find(|p| p.name == name)
+            .map(|p| p.version))
+    }
+
+    /// The version `install` would install, if the package exists
+    pub fn available_version(&self, name: &str) -> Result<Option<String>, GetError> {
+        let output = |program: &str, args: &[&str]| match self.query(program, args) {
+            Ok(output) => Ok(Some(output)),
+            // Unknown packages make these commands fail
+            Err(GetError::CommandError(_)) => Ok(None),
+            Err(e) => Err(e),
+        };
+        Ok(match self.kind {
+            NativeKind::Apt => output("apt-cache", &["policy", "--", name])?
+                .and_then(|o| field_value(&o, "Candidate"))
+                .filter(|v| v != "(none)"),
+            NativeKind::Dnf => output("dnf", &["info", "-q", "--", name])?.and_then(|o| {
+                let version = field_value(&o, "Version")?;
+                Some(match field_value(&o, "Release") {
+                    Some(release) => format!("{}-{}", version, release),
+                    None => version,
+                })
+            }),
+            NativeKind::Pacman => output("pacman", &["-Si", "--", name])?.and_then(|o| field_value(&o, "Version")),
+            NativeKind::Zypper => {
+                output("zypper", &["--non-interactive", "--quiet", "info", "--", name])?.and_then(|o| field_value(&o, "Version"))
+            }
+            NativeKind::Apk => output("apk", &["policy", "--", name])?.and_then(|o| parse_apk_policy(&o)),
+        })
+    }
+}
+
+impl PackageManager for NativeManager {
+    fn name(&self) -> &str {
+        self.kind.name()
+    }
+
+    fn install(&self, package: &str, config: &Config, logger: &Logger, _m: &MultiProgress) -> Result<InstallReport, GetError> {
+        let (name, version) = split_package_spec(package);
+        let target = match version.map(VersionReq::parse).transpose()? {
+            Some(requirement) => match requirement.exact_version() {
+                Some(version) => self.pinned(name, version.as_str())?,
+                None => {
+                    return Err(GetError::InvalidInput(format!(
+                        "{} can only install an exact version of '{}'",
+                        self.kind.name(),
+                        name
+                    )))
+                }
+            },
+            None => name.to_string(),
+        };
+        if self.available_version(name)?.is_none() {
+            return Err(GetError::PackageNotFound(format!("'{}' not found by {}", name, self.kind.name())));
+        }
+
+        logger.log(&format!("Installing '{}' with {}...", target, self.kind.name()));
+        let mut command = self.privileged(self.kind.program());
+        match self.kind {
+            NativeKind::Apt | NativeKind::Dnf => command.args(["install", "-y", "--", &target]),
+            NativeKind::Pacman => command.args(["-S", "--noconfirm", "--needed", "--", &target]),
+            NativeKind::Zypper => command.args(["--non-interactive", "install", "--", &target]),
+            NativeKind::Apk => command.args(["add", "--", &target]),
+        };
+        self.run(command, "install")?;
+
+        let installed = self.installed_version(name)?;
+        PackageDb::open(&package_db_path(config))?.record_install(&InstalledPackage {
+            name: name.to_string(),
+            version: installed.clone().unwrap_or_default(),
+            source: self.kind.name().to_string(),
+            installed_at: SystemTime::now(),
+            manifest: serde_json::json!({ "manager": self.kind.name(), "package": name }).to_string(),
+            location: None,
+            files: Vec::new(),
+            shims: Vec::new(),
+        })?;
+        Ok(InstallReport {
+            source: self.kind.name().to_string(),
+            name: name.to_string(),
+            version: installed,
+            location: None,
+        })
+    }
+
+    fn uninstall(&self, package: &str, config: &Config, logger: &Logger, _m: &MultiProgress) -> Result<UninstallReport, GetError> {
+        let (name, _) = split_package_spec(package);
+        let Some(installed) = self.installed_version(name)? else {
+            return Err(GetError::PackageNotFound(format!("'{}' is not installed with {}", name, self.kind.name())));
+        };
+
+        logger.log(&format!("Removing '{}' with {}...", name, self.kind.name()));
+        let mut command = self.privileged(self.kind.program());
+        match self.kind {
+            NativeKind::Apt | NativeKind::Dnf => command.args(["remove", "-y", "--", name]),
+            NativeKind::Pacman => command.args(["-R", "--noconfirm", "--", name]),
+            NativeKind::Zypper => command.args(["--non-interactive", "remove", "--", name]),
+            NativeKind::Apk => command.args(["del", "--", name]),
+        };
+        self.run(command, "remove")?;
+
+        PackageDb::open(&package_db_path(config))?.remove_installed(name)?;
+        Ok(UninstallReport {
+            source: self.kind.name().to_string(),
+            name: name.to_string(),
+            version: Some(installed),
+        })
+    }
+
+    fn search(&self, query: &str, _config: &Config, _logger: &Logger, _m: &MultiProgress) -> Result<Vec<SearchHit>, GetError> {
+        let results = self.search_packages(query)?;
+        Ok(results.into_iter().map(|package| package.into_hit(self.kind.name())).collect())
+    }
+}
+
+fn check(program: &str, output: &Output) -> Result<(), GetError> {
+    if output.status.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    Err(GetError::CommandError(format!(
+        "'{}' failed: {}",
+        program,
+        stderr.lines().last().unwrap_or("no output")
+    )))
+}
+
+/// The value of a `Key : value` line, as printed by `dnf info`, `pacman -Si`,
+/// `zypper info` and `apt-cache policy`
+pub fn field_value(output: &str, key: &str) -> Option<String> {
+    output.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        (name.trim() == key).then(|| value.trim().to_string())
+    })
+}
+
+/// `apt-cache search`: `name - description`
+pub fn parse_apt_search(output: &str) -> Vec<NativePackage> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (name, description) = line.split_once(" - ")?;
+            Some(NativePackage {
+                name: name.trim().to_string(),
+                version: String::new(),
+                description: description.trim().to_string(),
+            })
+        })
+        .collect()
+}
+
+/// `dnf search`: `name.arch : summary` (dnf 4) or ` name.arch<tab>summary`
+/// (dnf 5), between `====` or `Matched fields:` headings
+pub fn parse_dnf_search(output: &str) -> Vec<NativePackage> {
+    output
+        .lines()
+        .filter(|line| !line.starts_with('=') && !line.trim_start().starts_with("Matched fields"))
+        .filter_map(|line| {
+            let (name, description) = line.split_once(" : ").or_else(|| line.trim().split_once('\t'))?;
+            let name = name.trim();
+            let name = name.rsplit_once('.').map_or(name, |(name, _arch)| name);
+            Some(NativePackage {
+                name: name.to_string(),
+                version: String::new(),
+                description: description.trim().to_string(),
+            })
+        })
+        .collect()
+}
+
+/// `pacman -Ss`: `repo/name version [installed]` followed by an indented description
+pub fn parse_pacman_search(output: &str) -> Vec<NativePackage> {
+    let mut packages: Vec<NativePackage> = Vec::new();
+    for line in output.lines() {
+        if line.starts_with(char::is_whitespace) {
+            if let Some(last) = packages.last_mut() {
+                last.description = line.trim().to_string();
+            }
+            continue;
+        }
+        let mut parts = line.split_whitespace();
+        let (Some(qualified), Some(version)) = (parts.next(), parts.next()) else {
+            continue;
+        };
+        packages.push(NativePackage {
+            name: qualified.rsplit('/').next().unwrap_or(qualified).to_string(),
+            version: version.to_string(),
+            description: String::new(),
+        });
+    }
+    packages
+}
+
+/// `zypper search`: a `S | Name | Summary | Type` table
+pub fn parse_zypper_search(output: &str) -> Vec<NativePackage> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let columns: Vec<&str> = line.split('|').map(str::trim).collect();
+            if columns.len() < 3 || columns[1].is_empty() || columns[1] == "Name" || columns[1].starts_with('-') {
+                return None;
+            }
+            Some(NativePackage {
+                name: columns[1].to_string(),
+                version: String::new(),
+                description: columns[2].to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Split an apk `name-version-rN` into name and version
+fn split_apk_package(text: &str) -> Option<(&str, &str)> {
+    let mut parts = text.rsplitn(3, '-');
+    let release = parts.next()?;
+    let version = parts.next()?;
+    let name = parts.next()?;
+    let version_start = text.len() - release.len() - version.len() - 1;
+    Some((name, &text[version_start..]))
+}
+
+/// `apk search -v -d`: `name-version-rN - description`
+pub fn parse_apk_search(output: &str) -> Vec<NativePackage> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (package, description) = line.split_once(" - ").unwrap_or((line, ""));
+            let (name, version) = split_apk_package(package.trim())?;
+            Some(NativePackage {
+                name: name.to_string(),
+                version: version.to_string(),
+                description: description.trim().to_string(),
+            })
+        })
+        .collect()
+}
+
+/// `apk info -v`: one `name-version-rN` per line
+pub fn parse_apk_list(output: &str) -> Vec<NativePackage> {
+    parse_apk_search(output)
+}
+
+/// `apk policy name`: the first indented `version:` line is the preferred one
+pub fn parse_apk_policy(output: &str) -> Option<String> {
+    output
+        .lines()
+        .skip(1)
+        .find(|line| line.starts_with("  ") && !line.starts_with("    "))
+        .and_then(|line| line.trim().strip_suffix(':'))
+        .map(str::to_string)
+}
+
+/// `name<tab>version` lines, from `dpkg-query` and `rpm -qa --qf`
+pub fn parse_tab_list(output: &str) -> Vec<NativePackage> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (name, version) = line.split_once('\t')?;
+            Some(NativePackage {
+                name: name.to_string(),
+                version: version.trim().to_string(),
+                description: String::new(),
+            })
+        })
+        .collect()
+}
+
+/// `pacman -Q`: `name version`
+pub fn parse_pacman_list(output: &str) -> Vec<NativePackage> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (name, version) = line.split_once(' ')?;
+            Some(NativePackage {
+                name: name.to_string(),
+                version: version.trim().to_string(),
+                description: String::new(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::logging::LogLevel;
+    use indicatif::ProgressDrawTarget;
+    use std::fs;
+
+    /// A directory of fake binaries in front of `PATH`; every fake appends
+    /// its name and arguments to `calls`
+    struct FakeSystem {
+        dir: tempfile::TempDir,
+    }
+
+    impl FakeSystem {
+        fn new(uid: u32) -> Self {
+            let system = FakeSystem { dir: tempfile::tempdir().unwrap() };
+            system.fake("id", &format!("echo {}", uid));
+            system.fake("sudo", "exec \"$@\"");
+            system
+        }
+
+        /// Install a fake `program` running the shell `body`
+        fn fake(&self, program: &str, body: &str) {
+            use std::os::unix::fs::PermissionsExt;
+            let path = self.dir.path().join(program);
+            let log = self.dir.path().join("calls");
+            fs::write(&path, format!("#!/bin/sh\necho \"{} $*\" >> '{}'\n{}\n", program, log.display(), body)).unwrap();
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        }
+
+        /// A manager that finds the fakes first
+        fn manager(&self, kind: NativeKind) -> NativeManager {
+            let mut paths = vec![self.dir.path().to_path_buf()];
+            paths.extend(env::var_os("PATH").iter().flat_map(env::split_paths));
+            NativeManager::with_path(kind, env::join_paths(paths).unwrap())
+        }
+
+        /// Calls of the fakes, except the `id` check
+        fn calls(&self) -> Vec<String> {
+            let calls = fs::read_to_string(self.dir.path().join("calls")).unwrap_or_default();
+            calls.lines().filter(|call| !call.starts_with("id ")).map(str::to_string).collect()
+        }
+
+        fn config(&self) -> Config {
+            Config { home: Some(self.dir.path().join("home").to_string_lossy().into_owned()), ..Default::default() }
+        }
+    }
+
+    fn quiet() -> (Logger, MultiProgress) {
+        (Logger::new(LogLevel::Minimal), MultiProgress::with_draw_target(ProgressDrawTarget::hidden()))
+    }
+
+    fn package(name: &str, version: &str, description: &str) -> NativePackage {
+        NativePackage { name: name.to_string(), version: version.to_string(), description: description.to_string() }
+    }
+
+    /// A fake apt whose `curl` becomes installed once `apt-get` runs
+    fn fake_apt(system: &FakeSystem) {
+        let state = system.dir.path().join("installed");
+        system.fake(
+            "apt-cache",
+            "case \"$1\" in
+search) printf 'curl - command line tool for transferring data\\nlibcurl4 - easy-to-use client-side URL transfer library\\n' ;;
+policy) [ \"$3\" = curl ] || exit 100; printf 'curl:\\n  Installed: (none)\\n  Candidate: 8.5.0-2\\n' ;;
+esac",
+        );
+        system.fake("dpkg-query", &format!("printf 'bash\\t5.2\\n'; cat '{}' 2>/dev/null; true", state.display()));
+        system.fake("apt-get", &format!("printf 'curl\\t8.5.0-2\\n' > '{}'", state.display()));
+    }
+
+    #[test]
+    fn reads_apt_search_and_installed_output() {
+        let system = FakeSystem::new(1000);
+        fake_apt(&system);
+        let apt = system.manager(NativeKind::Apt);
+
+        assert_eq!(
+            apt.search_packages("curl").unwrap(),
+            [
+                package("curl", "", "command line tool for transferring data"),
+                package("libcurl4", "", "easy-to-use client-side URL transfer library"),
+            ]
+        );
+        assert_eq!(apt.installed_packages().unwrap(), [package("bash", "5.2", "")]);
+        assert_eq!(apt.available_version("curl").unwrap().as_deref(), Some("8.5.0-2"));
+        assert_eq!(apt.available_version("missing").unwrap(), None);
+        assert!(system.calls().contains(&"apt-cache search -- curl".to_string()));
+    }
+
+    #[test]
+    fn installs_with_sudo_unless_root() {
+        let (logger, m) = quiet();
+        for (uid, expected) in [(1000, "sudo apt-get install -y -- curl"), (0, "apt-get install -y -- curl")] {
+            let system = FakeSystem::new(uid);
+            fake_apt(&system);
+            let config = system.config();
+
+            let report = system.manager(NativeKind::Apt).install("curl", &config, &logger, &m).unwrap();
+            assert_eq!(report.version.as_deref(), Some("8.5.0-2"));
+            let calls = system.calls();
+            assert!(calls.contains(&expected.to_string()), "{:?}", calls);
+            assert_eq!(calls.iter().filter(|call| call.starts_with("sudo ")).count(), usize::from(uid != 0));
+
+            let recorded = PackageDb::open(&package_db_path(&config)).unwrap().installed("curl").unwrap().unwrap();
+            assert_eq!((recorded.source.as_str(), recorded.version.as_str()), ("apt", "8.5.0-2"));
+        }
+    }
+
+    #[test]
+    fn does_not_install_what_the_manager_does_not_have() {
+        let system = FakeSystem::new(1000);
+        fake_apt(&system);
+        let (logger, m) = quiet();
+
+        let result = system.manager(NativeKind::Apt).install("-o=evil", &system.config(), &logger, &m);
+        assert!(matches!(result, Err(GetError::PackageNotFound(_))), "{:?}", result.err());
+        assert!(!system.calls().iter().any(|call| call.contains("apt-get")));
+    }
+
+    #[test]
+    fn reads_dnf_search_and_rpm_output() {
+        let system = FakeSystem::new(0);
+        system.fake(
+            "dnf",
+            "printf '=== Name Matched: curl ===\\ncurl.x86_64 : A utility for getting files from remote servers\\n'",
+        );
+        system.fake("rpm", "printf 'curl\\t8.2.1-3.fc39\\n'");
+        let dnf = system.manager(NativeKind::Dnf);
+
+        assert_eq!(
+            dnf.search_packages("curl").unwrap(),
+            [package("curl", "", "A utility for getting files from remote servers")]
+        );
+        assert_eq!(dnf.installed_version("curl").unwrap().as_deref(), Some("8.2.1-3.fc39"));
+        assert_eq!(system.calls()[0], "dnf search -q -- curl");
+    }
+
+    #[test]
+    fn reads_pacman_output_and_removes_as_root() {
+        let system = FakeSystem::new(0);
+        system.fake(
+            "pacman",
+            "case \"$1\" in
+-Ss) [ \"$3\" = curl ] || exit 1; printf 'core/curl 8.5.0-1 [installed]\\n    command line tool and library for transferring data with URLs\\n' ;;
+-Q) printf 'curl 8.5.0-1\\n' ;;
+esac",
+        );
+        let pacman = system.manager(NativeKind::Pacman);
+        let (logger, m) = quiet();
+
+        assert_eq!(
+            pacman.search_packages("curl").unwrap(),
+            [package("curl", "8.5.0-1", "command line tool and library for transferring data with URLs")]
+        );
+        // pacman exits with 1 when nothing matches
+        assert!(pacman.search_packages("missing").unwrap().is_empty());
+
+        let report = pacman.uninstall("curl", &system.config(), &logger, &m).unwrap();
+        assert_eq!(report.version.as_deref(), Some("8.5.0-1"));
+        assert!(system.calls().contains(&"pacman -R --noconfirm -- curl".to_string()));
+        assert!(!system.calls().iter().any(|call| call.starts_with("sudo ")));
+    }
+}
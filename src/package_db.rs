@@ -0,0 +1,523 @@
+//! Local package database
+//!
+//! Sources that have no index of their own (Scoop buckets) are indexed into an
+//! embedded SQLite database under the repositories directory, so searches,
+//! `provides` lookups and version queries are indexed queries instead of a
+//! scan over a deserialized index. Each source is replaced as a whole when it
+//! is re-indexed.
+//!
+//! Winget is not stored here: its source already ships a SQLite index (see
+//! `crate::winget_source`).
+//!
+//! The `installed` table records what `get` itself installed: the source,
+//! manifest, location, files and shims of each package, so it can be listed
+//! and uninstalled later without going back to the source.
+
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use hex::encode as hex_encode;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use sha2::{Digest, Sha256};
+
+use crate::config::Config;
+use crate::error::GetError;
+use crate::version::Version;
+
+const SCHEMA: &str = "
+    PRAGMA foreign_keys = ON;
+    CREATE TABLE IF NOT EXISTS sources (
+        name TEXT PRIMARY KEY,
+        indexed_at INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS packages (
+        id INTEGER PRIMARY KEY,
+        source TEXT NOT NULL REFERENCES sources(name) ON DELETE CASCADE,
+        name TEXT NOT NULL COLLATE NOCASE,
+        description TEXT NOT NULL DEFAULT '',
+        homepage TEXT,
+        license TEXT,
+        UNIQUE (source, name)
+    );
+    CREATE TABLE IF NOT EXISTS versions (
+        id INTEGER PRIMARY KEY,
+        package INTEGER NOT NULL REFERENCES packages(id) ON DELETE CASCADE,
+        version TEXT NOT NULL,
+        manifest_path TEXT NOT NULL,
+        UNIQUE (package, version)
+    );
+    CREATE TABLE IF NOT EXISTS installers (
+        version INTEGER NOT NULL REFERENCES versions(id) ON DELETE CASCADE,
+        architecture TEXT,
+        url TEXT NOT NULL,
+        hash TEXT
+    );
+    CREATE TABLE IF NOT EXISTS commands (
+        package INTEGER NOT NULL REFERENCES packages(id) ON DELETE CASCADE,
+        command TEXT NOT NULL COLLATE NOCASE
+    );
+    CREATE TABLE IF NOT EXISTS tags (
+        package INTEGER NOT NULL REFERENCES packages(id) ON DELETE CASCADE,
+        tag TEXT NOT NULL COLLATE NOCASE
+    );
+    CREATE TABLE IF NOT EXISTS installed (
+        name TEXT PRIMARY KEY COLLATE NOCASE,
+        version TEXT NOT NULL,
+        source TEXT NOT NULL,
+        installed_at INTEGER NOT NULL,
+        manifest TEXT NOT NULL,
+        manifest_hash TEXT NOT NULL DEFAULT '',
+        location TEXT,
+        files TEXT NOT NULL DEFAULT '[]',
+        shims TEXT NOT NULL DEFAULT '[]'
+    );
+    CREATE INDEX IF NOT EXISTS packages_name ON packages(name);
+    CREATE INDEX IF NOT EXISTS versions_package ON versions(package);
+    CREATE INDEX IF NOT EXISTS installers_version ON installers(version);
+    CREATE INDEX IF NOT EXISTS commands_command ON commands(command);
+    CREATE INDEX IF NOT EXISTS commands_package ON commands(package);
+    CREATE INDEX IF NOT EXISTS tags_tag ON tags(tag);
+    CREATE INDEX IF NOT EXISTS tags_package ON tags(package);
+";
+
+/// Columns added to `installed` after its first release, for older databases
+const INSTALLED_COLUMNS: [(&str, &str); 4] = [
+    ("manifest_hash", "TEXT NOT NULL DEFAULT ''"),
+    ("location", "TEXT"),
+    ("files", "TEXT NOT NULL DEFAULT '[]'"),
+    ("shims", "TEXT NOT NULL DEFAULT '[]'"),
+];
+
+/// A package version to store, with everything indexed about it
+#[derive(Debug, Clone, Default)]
+pub struct PackageRecord {
+    pub name: String,
+    pub version: String,
+    pub description: String,
+    pub homepage: Option<String>,
+    pub license: Option<String>,
+    /// Path of the source manifest the record was built from
+    pub manifest_path: String,
+    pub installers: Vec<InstallerRecord>,
+    /// Commands the package puts on the PATH
+    pub commands: Vec<String>,
+    pub tags: Vec<String>,
+}
+
+/// A downloadable installer of a package version
+#[derive(Debug, Clone, Default)]
+pub struct InstallerRecord {
+    pub architecture: Option<String>,
+    pub url: String,
+    pub hash: Option<String>,
+}
+
+/// A package version found by a query
+#[derive(Debug, Clone)]
+pub struct DbEntry {
+    pub source: String,
+    pub name: String,
+    pub version: String,
+    pub description: String,
+    pub homepage: Option<String>,
+    pub manifest_path: String,
+}
+
+/// A package installed by `get`
+#[derive(Debug, Clone)]
+pub struct InstalledPackage {
+    pub name: String,
+    pub version: String,
+    /// The source it was installed from (a repository name, `bundle`, ...)
+    pub source: String,
+    pub installed_at: SystemTime,
+    /// The manifest it was installed with, as JSON
+    pub manifest: String,
+    /// Where it was installed, when `get` chose the place
+    pub location: Option<PathBuf>,
+    /// Files and directories created by `get` itself; empty when an
+    /// installer or another package manager did the work
+    pub files: Vec<PathBuf>,
+    /// Shims created for it in the bin directory
+    pub shims: Vec<PathBuf>,
+}
+
+impl InstalledPackage {
+    /// SHA-256 of the manifest, to tell whether a source changed it since
+    pub fn manifest_hash(&self) -> String {
+        hex_encode(Sha256::digest(self.manifest.as_bytes()))
+    }
+}
+
+/// Location of the package database
+pub fn package_db_path(config: &Config) -> PathBuf {
+    config.get_repos_dir().join("packages.db")
+}
+
+fn to_unix(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(0)
+}
+
+fn from_unix(secs: i64) -> SystemTime {
+    UNIX_EPOCH + std::time::Duration::from_secs(secs.max(0) as u64)
+}
+
+/// Bring an `installed` table created by an older version up to date
+fn add_missing_columns(conn: &Connection) -> Result<(), GetError> {
+    let existing = conn
+        .prepare("SELECT name FROM pragma_table_info('installed')")?
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    for (column, definition) in INSTALLED_COLUMNS {
+        if !existing.iter().any(|c| c == column) {
+            conn.execute_batch(&format!("ALTER TABLE installed ADD COLUMN {} {}", column, definition))?;
+        }
+    }
+    Ok(())
+}
+
+const SELECT_INSTALLED: &str = "SELECT name, version, source, installed_at, manifest, location, files, shims FROM installed";
+
+fn installed_from_row(row: &Row) -> rusqlite::Result<InstalledPackage> {
+    let paths = |index: usize| -> rusqlite::Result<Vec<PathBuf>> {
+        // Written by `record_install`; an unreadable list is as good as none
+        Ok(serde_json::from_str(&row.get::<_, String>(index)?).unwrap_or_default())
+    };
+    Ok(InstalledPackage {
+        name: row.get(0)?,
+        version: row.get(1)?,
+        source: row.get(2)?,
+        installed_at: from_unix(row.get(3)?),
+        manifest: row.get(4)?,
+        location: row.get::<_, Option<String>>(5)?.map(PathBuf::from),
+        files: paths(6)?,
+        shims: paths(7)?,
+    })
+}
+
+const SELECT_ENTRIES: &str = "SELECT packages.source, packages.name, versions.version, \
+     packages.description, packages.homepage, versions.manifest_path \
+     FROM packages JOIN versions ON versions.package = packages.id";
+
+fn entry_from_row(row: &Row) -> rusqlite::Result<DbEntry> {
+    Ok(DbEntry {
+        source: row.get(0)?,
+        name: row.get(1)?,
+        version: row.get(2)?,
+        description: row.get(3)?,
+        homepage: row.get(4)?,
+        manifest_path: row.get(5)?,
+    })
+}
+
+/// Escape `LIKE` wildcards in user input
+fn like_pattern(query: &str) -> String {
+    format!(
+        "%{}%",
+        query.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+    )
+}
+
+/// The package database at a path
+pub struct PackageDb {
+    conn: Connection,
+}
+
+impl PackageDb {
+    /// Open (or create) the database
+    pub fn open(path: &Path) -> Result<Self, GetError> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let conn = Connection::open(path)?;
+        conn.execute_batch(SCHEMA)?;
+        add_missing_columns(&conn)?;
+        Ok(PackageDb { conn })
+    }
+
+    /// When `source` was last indexed
+    pub fn indexed_at(&self, source: &str) -> Result<Option<SystemTime>, GetError> {
+        let secs: Option<i64> = self
+            .conn
+            .query_row("SELECT indexed_at FROM sources WHERE name = ?1", params![source], |row| row.get(0))
+            .optional()?;
+        Ok(secs.map(from_unix))
+    }
+
+    /// Replace everything stored for `source` with `records`
+    pub fn replace_source(&mut self, source: &str, records: &[PackageRecord]) -> Result<(), GetError> {
+        let now = to_unix(SystemTime::now());
+        let tx = self.conn.transaction()?;
+        tx.execute("DELETE FROM sources WHERE name = ?1", params![source])?;
+        tx.execute("INSERT INTO sources (name, indexed_at) VALUES (?1, ?2)", params![source, now])?;
+
+        {
+            let mut insert_package = tx.prepare(
+                "INSERT INTO packages (source, name, description, homepage, license) VALUES (?1, ?2, ?3, ?4, ?5) \
+                 ON CONFLICT (source, name) DO UPDATE SET description = excluded.description \
+                 RETURNING id",
+            )?;
+            let mut insert_version = tx.prepare(
+                "INSERT OR REPLACE INTO versions (package, version, manifest_path) VALUES (?1, ?2, ?3)",
+            )?;
+            let mut insert_installer =
+                tx.prepare("INSERT INTO installers (version, architecture, url, hash) VALUES (?1, ?2, ?3, ?4)")?;
+            let mut insert_command = tx.prepare("INSERT INTO commands (package, command) VALUES (?1, ?2)")?;
+            let mut insert_tag = tx.prepare("INSERT INTO tags (package, tag) VALUES (?1, ?2)")?;
+
+            for record in records {
+                let package: i64 = insert_package.query_row(
+                    params![source, record.name, record.description, record.homepage, record.license],
+                    |row| row.get(0),
+                )?;
+                insert_version.execute(params![package, record.version, record.manifest_path])?;
+                let version = tx.last_insert_rowid();
+
+                for installer in &record.installers {
+                    insert_installer.execute(params![version, installer.architecture, installer.url, installer.hash])?;
+                }
+                for command in &record.commands {
+                    insert_command.execute(params![package, command])?;
+                }
+                for tag in &record.tags {
+                    insert_tag.execute(params![package, tag])?;
+                }
+            }
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Drop everything stored for `source`
+    pub fn remove_source(&self, source: &str) -> Result<(), GetError> {
+        self.conn.execute("DELETE FROM sources WHERE name = ?1", params![source])?;
+        Ok(())
+    }
+
+    fn query(&self, sql: &str, params: impl rusqlite::Params) -> Result<Vec<DbEntry>, GetError> {
+        let mut stmt = self.conn.prepare(sql)?;
+        let entries = stmt.query_map(params, entry_from_row)?.collect::<Result<Vec<_>, _>>()?;
+        Ok(entries)
+    }
+
+    /// Package versions whose name or description contains `query`, or whose
+    /// tags or commands equal it
+    pub fn search(&self, query: &str) -> Result<Vec<DbEntry>, GetError> {
+        let sql = format!(
+            "{} WHERE packages.name LIKE ?1 ESCAPE '\\' OR packages.description LIKE ?1 ESCAPE '\\' \
+             OR packages.id IN (SELECT package FROM tags WHERE tag = ?2) \
+             OR packages.id IN (SELECT package FROM commands WHERE command = ?2) \
+             ORDER BY packages.name, packages.source",
+            SELECT_ENTRIES
+        );
+        self.query(&sql, params![like_pattern(query), query])
+    }
+
+    /// Every version of `name` (case-insensitive) in sources starting with
+    /// `source_prefix`, newest first
+    pub fn versions(&self, name: &str, source_prefix: &str) -> Result<Vec<DbEntry>, GetError> {
+        let sql = format!(
+            "{} WHERE packages.name = ?1 AND packages.source LIKE ?2 ESCAPE '\\' ORDER BY packages.source",
+            SELECT_ENTRIES
+        );
+        let prefix = format!(
+            "{}%",
+            source_prefix.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+        );
+        let mut entries = self.query(&sql, params![name, prefix])?;
+        entries.sort_by(|a, b| Version::parse(&b.version).cmp(&Version::parse(&a.version)));
+        Ok(entries)
+    }
+
+    /// Packages that put `command` on the PATH
+    pub fn provides(&self, command: &str) -> Result<Vec<DbEntry>, GetError> {
+        let sql = format!(
+            "{} WHERE packages.id IN (SELECT package FROM commands WHERE command = ?1) \
+             ORDER BY packages.name, packages.source",
+            SELECT_ENTRIES
+        );
+        self.query(&sql, params![command])
+    }
+
+    /// The installers recorded for a package version
+    pub fn installers(&self, entry: &DbEntry) -> Result<Vec<InstallerRecord>, GetError> {
+        let mut stmt = self.conn.prepare(
+            "SELECT installers.architecture, installers.url, installers.hash FROM installers \
+             JOIN versions ON versions.id = installers.version \
+             JOIN packages ON packages.id = versions.package \
+             WHERE packages.source = ?1 AND packages.name = ?2 AND versions.version = ?3",
+        )?;
+        let installers = stmt
+            .query_map(params![entry.source, entry.name, entry.version], |row| {
+                Ok(InstallerRecord {
+                    architecture: row.get(0)?,
+                    url: row.get(1)?,
+                    hash: row.get(2)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(installers)
+    }
+
+    /// Record that a package was installed, replacing any earlier record
+    pub fn record_install(&self, package: &InstalledPackage) -> Result<(), GetError> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO installed \
+             (name, version, source, installed_at, manifest, manifest_hash, location, files, shims) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                package.name,
+                package.version,
+                package.source,
+                to_unix(package.installed_at),
+                package.manifest,
+                package.manifest_hash(),
+                package.location.as_ref().map(|p| p.to_string_lossy().into_owned()),
+                serde_json::to_string(&package.files)?,
+                serde_json::to_string(&package.shims)?,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// The install record of `name` (case-insensitive)
+    pub fn installed(&self, name: &str) -> Result<Option<InstalledPackage>, GetError> {
+        let package = self
+            .conn
+            .query_row(&format!("{} WHERE name = ?1", SELECT_INSTALLED), params![name], installed_from_row)
+            .optional()?;
+        Ok(package)
+    }
+
+    /// Every install record, by name
+    pub fn list_installed(&self) -> Result<Vec<InstalledPackage>, GetError> {
+        let packages = self
+            .conn
+            .prepare(&format!("{} ORDER BY name", SELECT_INSTALLED))?
+            .query_map([], installed_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(packages)
+    }
+
+    /// Forget the install record of `name`
+    pub fn remove_installed(&self, name: &str) -> Result<(), GetError> {
+        self.conn.execute("DELETE FROM installed WHERE name = ?1", params![name])?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(name: &str, version: &str, description: &str) -> PackageRecord {
+        PackageRecord {
+            name: name.to_string(),
+            version: version.to_string(),
+            description: description.to_string(),
+            manifest_path: format!("{}-{}.json", name, version),
+            ..Default::default()
+        }
+    }
+
+    fn names(entries: &[DbEntry]) -> Vec<(&str, &str, &str)> {
+        entries.iter().map(|e| (e.source.as_str(), e.name.as_str(), e.version.as_str())).collect()
+    }
+
+    fn open() -> (tempfile::TempDir, PackageDb) {
+        let dir = tempfile::tempdir().unwrap();
+        let db = PackageDb::open(&dir.path().join("repos/packages.db")).unwrap();
+        (dir, db)
+    }
+
+    #[test]
+    fn indexes_sources_for_search_provides_and_versions() {
+        let (_dir, mut db) = open();
+        let ripgrep = PackageRecord {
+            commands: vec!["rg".to_string()],
+            tags: vec!["search".to_string()],
+            installers: vec![InstallerRecord { architecture: Some("64bit".to_string()), url: "https://example.com/rg.zip".to_string(), hash: None }],
+            ..record("ripgrep", "14.1.0", "Recursive line-oriented grep")
+        };
+        db.replace_source("scoop/main", &[ripgrep, record("fd", "9.0.0", "Find files 100% faster")]).unwrap();
+        db.replace_source("scoop/versions", &[record("ripgrep", "13.0.0", "Older ripgrep"), record("ripgrep", "2.0", "Much older")])
+            .unwrap();
+        assert!(db.indexed_at("scoop/main").unwrap().is_some());
+        assert!(db.indexed_at("scoop/extras").unwrap().is_none());
+
+        assert_eq!(names(&db.search("GREP").unwrap()), [("scoop/main", "ripgrep", "14.1.0"), ("scoop/versions", "ripgrep", "13.0.0"), ("scoop/versions", "ripgrep", "2.0")]);
+        // Tags and commands match whole words only, and LIKE wildcards are literal
+        assert_eq!(names(&db.search("rg").unwrap()), [("scoop/main", "ripgrep", "14.1.0")]);
+        assert_eq!(names(&db.search("sear").unwrap()), []);
+        assert_eq!(names(&db.search("100%").unwrap()), [("scoop/main", "fd", "9.0.0")]);
+        assert_eq!(names(&db.search("_").unwrap()), []);
+        assert_eq!(names(&db.provides("RG").unwrap()), [("scoop/main", "ripgrep", "14.1.0")]);
+
+        let versions = db.versions("RipGrep", "scoop/").unwrap();
+        assert_eq!(names(&versions), [("scoop/main", "ripgrep", "14.1.0"), ("scoop/versions", "ripgrep", "13.0.0"), ("scoop/versions", "ripgrep", "2.0")]);
+        assert_eq!(names(&db.versions("ripgrep", "scoop/versions").unwrap()).len(), 2);
+        assert_eq!(db.installers(&versions[0]).unwrap()[0].url, "https://example.com/rg.zip");
+        assert!(db.installers(&versions[1]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn replaces_and_removes_whole_sources() {
+        let (_dir, mut db) = open();
+        db.replace_source("scoop/main", &[PackageRecord { commands: vec!["jq".to_string()], ..record("jq", "1.6", "JSON processor") }])
+            .unwrap();
+        db.replace_source("scoop/main", &[record("jq", "1.7", "JSON processor")]).unwrap();
+        assert_eq!(names(&db.versions("jq", "").unwrap()), [("scoop/main", "jq", "1.7")]);
+        assert!(db.provides("jq").unwrap().is_empty());
+
+        db.remove_source("scoop/main").unwrap();
+        assert!(db.search("jq").unwrap().is_empty());
+        assert!(db.indexed_at("scoop/main").unwrap().is_none());
+    }
+
+    #[test]
+    fn records_installs() {
+        let (_dir, db) = open();
+        let package = InstalledPackage {
+            name: "Tool".to_string(),
+            version: "1.0".to_string(),
+            source: "github".to_string(),
+            installed_at: UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000),
+            manifest: "{\"name\":\"Tool\"}".to_string(),
+            location: Some(PathBuf::from("/apps/tool/current")),
+            files: vec![PathBuf::from("/apps/tool/1.0")],
+            shims: vec![PathBuf::from("/bin/tool")],
+        };
+        db.record_install(&package).unwrap();
+        db.record_install(&InstalledPackage { name: "other".to_string(), ..package.clone() }).unwrap();
+        db.record_install(&InstalledPackage { version: "2.0".to_string(), ..package.clone() }).unwrap();
+
+        let installed = db.installed("tool").unwrap().unwrap();
+        assert_eq!((installed.version.as_str(), installed.installed_at), ("2.0", package.installed_at));
+        assert_eq!(installed.manifest_hash(), package.manifest_hash());
+        assert_eq!((installed.location, installed.files, installed.shims), (package.location, package.files, package.shims));
+        let listed: Vec<_> = db.list_installed().unwrap().into_iter().map(|p| p.name).collect();
+        assert_eq!(listed, ["other", "Tool"]);
+
+        db.remove_installed("TOOL").unwrap();
+        assert!(db.installed("tool").unwrap().is_none());
+    }
+
+    #[test]
+    fn upgrades_an_older_installed_table() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("packages.db");
+        Connection::open(&path)
+            .unwrap()
+            .execute_batch(
+                "CREATE TABLE installed (name TEXT PRIMARY KEY COLLATE NOCASE, version TEXT NOT NULL, \
+                 source TEXT NOT NULL, installed_at INTEGER NOT NULL, manifest TEXT NOT NULL); \
+                 INSERT INTO installed VALUES ('tool', '1.0', 'winget', 0, '{}');",
+            )
+            .unwrap();
+
+        let installed = PackageDb::open(&path).unwrap().installed("tool").unwrap().unwrap();
+        assert_eq!(installed.version, "1.0");
+        assert!(installed.location.is_none() && installed.files.is_empty() && installed.shims.is_empty());
+    }
+}
@@ -1,107 +1,407 @@
 //! Universal package manager implementation
 
 use std::fs::File;
-use std::io::{self, Read, Write};
+use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
-use std::process::{Command, Stdio};
-use std::time::Duration;
+use std::process::Command;
+use std::time::{Duration, SystemTime};
 
-use reqwest::blocking::{Client, Response};
-use reqwest::header::USER_AGENT;
+use reqwest::blocking::{Client, RequestBuilder, Response};
+use reqwest::header::{ACCEPT, CONTENT_TYPE, ETAG, IF_NONE_MATCH, USER_AGENT};
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use hex::encode as hex_encode;
 use indicatif::{ProgressBar, ProgressStyle, MultiProgress};
 
-use crate::config::Config;
+use crate::apps::{find_executables, Apps, BinSpec};
+use crate::archive;
+use crate::config::{Config, Repository, RepositoryAuth};
 use crate::error::GetError;
 use crate::logging::Logger;
+use crate::package_db::{package_db_path, InstalledPackage, PackageDb};
+use crate::protocol::{self, IndexEntry, RepositoryIndex};
+use crate::source::DEFAULT_REPOSITORY_PRIORITY;
+use crate::version::{select_version, split_package_spec};
+
+/// A package found by a backend's search
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchHit {
+    /// The source that has it, as used in `source:` qualifiers
+    pub source: String,
+    pub name: String,
+    /// The latest version, when the backend's search reports one
+    pub version: Option<String>,
+    pub description: Option<String>,
+}
+
+/// What an install did
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstallReport {
+    pub source: String,
+    pub name: String,
+    /// The installed version, when the backend knows it
+    pub version: Option<String>,
+    /// Where the files went, for backends that manage their own directory
+    pub location: Option<PathBuf>,
+}
+
+/// What an uninstall did
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UninstallReport {
+    pub source: String,
+    pub name: String,
+    /// The version that was removed, when the backend knows it
+    pub version: Option<String>,
+}
+
+/// An installed package with a newer version in its source
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutdatedPackage {
+    pub name: String,
+    pub installed: String,
+    /// The newest version the source has, within any hold
+    pub available: String,
+    pub source: String,
+    /// The request that installs `available`; `None` when only a version
+    /// check found it and the source has no manifest for it yet
+    pub install_spec: Option<String>,
+    /// The hold `available` was chosen within
+    pub held: Option<String>,
+}
+
+/// What an install left on disk, for the install record
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InstalledFiles {
+    pub location: Option<PathBuf>,
+    pub files: Vec<PathBuf>,
+    pub shims: Vec<PathBuf>,
+}
 
 /// Universal package manager interface
+///
+/// Implementations return what they did rather than printing it; rendering is
+/// left to [`crate::presentation`]. The trait is object-safe, so backends can
+/// be held as `Box<dyn PackageManager>` in a [`BackendRegistry`].
 pub trait PackageManager {
-    fn install(&self, package: &str, config: &Config, logger: &Logger, m: &MultiProgress) -> Result<(), GetError>;
-    fn uninstall(&self, package: &str, logger: &Logger, m: &MultiProgress) -> Result<(), GetError>;
-    fn search(&self, query: &str, logger: &Logger, config: &Config, m: &MultiProgress) -> Result<(), GetError>;
+    /// The source name this backend answers to
+    fn name(&self) -> &str;
+    fn install(&self, package: &str, config: &Config, logger: &Logger, m: &MultiProgress) -> Result<InstallReport, GetError>;
+    fn uninstall(&self, package: &str, config: &Config, logger: &Logger, m: &MultiProgress) -> Result<UninstallReport, GetError>;
+    fn search(&self, query: &str, config: &Config, logger: &Logger, m: &MultiProgress) -> Result<Vec<SearchHit>, GetError>;
+}
+
+/// A set of backends, looked up by name
+#[derive(Default)]
+pub struct BackendRegistry {
+    backends: Vec<Box<dyn PackageManager>>,
+}
+
+impl BackendRegistry {
+    pub fn new() -> Self {
+        BackendRegistry::default()
+    }
+
+    /// Add a backend; an earlier one of the same name takes precedence
+    pub fn register(&mut self, backend: Box<dyn PackageManager>) {
+        self.backends.push(backend);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn PackageManager> {
+        self.backends.iter().find(|b| b.name().eq_ignore_ascii_case(name)).map(|b| b.as_ref())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &dyn PackageManager> {
+        self.backends.iter().map(|b| b.as_ref())
+    }
+
+    /// Search every backend in order; a failing backend is logged and skipped
+    /// so it doesn't hide the others' results
+    pub fn search_all(&self, query: &str, config: &Config, logger: &Logger, m: &MultiProgress) -> Vec<SearchHit> {
+        let mut hits = Vec::new();
+        for backend in self.iter() {
+            logger.log(&format!("Searching with {}...", backend.name()));
+            match backend.search(query, config, logger, m) {
+                Ok(found) => hits.extend(found),
+                Err(e) => logger.warn(&format!("{} search failed: {}", backend.name(), e)),
+            }
+        }
+        hits
+    }
 }
 
 /// Universal package manager implementation
 pub struct UniversalPackageManager;
 
+/// A package found in one of the configured repositories
+#[derive(Debug, Clone)]
+pub struct RepositorySearchResult {
+    pub repository: String,
+    pub entry: IndexEntry,
+}
+
 impl PackageManager for UniversalPackageManager {
-    fn install(&self, package: &str, config: &Config, logger: &Logger, m: &MultiProgress) -> Result<(), GetError> {
-        let package_info = self.fetch_package_info(package, config, logger, m)?;
-        self.download_and_install(&package_info, config, logger, m)
+    fn name(&self) -> &str {
+        "repositories"
+    }
+
+    fn install(&self, package: &str, config: &Config, logger: &Logger, m: &MultiProgress) -> Result<InstallReport, GetError> {
+        let (repo, package_info) = self.locate(package, config, logger, m)?;
+        let installed = self.download_and_install(repo, &package_info, config, logger, m)?;
+        self.record_install(&package_info, &repo.name, &installed, config)?;
+        Ok(install_report(&package_info, &repo.name, installed.location))
     }
 
-    fn uninstall(&self, package: &str, logger: &Logger, m: &MultiProgress) -> Result<(), GetError> {
-        self.handle_uninstall(package, logger, m)
+    fn uninstall(&self, package: &str, config: &Config, logger: &Logger, _m: &MultiProgress) -> Result<UninstallReport, GetError> {
+        self.handle_uninstall(package, config, logger)
     }
 
-    fn search(&self, query: &str, logger: &Logger, config: &Config, m: &MultiProgress) -> Result<(), GetError> {
-        let results = self.search_repositories(query, config, logger, m)?;
-        self.display_search_results(&results, logger)
+    fn search(&self, query: &str, config: &Config, logger: &Logger, _m: &MultiProgress) -> Result<Vec<SearchHit>, GetError> {
+        let results = self.search_repositories(query, config, logger)?;
+        Ok(results.into_iter().map(RepositorySearchResult::into_hit).collect())
+    }
+}
+
+impl RepositorySearchResult {
+    /// The hit for this result, with the latest version it has
+    pub fn into_hit(self) -> SearchHit {
+        SearchHit {
+            version: select_version(&self.entry.versions, |v| v.as_str(), None).cloned(),
+            source: self.repository,
+            name: self.entry.name,
+            description: Some(self.entry.description),
+        }
+    }
+}
+
+fn install_report(package_info: &PackageInfo, repository: &str, location: Option<PathBuf>) -> InstallReport {
+    InstallReport {
+        source: repository.to_string(),
+        name: package_info.name.clone(),
+        version: Some(package_info.version.clone()),
+        location,
     }
 }
 
 impl UniversalPackageManager {
-    fn fetch_package_info(&self, package: &str, config: &Config, logger: &Logger, m: &MultiProgress) -> Result<PackageInfo, GetError> {
+    fn client(&self) -> Result<Client, GetError> {
+        Ok(Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()?)
+    }
+
+    /// Build a GET request with authentication and content negotiation for `repo`
+    fn request(&self, client: &Client, repo: &Repository, url: &str) -> Result<RequestBuilder, GetError> {
+        let mut request = client.get(url)
+            .header(USER_AGENT, "get-package-manager/1.0")
+            .header(ACCEPT, protocol::media_type(&repo.package_format));
+
+        // Add authentication if needed
+        if let Some(auth) = &repo.authentication {
+            request = match auth {
+                RepositoryAuth::Basic { username, password } => {
+                    request.basic_auth(username, Some(password))
+                }
+                RepositoryAuth::Token { token } => {
+                    request.header("Authorization", format!("Bearer {}", token))
+                }
+                // There is no token endpoint to exchange these at, and sending
+                // the secret itself to the repository would leak it
+                RepositoryAuth::OAuth2 { .. } => {
+                    return Err(GetError::ConfigError(format!(
+                        "Repository '{}' uses OAuth2, which is not supported; configure a token instead",
+                        repo.name
+                    )))
+                }
+            };
+        }
+
+        Ok(request)
+    }
+
+    /// Decode a response by its `Content-Type`, falling back to the repository's format
+    fn decode_response<T: DeserializeOwned>(&self, repo: &Repository, response: Response) -> Result<T, GetError> {
+        let format = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(protocol::format_for_media_type)
+            .unwrap_or(repo.package_format);
+        let body = response.bytes()?;
+        protocol::decode(&format, &body)
+    }
+
+    /// GET `path` from `repo`, falling back to `path.{ext}` for static repositories.
+    /// Returns `None` when neither exists.
+    fn get_negotiated<T: DeserializeOwned>(&self, client: &Client, repo: &Repository, path: &str) -> Result<Option<T>, GetError> {
+        let base = repo.url.trim_end_matches('/');
+        let candidates = [
+            format!("{}/{}", base, path),
+            format!("{}/{}.{}", base, path, protocol::extension(&repo.package_format)),
+        ];
+
+        for url in &candidates {
+            let response = self.request(client, repo, url)?.send()?;
+            match response.status() {
+                status if status.is_success() => return self.decode_response(repo, response).map(Some),
+                StatusCode::NOT_FOUND => continue,
+                status => {
+                    return Err(GetError::NetworkError(format!(
+                        "Repository '{}' returned HTTP {} for '{}'",
+                        repo.name, status, url
+                    )));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Fetch a repository's index, reusing the cached copy when the server reports it unchanged
+    pub fn fetch_index(&self, repo: &Repository, config: &Config) -> Result<Option<RepositoryIndex>, GetError> {
+        let client = self.client()?;
+        let cache_dir = config.get_repos_dir().join("http").join(&repo.name);
+        let cache_path = cache_dir.join("index.msgpack");
+        let etag_path = cache_dir.join("index.etag");
+        let cached_etag = std::fs::read_to_string(&etag_path).ok().filter(|_| cache_path.exists());
+
+        let base = repo.url.trim_end_matches('/');
+        let candidates = [
+            format!("{}/index", base),
+            format!("{}/index.{}", base, protocol::extension(&repo.package_format)),
+        ];
+
+        for url in &candidates {
+            let mut request = self.request(&client, repo, url)?;
+            if let Some(etag) = &cached_etag {
+                request = request.header(IF_NONE_MATCH, etag.as_str());
+            }
+            let response = request.send()?;
+
+            match response.status() {
+                StatusCode::NOT_MODIFIED => {
+                    let data = std::fs::read(&cache_path)?;
+                    return Ok(Some(rmp_serde::from_slice(&data)?));
+                }
+                StatusCode::NOT_FOUND => continue,
+                status if status.is_success() => {
+                    let etag = response
+                        .headers()
+                        .get(ETAG)
+                        .and_then(|v| v.to_str().ok())
+                        .map(str::to_string);
+                    let index: RepositoryIndex = self.decode_response(repo, response)?;
+
+                    if let Some(etag) = etag {
+                        std::fs::create_dir_all(&cache_dir)?;
+                        std::fs::write(&cache_path, rmp_serde::to_vec(&index)?)?;
+                        std::fs::write(&etag_path, etag)?;
+                    }
+                    return Ok(Some(index));
+                }
+                status => {
+                    return Err(GetError::NetworkError(format!(
+                        "Repository '{}' returned HTTP {} for its index",
+                        repo.name, status
+                    )));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Resolve `name[@version]` to its manifest in the highest-priority repository that has it
+    pub fn fetch_package_info(&self, package: &str, config: &Config, logger: &Logger, m: &MultiProgress) -> Result<PackageInfo, GetError> {
+        self.locate(package, config, logger, m).map(|(_, package_info)| package_info)
+    }
+
+    /// Find the highest-priority repository serving `name[@version]`
+    fn locate<'a>(&self, package: &str, config: &'a Config, logger: &Logger, m: &MultiProgress) -> Result<(&'a Repository, PackageInfo), GetError> {
         let pb = m.add(ProgressBar::new_spinner());
         pb.set_message("Fetching package info...");
         pb.enable_steady_tick(Duration::from_millis(100));
 
         // Get repositories from config
-        let repositories = config.get_repositories();
+        let mut repositories: Vec<&Repository> = config.get_repositories().iter().collect();
         if repositories.is_empty() {
-            return Err(GetError::ConfigurationError("No repositories configured".to_string()));
+            pb.finish_and_clear();
+            return Err(GetError::ConfigError("No repositories configured".to_string()));
         }
+        repositories.sort_by_key(|repo| -repo.priority.unwrap_or(DEFAULT_REPOSITORY_PRIORITY));
 
         // Try each repository until we find the package
         for repo in repositories {
-            let url = format!("{}/packages/{}", repo.url, package);
-            let client = Client::builder()
-                .timeout(Duration::from_secs(30))
-                .build()?;
-
-            let mut request = client.get(&url)
-                .header(USER_AGENT, "get-package-manager/1.0");
-
-            // Add authentication if needed
-            if let Some(auth) = &repo.authentication {
-                request = match auth {
-                    RepositoryAuth::Basic { username, password } => {
-                        request.basic_auth(username, Some(password))
-                    }
-                    RepositoryAuth::Token { token } => {
-                        request.header("Authorization", format!("Bearer {}", token))
-                    }
-                    RepositoryAuth::OAuth2 { client_id, client_secret } => {
-                        request.basic_auth(client_id, Some(client_secret))
-                    }
-                };
+            if let Some(package_info) = self.fetch_from_repository(repo, package, config, logger)? {
+                pb.finish_with_message(format!(
+                    "Found '{}' {} in repository '{}'",
+                    package_info.name, package_info.version, repo.name
+                ));
+                return Ok((repo, package_info));
             }
+        }
 
-            let response = request.send()?;
+        pb.finish_with_message("Package not found in any repository");
+        Err(GetError::PackageNotFound(package.to_string()))
+    }
 
-            if response.status().is_success() {
-                let package_info: PackageInfo = match repo.package_format {
-                    PackageFormat::Json => response.json()?,
-                    PackageFormat::Yaml => serde_yaml::from_reader(response)?,
-                    PackageFormat::Toml => {
-                        let text = response.text()?;
-                        toml::from_str(&text)?
-                    }
-                    PackageFormat::MsgPack => rmp_serde::decode::from_read(response)?,
+    /// Resolve `name[@version]` to its manifest in one repository
+    pub fn fetch_from_repository(&self, repo: &Repository, package: &str, config: &Config, logger: &Logger) -> Result<Option<PackageInfo>, GetError> {
+        let (name, version) = split_package_spec(package);
+        let client = self.client()?;
+
+        let path = match self.fetch_index(repo, config)? {
+            Some(index) => {
+                let entry = match index.packages.iter().find(|p| p.name.eq_ignore_ascii_case(name)) {
+                    Some(entry) => entry,
+                    None => return Ok(None),
                 };
-                pb.finish_with_message("Package info fetched successfully");
-                return Ok(package_info);
+                match select_version(&entry.versions, |v| v.as_str(), version) {
+                    Some(selected) => format!("packages/{}/{}", entry.name, selected),
+                    None => {
+                        logger.warn(&format!(
+                            "Repository '{}' has '{}' but not version {}",
+                            repo.name, entry.name, version.unwrap_or_default()
+                        ));
+                        return Ok(None);
+                    }
+                }
             }
-        }
+            // Repositories without an index only serve the latest version
+            None => format!("packages/{}", name),
+        };
 
-        pb.finish_with_message("Package not found in any repository");
-        Err(GetError::PackageNotFound(package.to_string()))
+        self.get_negotiated::<PackageInfo>(&client, repo, &path)
+    }
+
+    /// Install `name[@version]` from one repository
+    pub fn install_from_repository(&self, repo: &Repository, package: &str, config: &Config, logger: &Logger, m: &MultiProgress) -> Result<InstallReport, GetError> {
+        let package_info = self
+            .fetch_from_repository(repo, package, config, logger)?
+            .ok_or_else(|| GetError::PackageNotFound(format!("'{}' not found in repository '{}'", package, repo.name)))?;
+        let installed = self.download_and_install(repo, &package_info, config, logger, m)?;
+        self.record_install(&package_info, &repo.name, &installed, config)?;
+        Ok(install_report(&package_info, &repo.name, installed.location))
     }
 
-    fn download_and_install(&self, package_info: &PackageInfo, config: &Config, logger: &Logger, m: &MultiProgress) -> Result<(), GetError> {
+    /// Remember what was installed and from where, for `uninstall`
+    pub fn record_install(&self, package_info: &PackageInfo, source: &str, installed: &InstalledFiles, config: &Config) -> Result<(), GetError> {
+        let db = PackageDb::open(&package_db_path(config))?;
+        db.record_install(&InstalledPackage {
+            name: package_info.name.clone(),
+            version: package_info.version.clone(),
+            source: source.to_string(),
+            installed_at: SystemTime::now(),
+            manifest: serde_json::to_string(package_info)?,
+            location: installed.location.clone(),
+            files: installed.files.clone(),
+            shims: installed.shims.clone(),
+        })
+    }
+
+    /// Download the artifact of `package_info`, found in `repo`, and install it
+    fn download_and_install(&self, repo: &Repository, package_info: &PackageInfo, config: &Config, logger: &Logger, m: &MultiProgress) -> Result<InstalledFiles, GetError> {
         let pb = m.add(ProgressBar::new_spinner());
         pb.set_message("Downloading package...");
         pb.enable_steady_tick(Duration::from_millis(100));
@@ -111,13 +411,18 @@ impl UniversalPackageManager {
             .timeout(Duration::from_secs(300))
             .build()?;
 
-        let response = client.get(&package_info.source_url)
-            .header(USER_AGENT, "get-package-manager/1.0")
-            .send()?;
+        // Artifacts hosted by the repository need its credentials; never leak
+        // them to another host
+        let request = if same_origin(&repo.url, &package_info.source_url) {
+            self.request(&client, repo, &package_info.source_url)?
+        } else {
+            client.get(&package_info.source_url).header(USER_AGENT, "get-package-manager/1.0")
+        };
+        let response = request.send()?;
 
         if !response.status().is_success() {
             return Err(GetError::NetworkError(format!(
-                "Failed to download package: HTTP {}", 
+                "Failed to download package: HTTP {}",
                 response.status()
             )));
         }
@@ -127,9 +432,7 @@ impl UniversalPackageManager {
         std::fs::create_dir_all(&download_dir)?;
 
         // Create temp file
-        let file_name = package_info.source_url.split('/').last()
-            .ok_or_else(|| GetError::InvalidInput("Invalid source URL".to_string()))?;
-        let file_path = download_dir.join(file_name);
+        let file_path = download_dir.join(artifact_file_name(package_info));
         let mut file = File::create(&file_path)?;
 
         // Download with progress
@@ -158,10 +461,19 @@ impl UniversalPackageManager {
 
         download_pb.finish_with_message("Download complete");
 
+        pb.set_message("Installing package...");
+        let installed = self.install_from_file(package_info, &file_path, config, logger, m)?;
+
+        pb.finish_with_message("Package installed successfully");
+        Ok(installed)
+    }
+
+    /// Verify an already downloaded artifact against the manifest checksum and
+    /// run its install instructions
+    pub fn install_from_file(&self, package_info: &PackageInfo, file_path: &Path, config: &Config, logger: &Logger, m: &MultiProgress) -> Result<InstalledFiles, GetError> {
         // Verify checksum
-        pb.set_message("Verifying checksum...");
         let mut hasher = Sha256::new();
-        let mut file = File::open(&file_path)?;
+        let mut file = File::open(file_path)?;
         let mut buffer = [0u8; 8192];
 
         loop {
@@ -173,83 +485,434 @@ impl UniversalPackageManager {
         }
 
         let computed_hash = hex_encode(hasher.finalize());
-        if computed_hash != package_info.checksum {
+        if !computed_hash.eq_ignore_ascii_case(&package_info.checksum) {
             return Err(GetError::ValidationError(format!(
                 "Checksum mismatch. Expected: {}, Got: {}",
                 package_info.checksum, computed_hash
             )));
         }
 
-        pb.set_message("Installing package...");
-
-        // Handle installation based on instructions
+        // Handle installation based on instructions; installers and scripts
+        // put their files wherever they like
         match &package_info.install_instructions {
             InstallInstructions::Executable { path, args } => {
-                self.install_executable(&file_path, path, args, logger, m)?;
+                self.install_executable(file_path, path, args, logger)?;
+                Ok(InstalledFiles::default())
             }
-            InstallInstructions::Archive { format, extract_path, post_extract_commands } => {
-                self.install_archive(&file_path, format, extract_path, post_extract_commands, logger, m)?;
+            InstallInstructions::Archive { format, extract_path, strip_components, post_extract_commands } => {
+                let extract_path = archive::expand_extract_path(extract_path, package_info)?;
+                let files = self.install_archive(file_path, *format, &extract_path, *strip_components, m)?;
+                self.run_post_extract_commands(file_path, &extract_path, post_extract_commands, logger)?;
+                Ok(InstalledFiles { location: Some(extract_path), files, shims: Vec::new() })
             }
             InstallInstructions::Script { interpreter, script } => {
-                self.install_script(&file_path, interpreter, script, logger, m)?;
+                self.install_script(file_path, interpreter, script, logger)?;
+                Ok(InstalledFiles::default())
+            }
+            InstallInstructions::Portable { format, strip_components, bin, post_extract_commands } => {
+                let dir = self.unpack_portable(package_info, file_path, *format, *strip_components, config, logger)?;
+                self.run_post_extract_commands(file_path, &dir, post_extract_commands, logger)?;
+                self.activate_portable(package_info, &dir, bin.as_ref(), config, logger)
+            }
+        }
+    }
+
+    /// Unpack into the app's version directory
+    fn unpack_portable(&self, package_info: &PackageInfo, file_path: &Path, format: Option<ArchiveFormat>, strip_components: usize, config: &Config, logger: &Logger) -> Result<PathBuf, GetError> {
+        let apps = Apps::from_config(config);
+        let (name, version) = (&package_info.name, &package_info.version);
+        let format = format.or_else(|| ArchiveFormat::detect(file_path));
+        let file_name = file_path.file_name().and_then(|n| n.to_str()).unwrap_or(name);
+        let dir = apps.unpack(name, version, file_path, format, strip_components, file_name)?;
+        logger.log(&format!("Unpacked '{}' {} to '{}'", name, version, dir.display()));
+        Ok(dir)
+    }
+
+    /// Make an unpacked version current and shim its commands
+    fn activate_portable(&self, package_info: &PackageInfo, dir: &Path, bin: Option<&BinSpec>, config: &Config, logger: &Logger) -> Result<InstalledFiles, GetError> {
+        let apps = Apps::from_config(config);
+        let (name, version) = (&package_info.name, &package_info.version);
+        let current = apps.set_current(name, version)?;
+        // The previous version's shims may name commands this one dropped
+        apps.remove_shims(name)?;
+        let shims = match bin {
+            Some(bin) => bin.shims(),
+            None => find_executables(dir),
+        };
+        let shims = apps.create_shims(name, &shims)?;
+        for shim in &shims {
+            logger.log(&format!("Created shim '{}'", shim.display()));
+        }
+        Ok(InstalledFiles {
+            location: Some(current),
+            files: apps.files(name, version),
+            shims,
+        })
+    }
+
+    /// Run a downloaded installer. `path` names the program to run; when empty
+    /// the downloaded file itself is executed.
+    fn install_executable(&self, file_path: &Path, path: &str, args: &[String], logger: &Logger) -> Result<(), GetError> {
+        let program = if path.is_empty() { file_path.to_path_buf() } else { PathBuf::from(path) };
+        logger.log(&format!("Running installer '{}'", program.display()));
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            if program == file_path {
+                std::fs::set_permissions(file_path, std::fs::Permissions::from_mode(0o755))?;
+            }
+        }
+
+        let status = Command::new(&program)
+            .args(args)
+            .env("GET_PACKAGE_FILE", file_path)
+            .status()?;
+
+        if status.success() {
+            Ok(())
+        } else {
+            Err(GetError::CommandError(format!(
+                "Installer '{}' exited with {}",
+                program.display(),
+                status
+            )))
+        }
+    }
+
+    /// Run an install script with `interpreter`; the downloaded file is passed
+    /// as `GET_PACKAGE_FILE`.
+    fn install_script(&self, file_path: &Path, interpreter: &str, script: &str, logger: &Logger) -> Result<(), GetError> {
+        logger.log(&format!("Running install script with '{}'", interpreter));
+
+        let mut script_file = tempfile::Builder::new().suffix(script_extension(interpreter)).tempfile()?;
+        script_file.write_all(script.as_bytes())?;
+        script_file.flush()?;
+
+        let status = Command::new(interpreter)
+            .arg(script_file.path())
+            .env("GET_PACKAGE_FILE", file_path)
+            .status()?;
+
+        if status.success() {
+            Ok(())
+        } else {
+            Err(GetError::CommandError(format!(
+                "Install script exited with {}",
+                status
+            )))
+        }
+    }
+
+    /// Extract a downloaded archive into `extract_path`, returning the files
+    /// and directories it created
+    fn install_archive(&self, file_path: &Path, format: ArchiveFormat, extract_path: &Path, strip_components: usize, m: &MultiProgress) -> Result<Vec<PathBuf>, GetError> {
+        let pb = m.add(ProgressBar::new_spinner());
+        pb.set_message(format!("Extracting to '{}'...", extract_path.display()));
+        pb.enable_steady_tick(Duration::from_millis(100));
+
+        let summary = archive::extract(file_path, format, extract_path, strip_components)?;
+        pb.finish_with_message(format!(
+            "Extracted {} files to '{}'",
+            summary.files,
+            extract_path.display()
+        ));
+        Ok(summary.paths.into_iter().chain(summary.created_dirs).collect())
+    }
+
+    /// Run `commands` in `extract_path` through the platform shell
+    fn run_post_extract_commands(&self, file_path: &Path, extract_path: &Path, commands: &[String], logger: &Logger) -> Result<(), GetError> {
+        for command in commands {
+            logger.log(&format!("Running post-extract command: {}", command));
+            let mut shell = if cfg!(target_os = "windows") {
+                let mut shell = Command::new("cmd");
+                shell.args(["/C", command]);
+                shell
+            } else {
+                let mut shell = Command::new("sh");
+                shell.args(["-c", command]);
+                shell
+            };
+
+            let status = shell
+                .current_dir(extract_path)
+                .env("GET_PACKAGE_FILE", file_path)
+                .env("GET_EXTRACT_PATH", extract_path)
+                .status()?;
+            if !status.success() {
+                return Err(GetError::CommandError(format!(
+                    "Post-extract command '{}' exited with {}",
+                    command, status
+                )));
             }
         }
 
-        pb.finish_with_message("Package installed successfully");
         Ok(())
     }
 
-    fn handle_uninstall(&self, package: &str, logger: &Logger, m: &MultiProgress) -> Result<(), GetError> {
-        // TODO: Implement package uninstallation
-        unimplemented!()
+    /// Uninstall a package using the manifest recorded when it was installed
+    fn handle_uninstall(&self, package: &str, config: &Config, logger: &Logger) -> Result<UninstallReport, GetError> {
+        let db = PackageDb::open(&package_db_path(config))?;
+        let (name, _) = split_package_spec(package);
+        let installed = db
+            .installed(name)?
+            .ok_or_else(|| GetError::PackageNotFound(format!("'{}' was not installed from a repository", name)))?;
+        let package_info: PackageInfo = serde_json::from_str(&installed.manifest)?;
+
+        logger.log(&format!(
+            "Uninstalling '{}' {} (installed from '{}')",
+            installed.name, installed.version, installed.source
+        ));
+        match (&package_info.uninstall_instructions, &package_info.install_instructions) {
+            (Some(UninstallInstructions::Executable { path, args }), _) => {
+                self.run_uninstaller(Command::new(path).args(args), path)?;
+            }
+            (Some(UninstallInstructions::Script { interpreter, script }), _) => {
+                let mut script_file = tempfile::Builder::new().suffix(script_extension(interpreter)).tempfile()?;
+                script_file.write_all(script.as_bytes())?;
+                script_file.flush()?;
+                self.run_uninstaller(Command::new(interpreter).arg(script_file.path()), interpreter)?;
+            }
+            (Some(UninstallInstructions::RemovePath { path }), _) => {
+                remove_path(&archive::expand_extract_path(path, &package_info)?)?;
+            }
+            (None, InstallInstructions::Archive { extract_path, .. }) => {
+                let location = archive::expand_extract_path(extract_path, &package_info)?;
+                if installed.files.is_empty() {
+                    return Err(GetError::CommandError(format!(
+                        "'{}' has no record of its files; remove '{}' manually",
+                        installed.name,
+                        location.display()
+                    )));
+                }
+                remove_recorded_files(&location, &installed.files, logger)?;
+            }
+            (None, InstallInstructions::Portable { .. }) => {
+                Apps::from_config(config).remove(&installed.name)?;
+            }
+            (None, _) => {
+                return Err(GetError::CommandError(format!(
+                    "'{}' has no uninstall instructions; remove it manually",
+                    installed.name
+                )));
+            }
+        }
+
+        db.remove_installed(&installed.name)?;
+        Ok(UninstallReport {
+            source: installed.source,
+            name: installed.name,
+            version: Some(installed.version),
+        })
     }
 
-    fn search_repositories(&self, query: &str, config: &Config, logger: &Logger, m: &MultiProgress) -> Result<Vec<PackageInfo>, GetError> {
-        // TODO: Implement repository searching
-        unimplemented!()
+    fn run_uninstaller(&self, command: &mut Command, program: &str) -> Result<(), GetError> {
+        let status = command.status()?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(GetError::CommandError(format!(
+                "Uninstaller '{}' exited with {}",
+                program, status
+            )))
+        }
     }
 
-    fn display_search_results(&self, results: &[PackageInfo], logger: &Logger) -> Result<(), GetError> {
-        // TODO: Implement search results display
-        unimplemented!()
+    /// Search every configured repository, using its `search` endpoint when
+    /// available and its index otherwise
+    pub fn search_repositories(&self, query: &str, config: &Config, logger: &Logger) -> Result<Vec<RepositorySearchResult>, GetError> {
+        let client = self.client()?;
+        let encoded: String = url::form_urlencoded::byte_serialize(query.as_bytes()).collect();
+        let mut results = Vec::new();
+
+        for repo in config.get_repositories() {
+            let url = format!("{}/search?q={}", repo.url.trim_end_matches('/'), encoded);
+            let response = match self.request(&client, repo, &url).and_then(|request| Ok(request.send()?)) {
+                Ok(response) => response,
+                Err(e) => {
+                    logger.warn(&format!("Repository '{}' is unreachable: {}", repo.name, e));
+                    continue;
+                }
+            };
+
+            // One broken repository shouldn't hide results from the others
+            let matches: Vec<IndexEntry> = if response.status().is_success() {
+                match self.decode_response::<RepositoryIndex>(repo, response) {
+                    Ok(index) => index.packages,
+                    Err(e) => {
+                        logger.warn(&format!("Repository '{}' returned an invalid search response: {}", repo.name, e));
+                        continue;
+                    }
+                }
+            } else {
+                // Static repositories have no search endpoint
+                match self.fetch_index(repo, config) {
+                    Ok(Some(index)) => index.packages.into_iter().filter(|p| p.matches(query)).collect(),
+                    Ok(None) => {
+                        logger.warn(&format!("Repository '{}' supports neither search nor index", repo.name));
+                        continue;
+                    }
+                    Err(e) => {
+                        logger.warn(&format!("Failed to fetch the index of repository '{}': {}", repo.name, e));
+                        continue;
+                    }
+                }
+            };
+
+            results.extend(matches.into_iter().map(|entry| RepositorySearchResult {
+                repository: repo.name.clone(),
+                entry,
+            }));
+        }
+
+        Ok(results)
+    }
+}
+
+/// Delete the files an archive install recorded, then the directories it
+/// created that are left empty. Paths outside `location` (other than the
+/// parents it created for it) and directories it didn't create are kept.
+fn remove_recorded_files(location: &Path, recorded: &[PathBuf], logger: &Logger) -> Result<(), GetError> {
+    let location = location.canonicalize().unwrap_or_else(|_| location.to_path_buf());
+    let mut paths: Vec<&PathBuf> = recorded
+        .iter()
+        .filter(|path| path.starts_with(&location) || location.starts_with(path))
+        .collect();
+    // Deepest first, so a directory's contents go before the directory
+    paths.sort_by_key(|path| std::cmp::Reverse(path.components().count()));
+
+    for path in paths {
+        let Ok(meta) = std::fs::symlink_metadata(path) else {
+            continue;
+        };
+        if !meta.is_dir() {
+            std::fs::remove_file(path)?;
+        } else if std::fs::remove_dir(path).is_err() {
+            logger.log(&format!("Keeping '{}', which holds files get didn't install", path.display()));
+        }
+    }
+    Ok(())
+}
+
+/// The name to save the artifact of `package_info` under: the last non-empty
+/// segment of its URL's path, or one derived from the URL when there is none
+fn artifact_file_name(package_info: &PackageInfo) -> String {
+    url::Url::parse(&package_info.source_url)
+        .ok()
+        .and_then(|url| {
+            let name = url.path_segments()?.rev().find(|segment| !segment.is_empty())?.to_string();
+            crate::utils::validate_path_component("file name", &name).is_ok().then_some(name)
+        })
+        .unwrap_or_else(|| {
+            let digest = hex_encode(Sha256::digest(package_info.source_url.as_bytes()));
+            format!("download-{}", &digest[..16])
+        })
+}
+
+/// Whether `a` and `b` share a scheme, host and port
+fn same_origin(a: &str, b: &str) -> bool {
+    match (url::Url::parse(a), url::Url::parse(b)) {
+        (Ok(a), Ok(b)) => a.origin() == b.origin(),
+        _ => false,
+    }
+}
+
+/// Delete an installed file or directory
+fn remove_path(path: &Path) -> Result<(), GetError> {
+    if path.is_dir() {
+        std::fs::remove_dir_all(path)?;
+    } else if path.exists() {
+        std::fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+fn script_extension(interpreter: &str) -> &'static str {
+    match interpreter.to_lowercase().as_str() {
+        "powershell" | "pwsh" => ".ps1",
+        "cmd" => ".cmd",
+        "python" | "python3" => ".py",
+        _ => ".sh",
     }
 }
 
 /// Package information structure
-#[derive(Debug, Serialize, Deserialize)]
-struct PackageInfo {
-    name: String,
-    version: String,
-    description: String,
-    author: Option<String>,
-    license: Option<String>,
-    source_url: String,
-    checksum: String,
-    dependencies: Vec<String>,
-    install_instructions: InstallInstructions,
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackageInfo {
+    pub name: String,
+    pub version: String,
+    pub description: String,
+    pub author: Option<String>,
+    pub license: Option<String>,
+    pub source_url: String,
+    pub checksum: String,
+    /// Dependencies as `name`, `name@^1.2` or `name>=3,<4`
+    pub dependencies: Vec<String>,
+    pub install_instructions: InstallInstructions,
+    /// How to remove the package; archives default to deleting `extract_path`
+    /// and portable apps to removing the app and its shims
+    #[serde(default)]
+    pub uninstall_instructions: Option<UninstallInstructions>,
 }
 
 /// Package installation instructions
-#[derive(Debug, Serialize, Deserialize)]
-enum InstallInstructions {
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum InstallInstructions {
     Executable {
         path: String,
         args: Vec<String>,
     },
     Archive {
         format: ArchiveFormat,
+        /// Destination; see `archive::expand_extract_path` for placeholders
         extract_path: String,
+        /// Leading path components dropped from every entry
+        #[serde(default)]
+        strip_components: usize,
+        /// Run in `extract_path` through the platform shell
         post_extract_commands: Vec<String>,
     },
     Script {
         interpreter: String,
         script: String,
     },
+    /// Unpack into `~/.get/apps/<name>/<version>`, switch `current` to it and
+    /// shim its commands into `~/.get/bin`
+    Portable {
+        /// `None` for a bare program, or to detect the format from the file name
+        #[serde(default)]
+        format: Option<ArchiveFormat>,
+        #[serde(default)]
+        strip_components: usize,
+        /// The commands to shim, in Scoop's `bin` format; by default every
+        /// program found in the app
+        #[serde(default)]
+        bin: Option<BinSpec>,
+        /// Run in the version directory through the platform shell
+        #[serde(default)]
+        post_extract_commands: Vec<String>,
+    },
+}
+
+/// Package removal instructions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum UninstallInstructions {
+    Executable {
+        path: String,
+        args: Vec<String>,
+    },
+    Script {
+        interpreter: String,
+        script: String,
+    },
+    RemovePath {
+        path: String,
+    },
 }
 
 /// Supported archive formats
-#[derive(Debug, Serialize, Deserialize)]
-enum ArchiveFormat {
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ArchiveFormat {
     Zip,
     TarGz,
     TarXz,
@@ -257,28 +920,221 @@ enum ArchiveFormat {
     SevenZip,
 }
 
-/// Repository information
-#[derive(Debug, Serialize, Deserialize)]
-struct Repository {
-    name: String,
-    url: String,
-    package_format: PackageFormat,
-    authentication: Option<RepositoryAuth>,
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logging::LogLevel;
+    use indicatif::ProgressDrawTarget;
+    use std::fs;
 
-/// Supported package formats
-#[derive(Debug, Serialize, Deserialize)]
-enum PackageFormat {
-    Json,
-    Yaml,
-    Toml,
-    MsgPack,
-}
+    fn write_zip(path: &Path, entries: &[&str]) {
+        let mut zip = zip::ZipWriter::new(File::create(path).unwrap());
+        for entry in entries {
+            zip.start_file(*entry, zip::write::SimpleFileOptions::default()).unwrap();
+            zip.write_all(entry.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    /// Extract like an archive install and return what it records
+    fn install(archive_path: &Path, dest: &Path) -> Vec<PathBuf> {
+        let summary = archive::extract(archive_path, ArchiveFormat::Zip, dest, 0).unwrap();
+        summary.paths.into_iter().chain(summary.created_dirs).collect()
+    }
+
+    #[test]
+    fn uninstall_keeps_a_directory_it_did_not_create() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive_path = dir.path().join("tool.zip");
+        write_zip(&archive_path, &["bin/tool", "README"]);
+        let dest = dir.path().join("shared");
+        fs::create_dir(&dest).unwrap();
+        fs::write(dest.join("mine.txt"), "keep").unwrap();
+
+        let recorded = install(&archive_path, &dest);
+        remove_recorded_files(&dest, &recorded, &Logger::new(LogLevel::Minimal)).unwrap();
+
+        assert!(!dest.join("bin").exists());
+        assert!(!dest.join("README").exists());
+        assert!(dest.join("mine.txt").exists());
+
+        // Even once empty, it wasn't ours to remove
+        fs::remove_file(dest.join("mine.txt")).unwrap();
+        let recorded = install(&archive_path, &dest);
+        remove_recorded_files(&dest, &recorded, &Logger::new(LogLevel::Minimal)).unwrap();
+        assert!(dest.is_dir());
+    }
+
+    #[test]
+    fn uninstall_removes_directories_it_created_once_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive_path = dir.path().join("tool.zip");
+        write_zip(&archive_path, &["bin/tool", "lib/a/b.so", "README"]);
+        let dest = dir.path().join("opt").join("tool");
+
+        let recorded = install(&archive_path, &dest);
+        // A file added after the install keeps its directory alive
+        fs::write(dest.join("lib").join("cache"), "").unwrap();
+        remove_recorded_files(&dest, &recorded, &Logger::new(LogLevel::Minimal)).unwrap();
+
+        assert!(!dest.join("bin").exists());
+        assert!(!dest.join("lib").join("a").exists());
+        assert!(dest.join("lib").join("cache").exists());
+
+        fs::remove_file(dest.join("lib").join("cache")).unwrap();
+        remove_recorded_files(&dest, &recorded, &Logger::new(LogLevel::Minimal)).unwrap();
+        assert!(!dir.path().join("opt").exists());
+        assert!(dir.path().exists());
+    }
+
+    #[test]
+    fn uninstall_ignores_recorded_paths_outside_the_location() {
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("tool");
+        fs::create_dir(&dest).unwrap();
+        let outside = dir.path().join("other.txt");
+        fs::write(&outside, "").unwrap();
+
+        remove_recorded_files(&dest, std::slice::from_ref(&outside), &Logger::new(LogLevel::Minimal)).unwrap();
+        assert!(outside.exists());
+    }
+
+    fn package(source_url: &str) -> PackageInfo {
+        PackageInfo {
+            name: "tool".to_string(),
+            version: "1.0".to_string(),
+            description: String::new(),
+            author: None,
+            license: None,
+            source_url: source_url.to_string(),
+            checksum: String::new(),
+            dependencies: Vec::new(),
+            install_instructions: InstallInstructions::Script { interpreter: "sh".to_string(), script: String::new() },
+            uninstall_instructions: None,
+        }
+    }
+
+    #[test]
+    fn names_downloads_after_the_last_path_segment() {
+        let name = |url: &str| artifact_file_name(&package(url));
+        assert_eq!(name("https://example.com/pkg/tool-1.0.zip"), "tool-1.0.zip");
+        assert_eq!(name("https://example.com/pkg/tool-1.0.zip?sig=abc&exp=1"), "tool-1.0.zip");
+        assert_eq!(name("https://example.com/pkg/tool-1.0.zip#top"), "tool-1.0.zip");
+        assert_eq!(name("https://example.com/pkg/tool/"), "tool");
+
+        // No usable segment: a name derived from the URL, never the directory itself
+        for url in ["https://example.com/", "https://example.com", "not a url"] {
+            let derived = name(url);
+            assert!(derived.starts_with("download-") && derived.len() == "download-".len() + 16, "{}", derived);
+        }
+        assert_ne!(name("https://a.example.com/"), name("https://b.example.com/"));
+    }
+
+    #[test]
+    fn refuses_to_send_oauth2_secrets() {
+        let repo = Repository {
+            name: "private".to_string(),
+            url: "https://example.com".to_string(),
+            package_format: crate::config::PackageFormat::Json,
+            authentication: Some(RepositoryAuth::OAuth2 { client_id: "id".to_string(), client_secret: "secret".to_string() }),
+            priority: None,
+        };
+        let client = Client::new();
+        let result = UniversalPackageManager.request(&client, &repo, "https://example.com/index");
+        assert!(matches!(result, Err(GetError::ConfigError(ref message)) if message.contains("OAuth2")));
 
-/// Repository authentication methods
-#[derive(Debug, Serialize, Deserialize)]
-enum RepositoryAuth {
-    Basic { username: String, password: String },
-    Token { token: String },
-    OAuth2 { client_id: String, client_secret: String },
+        let repo = Repository { authentication: Some(RepositoryAuth::Token { token: "t".to_string() }), ..repo };
+        let request = UniversalPackageManager.request(&client, &repo, "https://example.com/index").unwrap().build().unwrap();
+        assert_eq!(request.headers()["Authorization"], "Bearer t");
+    }
+
+    /// A backend that answers searches with its own name, or fails
+    struct Fake {
+        name: &'static str,
+        fails: bool,
+    }
+
+    impl PackageManager for Fake {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn install(&self, package: &str, _config: &Config, _logger: &Logger, _m: &MultiProgress) -> Result<InstallReport, GetError> {
+            Ok(InstallReport { source: self.name.to_string(), name: package.to_string(), version: None, location: None })
+        }
+
+        fn uninstall(&self, package: &str, _config: &Config, _logger: &Logger, _m: &MultiProgress) -> Result<UninstallReport, GetError> {
+            Ok(UninstallReport { source: self.name.to_string(), name: package.to_string(), version: None })
+        }
+
+        fn search(&self, query: &str, _config: &Config, _logger: &Logger, _m: &MultiProgress) -> Result<Vec<SearchHit>, GetError> {
+            if self.fails {
+                return Err(GetError::NetworkError("offline".to_string()));
+            }
+            Ok(vec![SearchHit { source: self.name.to_string(), name: query.to_string(), version: None, description: None }])
+        }
+    }
+
+    fn registry(backends: &[(&'static str, bool)]) -> BackendRegistry {
+        let mut registry = BackendRegistry::new();
+        for &(name, fails) in backends {
+            registry.register(Box::new(Fake { name, fails }));
+        }
+        registry
+    }
+
+    #[test]
+    fn registry_dispatches_by_name() {
+        let registry = registry(&[("winget", false), ("scoop", false), ("Winget", true)]);
+        let (config, logger, m) = (Config::default(), Logger::new(LogLevel::Minimal), MultiProgress::with_draw_target(ProgressDrawTarget::hidden()));
+
+        let names: Vec<_> = registry.iter().map(|b| b.name()).collect();
+        assert_eq!(names, ["winget", "scoop", "Winget"]);
+        // Case-insensitive, and the first registered wins
+        assert_eq!(registry.get("WINGET").unwrap().name(), "winget");
+        assert!(registry.get("choco").is_none());
+
+        let report = registry.get("scoop").unwrap().install("git", &config, &logger, &m).unwrap();
+        assert_eq!((report.source.as_str(), report.name.as_str()), ("scoop", "git"));
+        let report = registry.get("scoop").unwrap().uninstall("git", &config, &logger, &m).unwrap();
+        assert_eq!((report.source.as_str(), report.name.as_str()), ("scoop", "git"));
+    }
+
+    #[test]
+    fn search_all_keeps_going_past_a_failing_backend() {
+        let registry = registry(&[("first", false), ("broken", true), ("last", false)]);
+        let m = MultiProgress::with_draw_target(ProgressDrawTarget::hidden());
+        let hits = registry.search_all("jq", &Config::default(), &Logger::new(LogLevel::Minimal), &m);
+        let sources: Vec<_> = hits.iter().map(|hit| hit.source.as_str()).collect();
+        assert_eq!(sources, ["first", "last"]);
+        assert!(hits.iter().all(|hit| hit.name == "jq"));
+    }
+
+    #[test]
+    fn repository_hits_carry_the_latest_version() {
+        let result = RepositorySearchResult {
+            repository: "main".to_string(),
+            entry: IndexEntry { name: "tool".to_string(), description: "A tool".to_string(), versions: vec!["1.10.0".to_string(), "1.9.2".to_string(), "2.0.0-rc.1".to_string()] },
+        };
+        assert_eq!(
+            result.into_hit(),
+            SearchHit { source: "main".to_string(), name: "tool".to_string(), version: Some("1.10.0".to_string()), description: Some("A tool".to_string()) }
+        );
+
+        let empty = RepositorySearchResult { repository: "main".to_string(), entry: IndexEntry { name: "tool".to_string(), description: String::new(), versions: Vec::new() } };
+        assert_eq!(empty.into_hit().version, None);
+    }
+
+    #[test]
+    fn reports_round_trip_through_json() {
+        let report = InstallReport { source: "github".to_string(), name: "rg".to_string(), version: Some("14.1.0".to_string()), location: Some(PathBuf::from("/opt/rg")) };
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json, serde_json::json!({ "source": "github", "name": "rg", "version": "14.1.0", "location": "/opt/rg" }));
+        assert_eq!(serde_json::from_value::<InstallReport>(json).unwrap(), report);
+
+        let report = UninstallReport { source: "scoop".to_string(), name: "git".to_string(), version: None };
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json, serde_json::json!({ "source": "scoop", "name": "git", "version": null }));
+        assert_eq!(serde_json::from_value::<UninstallReport>(json).unwrap(), report);
+    }
 }
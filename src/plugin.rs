@@ -0,0 +1,419 @@
+//! External backend plugins
+//!
+//! Any executable named `get-<name>` on the PATH is a backend, used with a
+//! `<name>:` qualifier, a pin, or a `[priorities]` entry like the built-in
+//! ones. Built-in backends and repositories of the same name take precedence.
+//!
+//! Each operation runs the plugin once. It is sent one JSON request on stdin
+//! and must answer with one JSON response on stdout; stderr is passed through
+//! to the user for progress output.
+//!
+//! ```text
+//! -> {"protocol": 1, "command": "search", "query": "ripgrep"}
+//! -> {"protocol": 1, "command": "info", "package": "ripgrep", "version": ">=14"}
+//! -> {"protocol": 1, "command": "install", "package": "ripgrep", "version": "14.1.0"}
+//! -> {"protocol": 1, "command": "uninstall", "package": "ripgrep"}
+//! -> {"protocol": 1, "command": "list"}
+//!
+//! <- {"result": ...}
+//! <- {"error": {"kind": "not_found", "message": "no package 'ripgrep'"}}
+//! ```
+//!
+//! `search` and `list` return a list of [`PluginPackage`], `info` and
+//! `install` a single one (`install` with the version it installed), and
+//! `uninstall` anything. `version` is omitted when no version was asked for.
+//! Error kinds are `not_found`, `invalid_input`, `unsupported`, `network` and
+//! `validation`; anything else is reported as a failed command.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::ffi::OsStr;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::time::SystemTime;
+
+use indicatif::MultiProgress;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::config::Config;
+use crate::error::GetError;
+use crate::logging::Logger;
+use crate::native::is_executable;
+use crate::package_db::{package_db_path, InstalledPackage, PackageDb};
+use crate::package_manager::{InstallReport, PackageManager, SearchHit, UninstallReport};
+use crate::version::split_package_spec;
+
+/// Version of the request and response format
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Executable name prefix that marks a plugin
+const PREFIX: &str = "get-";
+
+/// A request sent to a plugin
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "command", rename_all = "lowercase")]
+pub enum PluginRequest<'a> {
+    Search {
+        query: &'a str,
+    },
+    Info {
+        package: &'a str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        version: Option<&'a str>,
+    },
+    Install {
+        package: &'a str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        version: Option<&'a str>,
+    },
+    Uninstall {
+        package: &'a str,
+    },
+    List,
+}
+
+impl PluginRequest<'_> {
+    fn command(&self) -> &'static str {
+        match self {
+            PluginRequest::Search { .. } => "search",
+            PluginRequest::Info { .. } => "info",
+            PluginRequest::Install { .. } => "install",
+            PluginRequest::Uninstall { .. } => "uninstall",
+            PluginRequest::List => "list",
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Envelope<'a> {
+    protocol: u32,
+    #[serde(flatten)]
+    request: &'a PluginRequest<'a>,
+}
+
+#[derive(Deserialize)]
+struct PluginResponse {
+    #[serde(default)]
+    result: Value,
+    #[serde(default)]
+    error: Option<PluginError>,
+}
+
+/// An error reported by a plugin
+#[derive(Debug, Clone, Deserialize)]
+pub struct PluginError {
+    pub kind: String,
+    pub message: String,
+}
+
+/// A package as reported by a plugin
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginPackage {
+    pub name: String,
+    #[serde(default)]
+    pub version: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Other packages it needs, as `get install` requests
+    #[serde(default)]
+    pub dependencies: Vec<String>,
+}
+
+/// A `get-<name>` executable
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Plugin {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+/// The plugin name of an executable file name, if it is one
+fn plugin_name(file_name: &str) -> Option<&str> {
+    let name = file_name.strip_prefix(PREFIX)?;
+    let name = if cfg!(target_os = "windows") {
+        let (stem, extension) = name.rsplit_once('.')?;
+        ["exe", "cmd", "bat"].iter().any(|e| extension.eq_ignore_ascii_case(e)).then_some(stem)?
+    } else {
+        name
+    };
+    (!name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')).then_some(name)
+}
+
+/// Every plugin on the PATH, by name; the first one found wins, as in a shell
+pub fn discover_plugins() -> Vec<Plugin> {
+    env::var_os("PATH").map(|path| discover_plugins_in(&path)).unwrap_or_default()
+}
+
+/// Every plugin in the directories of `path`, by name
+pub fn discover_plugins_in(path: &OsStr) -> Vec<Plugin> {
+    let mut plugins: BTreeMap<String, PathBuf> = BTreeMap::new();
+    for dir in env::split_paths(path) {
+        let Ok(entries) = dir.read_dir() else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let file_name = entry.file_name();
+            let Some(name) = file_name.to_str().and_then(plugin_name) else {
+                continue;
+            };
+            let name = name.to_lowercase();
+            if !plugins.contains_key(&name) && is_executable(&entry.path()) {
+                plugins.insert(name, entry.path());
+            }
+        }
+    }
+    plugins.into_iter().map(|(name, path)| Plugin { name, path }).collect()
+}
+
+impl Plugin {
+    /// The plugin called `name`, if one is on the PATH
+    pub fn find(name: &str) -> Option<Plugin> {
+        discover_plugins().into_iter().find(|p| p.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Like [`Plugin::find`], for a source that has to exist
+    pub fn named(name: &str) -> Result<Plugin, GetError> {
+        Plugin::find(name).ok_or_else(|| GetError::ConfigError(format!("Plugin 'get-{}' is not on the PATH", name)))
+    }
+
+    /// Run one request and decode its result
+    pub fn call<T: DeserializeOwned>(&self, request: &PluginRequest) -> Result<T, GetError> {
+        let mut child = Command::new(&self.path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .map_err(|e| GetError::CommandError(format!("Failed to start plugin '{}': {}", self.path.display(), e)))?;
+
+        let input = serde_json::to_vec(&Envelope { protocol: PROTOCOL_VERSION, request })?;
+        if let Some(mut stdin) = child.stdin.take() {
+            // A plugin may exit without reading; its response says what happened
+            let _ = stdin.write_all(&input);
+        }
+        let output = child.wait_with_output()?;
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let response: PluginResponse = match serde_json::from_str(stdout.trim()) {
+            Ok(response) => response,
+            Err(_) if !output.status.success() => {
+                return Err(GetError::CommandError(format!(
+                    "Plugin '{}' {} failed ({})",
+                    self.name,
+                    request.command(),
+                    output.status.code().map_or("killed".to_string(), |code| format!("exit code {}", code))
+                )))
+            }
+            Err(e) => {
+                return Err(GetError::ParseError(format!(
+                    "Plugin '{}' gave an invalid {} response: {}",
+                    self.name,
+                    request.command(),
+                    e
+                )))
+            }
+        };
+
+        if let Some(error) = response.error {
+            return Err(self.map_error(error));
+        }
+        serde_json::from_value(response.result).map_err(|e| {
+            GetError::ParseError(format!("Plugin '{}' gave an invalid {} result: {}", self.name, request.command(), e))
+        })
+    }
+
+    fn map_error(&self, error: PluginError) -> GetError {
+        let message = format!("{} (plugin '{}')", error.message, self.name);
+        match error.kind.as_str() {
+            "not_found" => GetError::PackageNotFound(message),
+            "invalid_input" | "unsupported" => GetError::InvalidInput(message),
+            "network" => GetError::NetworkError(message),
+            "validation" => GetError::ValidationError(message),
+            _ => GetError::CommandError(message),
+        }
+    }
+
+    pub fn search_packages(&self, query: &str) -> Result<Vec<PluginPackage>, GetError> {
+        self.call(&PluginRequest::Search { query })
+    }
+
+    pub fn info(&self, package: &str, version: Option<&str>) -> Result<PluginPackage, GetError> {
+        self.call(&PluginRequest::Info { package, version })
+    }
+
+    pub fn installed_packages(&self) -> Result<Vec<PluginPackage>, GetError> {
+        self.call(&PluginRequest::List)
+    }
+}
+
+impl PackageManager for Plugin {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn install(&self, package: &str, config: &Config, logger: &Logger, _m: &MultiProgress) -> Result<InstallReport, GetError> {
+        let (name, version) = split_package_spec(package);
+        logger.log(&format!("Installing '{}' with plugin '{}'...", package, self.name));
+        let installed: PluginPackage = self.call(&PluginRequest::Install { package: name, version })?;
+
+        PackageDb::open(&package_db_path(config))?.record_install(&InstalledPackage {
+            name: installed.name.clone(),
+            version: installed.version.clone().unwrap_or_default(),
+            source: self.name.clone(),
+            installed_at: SystemTime::now(),
+            manifest: serde_json::to_string(&installed)?,
+            location: None,
+            files: Vec::new(),
+            shims: Vec::new(),
+        })?;
+        Ok(InstallReport {
+            source: self.name.clone(),
+            name: installed.name,
+            version: installed.version,
+            location: None,
+        })
+    }
+
+    fn uninstall(&self, package: &str, config: &Config, logger: &Logger, _m: &MultiProgress) -> Result<UninstallReport, GetError> {
+        let (name, _) = split_package_spec(package);
+        logger.log(&format!("Removing '{}' with plugin '{}'...", name, self.name));
+        self.call::<Value>(&PluginRequest::Uninstall { package: name })?;
+
+        let db = PackageDb::open(&package_db_path(config))?;
+        let version = db.installed(name)?.map(|record| record.version);
+        db.remove_installed(name)?;
+        Ok(UninstallReport {
+            source: self.name.clone(),
+            name: name.to_string(),
+            version,
+        })
+    }
+
+    fn search(&self, query: &str, _config: &Config, _logger: &Logger, _m: &MultiProgress) -> Result<Vec<SearchHit>, GetError> {
+        Ok(self
+            .search_packages(query)?
+            .into_iter()
+            .map(|package| SearchHit {
+                source: self.name.clone(),
+                name: package.name,
+                version: package.version,
+                description: package.description,
+            })
+            .collect())
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::logging::LogLevel;
+    use indicatif::ProgressDrawTarget;
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::Path;
+
+    /// Write an executable `get-<name>` that logs its request next to itself
+    /// and runs the shell `body`
+    fn plugin(dir: &Path, name: &str, body: &str) -> Plugin {
+        let path = dir.join(format!("get-{}", name));
+        let log = dir.join(format!("{}.requests", name));
+        fs::write(&path, format!("#!/bin/sh\nread -r request\necho \"$request\" >> '{}'\n{}\n", log.display(), body)).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        Plugin { name: name.to_string(), path }
+    }
+
+    fn requests(dir: &Path, name: &str) -> Vec<Value> {
+        let log = fs::read_to_string(dir.join(format!("{}.requests", name))).unwrap();
+        log.lines().map(|line| serde_json::from_str(line).unwrap()).collect()
+    }
+
+    fn quiet() -> (Logger, MultiProgress) {
+        (Logger::new(LogLevel::Minimal), MultiProgress::with_draw_target(ProgressDrawTarget::hidden()))
+    }
+
+    #[test]
+    fn discovers_plugins_in_path_order() {
+        let (first, second) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        plugin(first.path(), "brew", "true");
+        plugin(second.path(), "brew", "true");
+        plugin(second.path(), "nix", "true");
+        // Not executable, or not a plugin name
+        fs::write(second.path().join("get-data"), "").unwrap();
+        plugin(second.path(), "bad.name", "true");
+        fs::write(second.path().join("getter"), "").unwrap();
+
+        let path = env::join_paths([first.path(), second.path()]).unwrap();
+        let plugins = discover_plugins_in(&path);
+        let found: Vec<_> = plugins.iter().map(|p| (p.name.as_str(), p.path.parent().unwrap())).collect();
+        assert_eq!(found, [("brew", first.path()), ("nix", second.path())]);
+    }
+
+    #[test]
+    fn speaks_json_over_stdin_and_stdout() {
+        let dir = tempfile::tempdir().unwrap();
+        let plugin = plugin(
+            dir.path(),
+            "brew",
+            r#"case "$request" in
+  *'"search"'*) echo '{"result": [{"name": "jq", "version": "1.7.1", "description": "JSON processor"}]}' ;;
+  *'"info"'*) echo '{"result": {"name": "jq", "version": "1.7.1", "dependencies": ["oniguruma"]}}' ;;
+  *) echo '{"result": []}' ;;
+esac"#,
+        );
+
+        let hits = plugin.search("jq", &Config::default(), &quiet().0, &quiet().1).unwrap();
+        assert_eq!((hits[0].source.as_str(), hits[0].name.as_str()), ("brew", "jq"));
+        assert_eq!(hits[0].description.as_deref(), Some("JSON processor"));
+        assert_eq!(plugin.info("jq", Some(">=1.7")).unwrap().dependencies, ["oniguruma"]);
+        assert!(plugin.installed_packages().unwrap().is_empty());
+
+        assert_eq!(
+            requests(dir.path(), "brew"),
+            [
+                serde_json::json!({ "protocol": 1, "command": "search", "query": "jq" }),
+                serde_json::json!({ "protocol": 1, "command": "info", "package": "jq", "version": ">=1.7" }),
+                serde_json::json!({ "protocol": 1, "command": "list" }),
+            ]
+        );
+    }
+
+    #[test]
+    fn records_what_a_plugin_installs() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = Config { home: Some(dir.path().to_string_lossy().into_owned()), ..Default::default() };
+        let (logger, m) = quiet();
+        let plugin = plugin(dir.path(), "brew", r#"echo '{"result": {"name": "jq", "version": "1.7.1"}}'"#);
+
+        let report = plugin.install("jq@1.7.1", &config, &logger, &m).unwrap();
+        assert_eq!(report.version.as_deref(), Some("1.7.1"));
+        let installed = PackageDb::open(&package_db_path(&config)).unwrap().installed("jq").unwrap().unwrap();
+        assert_eq!((installed.version.as_str(), installed.source.as_str()), ("1.7.1", "brew"));
+
+        let report = plugin.uninstall("jq", &config, &logger, &m).unwrap();
+        assert_eq!(report.version.as_deref(), Some("1.7.1"));
+        assert!(PackageDb::open(&package_db_path(&config)).unwrap().installed("jq").unwrap().is_none());
+        assert_eq!(requests(dir.path(), "brew")[1], serde_json::json!({ "protocol": 1, "command": "uninstall", "package": "jq" }));
+    }
+
+    #[test]
+    fn maps_failures_to_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let failing = |kind: &str| {
+            let body = format!(r#"echo '{{"error": {{"kind": "{}", "message": "no"}}}}'"#, kind);
+            plugin(dir.path(), "failing", &body).search_packages("jq").unwrap_err()
+        };
+        assert!(matches!(failing("not_found"), GetError::PackageNotFound(_)));
+        assert!(matches!(failing("unsupported"), GetError::InvalidInput(_)));
+        assert!(matches!(failing("network"), GetError::NetworkError(_)));
+        assert!(matches!(failing("validation"), GetError::ValidationError(_)));
+        assert!(matches!(failing("strange"), GetError::CommandError(message) if message == "no (plugin 'failing')"));
+
+        let crashed = plugin(dir.path(), "crashed", "exit 3").search_packages("jq").unwrap_err();
+        assert!(matches!(crashed, GetError::CommandError(message) if message.contains("exit code 3")));
+        let garbled = plugin(dir.path(), "garbled", "echo hello").search_packages("jq").unwrap_err();
+        assert!(matches!(garbled, GetError::ParseError(_)));
+        let wrong_shape = plugin(dir.path(), "shape", r#"echo '{"result": 5}'"#).search_packages("jq").unwrap_err();
+        assert!(matches!(wrong_shape, GetError::ParseError(_)));
+    }
+}
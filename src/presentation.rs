@@ -0,0 +1,244 @@
+//! Terminal output for backend results
+//!
+//! Backends return [`SearchHit`], [`InstallReport`] and [`UninstallReport`]
+//! values and print nothing themselves, so library users get the data; this
+//! module is what the command-line tool renders them with, along with the
+//! install records of `get list` and `get info` and the `get outdated` table.
+
+use std::time::SystemTime;
+
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+
+use crate::error::GetError;
+use crate::logging::Logger;
+use crate::package_db::InstalledPackage;
+use crate::package_manager::{InstallReport, OutdatedPackage, SearchHit, UninstallReport};
+
+/// Print search results as one table, in the order the sources were searched
+pub fn print_search_results(query: &str, hits: &[SearchHit]) {
+    println!("\nSearch Results for '{}':\n", query);
+    if hits.is_empty() {
+        println!("No results found.");
+        return;
+    }
+
+    let rows: Vec<[String; 4]> = hits
+        .iter()
+        .map(|hit| {
+            [
+                hit.name.clone(),
+                hit.version.clone().unwrap_or_default(),
+                hit.source.clone(),
+                truncate(hit.description.as_deref().unwrap_or_default(), 60),
+            ]
+        })
+        .collect();
+    print_table(["Name", "Version", "Source", "Description"], &rows);
+}
+
+/// Print install records as a table
+pub fn print_installed(packages: &[InstalledPackage]) {
+    if packages.is_empty() {
+        println!("No packages installed by get.");
+        return;
+    }
+
+    let rows: Vec<[String; 5]> = packages
+        .iter()
+        .map(|package| {
+            [
+                package.name.clone(),
+                package.version.clone(),
+                package.source.clone(),
+                format_date(package.installed_at),
+                package.location.as_ref().map(|p| p.display().to_string()).unwrap_or_default(),
+            ]
+        })
+        .collect();
+    println!();
+    print_table(["Name", "Version", "Source", "Installed", "Location"], &rows);
+}
+
+/// Print install records as a JSON array, for scripts
+pub fn print_installed_json(packages: &[InstalledPackage]) -> Result<(), GetError> {
+    println!("{}", serde_json::to_string_pretty(&installed_json(packages))?);
+    Ok(())
+}
+
+/// Install records as the JSON `get list --json` prints
+pub fn installed_json(packages: &[InstalledPackage]) -> Value {
+    packages
+        .iter()
+        .map(|package| {
+            json!({
+                "name": package.name,
+                "version": package.version,
+                "source": package.source,
+                "installed_at": DateTime::<Utc>::from(package.installed_at).to_rfc3339(),
+                "manifest_hash": package.manifest_hash(),
+                "location": package.location,
+                "files": package.files,
+                "shims": package.shims,
+            })
+        })
+        .collect()
+}
+
+/// Print what is known about a package: its install record, if any, and
+/// the versions each source offers, newest first
+pub fn print_package_info(name: &str, installed: Option<&InstalledPackage>, available: &[(String, Vec<String>)]) {
+    println!("\n{}\n", name);
+    match installed {
+        Some(package) => {
+            println!("  Installed:  {} from {}", package.version, package.source);
+            println!("  Date:       {}", format_date(package.installed_at));
+            if let Some(location) = &package.location {
+                println!("  Location:   {}", location.display());
+            }
+            if !package.files.is_empty() {
+                println!("  Files:      {}", package.files.len());
+            }
+            for (i, shim) in package.shims.iter().enumerate() {
+                println!("  {}{}", if i == 0 { "Shims:      " } else { "            " }, shim.display());
+            }
+            println!("  Manifest:   sha256:{}", package.manifest_hash());
+        }
+        None => println!("  Not installed"),
+    }
+
+    if available.is_empty() {
+        return;
+    }
+    println!("\n  Available:\n");
+    let rows: Vec<[String; 2]> = available
+        .iter()
+        .map(|(source, versions)| [source.clone(), truncate(&versions.join(", "), 70)])
+        .collect();
+    print_table(["Source", "Versions"], &rows);
+}
+
+/// Print the packages `get upgrade` would upgrade
+pub fn print_outdated(packages: &[OutdatedPackage]) {
+    if packages.is_empty() {
+        println!("All packages are up to date.");
+        return;
+    }
+
+    let rows: Vec<[String; 5]> = packages
+        .iter()
+        .map(|package| {
+            let note = match (&package.install_spec, &package.held) {
+                (None, _) => "version check only; no manifest yet".to_string(),
+                (Some(_), Some(hold)) => format!("held to {}", hold),
+                (Some(_), None) => String::new(),
+            };
+            [
+                package.name.clone(),
+                package.installed.clone(),
+                package.available.clone(),
+                package.source.clone(),
+                note,
+            ]
+        })
+        .collect();
+    println!();
+    print_table(["Name", "Installed", "Available", "Source", "Note"], &rows);
+}
+
+/// Print rows under headers, each column as wide as its widest cell
+fn print_table<const N: usize>(headers: [&str; N], rows: &[[String; N]]) {
+    let mut widths = headers.map(str::len);
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let format_row = |cells: Vec<&str>| {
+        cells
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+
+    println!("  {}", format_row(headers.to_vec()));
+    println!("  {}", widths.map(|w| "-".repeat(w)).join("  "));
+    for row in rows {
+        println!("  {}", format_row(row.iter().map(String::as_str).collect()));
+    }
+}
+
+fn format_date(time: SystemTime) -> String {
+    DateTime::<Utc>::from(time).format("%Y-%m-%d %H:%M").to_string()
+}
+
+pub fn print_install_report(report: &InstallReport, logger: &Logger) {
+    match &report.version {
+        Some(version) => logger.info(&format!(
+            "Package '{}' {} installed successfully from {}.",
+            report.name, version, report.source
+        )),
+        None => logger.info(&format!("Package '{}' installed successfully from {}.", report.name, report.source)),
+    }
+    if let Some(location) = &report.location {
+        logger.log(&format!("Installed to '{}'.", location.display()));
+    }
+}
+
+pub fn print_uninstall_report(report: &UninstallReport, logger: &Logger) {
+    match &report.version {
+        Some(version) => logger.info(&format!("Package '{}' {} uninstalled successfully.", report.name, version)),
+        None => logger.info(&format!("Package '{}' uninstalled successfully.", report.name)),
+    }
+}
+
+/// Shorten `text` to at most `max` characters
+fn truncate(text: &str, max: usize) -> String {
+    let text = text.lines().next().unwrap_or("").trim();
+    if text.chars().count() <= max {
+        text.to_string()
+    } else {
+        format!("{}...", text.chars().take(max - 3).collect::<String>())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    #[test]
+    fn lists_install_records_as_json() {
+        let package = InstalledPackage {
+            name: "tool".to_string(),
+            version: "1.2.0".to_string(),
+            source: "scoop/main".to_string(),
+            installed_at: SystemTime::UNIX_EPOCH + Duration::from_secs(86_400),
+            manifest: "{}".to_string(),
+            location: Some(PathBuf::from("apps/tool/current")),
+            files: vec![PathBuf::from("apps/tool/1.2.0")],
+            shims: vec![PathBuf::from("bin/tool")],
+        };
+
+        assert_eq!(
+            installed_json(std::slice::from_ref(&package)),
+            json!([{
+                "name": "tool",
+                "version": "1.2.0",
+                "source": "scoop/main",
+                "installed_at": "1970-01-02T00:00:00+00:00",
+                "manifest_hash": package.manifest_hash(),
+                "location": "apps/tool/current",
+                "files": ["apps/tool/1.2.0"],
+                "shims": ["bin/tool"],
+            }])
+        );
+        assert_eq!(installed_json(&[]), json!([]));
+    }
+}
@@ -0,0 +1,186 @@
+//! HTTP repository protocol
+//!
+//! A repository listed in `Config.repositories` is a base URL serving these
+//! endpoints:
+//!
+//! | Endpoint                       | Response                                   |
+//! |--------------------------------|--------------------------------------------|
+//! | `GET index`                    | [`RepositoryIndex`], with an `ETag` header |
+//! | `GET search?q=<query>`         | [`RepositoryIndex`] of matching packages   |
+//! | `GET packages/{name}/{version}`| [`PackageInfo`] for that version           |
+//!
+//! Clients send `If-None-Match` with the last seen ETag when fetching the
+//! index; the server answers `304 Not Modified` if it is unchanged.
+//!
+//! Every endpoint supports content negotiation: the client sends an `Accept`
+//! header for its configured [`PackageFormat`] and decodes the response by
+//! its `Content-Type` (see [`media_type`]). Unknown content types are decoded
+//! with the configured format.
+//!
+//! Static repositories (plain file servers) cannot negotiate or search, so
+//! clients fall back to `index.{ext}` and `packages/{name}/{version}.{ext}`
+//! when the extensionless path returns 404, and search the index locally
+//! when `search` is unavailable.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
+
+use crate::config::PackageFormat;
+use crate::error::GetError;
+use crate::package_manager::PackageInfo;
+use crate::version::Version;
+
+/// Every format, in the order servers prefer them
+pub const FORMATS: [PackageFormat; 4] = [
+    PackageFormat::Json,
+    PackageFormat::MsgPack,
+    PackageFormat::Yaml,
+    PackageFormat::Toml,
+];
+
+/// The package list served at `index` and `search`
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct RepositoryIndex {
+    pub packages: Vec<IndexEntry>,
+}
+
+/// One package in a repository index
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexEntry {
+    pub name: String,
+    pub description: String,
+    /// Every published version
+    pub versions: Vec<String>,
+}
+
+impl IndexEntry {
+    /// Summarise the versions of one package
+    pub fn from_versions(versions: &[PackageInfo]) -> Option<IndexEntry> {
+        let first = versions.first()?;
+        Some(IndexEntry {
+            name: first.name.clone(),
+            description: first.description.clone(),
+            versions: versions.iter().map(|p| p.version.clone()).collect(),
+        })
+    }
+
+    /// Whether the name or description contains `query` (case-insensitive)
+    pub fn matches(&self, query: &str) -> bool {
+        let query = query.to_lowercase();
+        self.name.to_lowercase().contains(&query) || self.description.to_lowercase().contains(&query)
+    }
+}
+
+/// The media type used for a format
+pub fn media_type(format: &PackageFormat) -> &'static str {
+    match format {
+        PackageFormat::Json => "application/json",
+        PackageFormat::Yaml => "application/yaml",
+        PackageFormat::Toml => "application/toml",
+        PackageFormat::MsgPack => "application/msgpack",
+    }
+}
+
+/// The file extension used for a format by static repositories
+pub fn extension(format: &PackageFormat) -> &'static str {
+    match format {
+        PackageFormat::Json => "json",
+        PackageFormat::Yaml => "yaml",
+        PackageFormat::Toml => "toml",
+        PackageFormat::MsgPack => "msgpack",
+    }
+}
+
+/// Map a file extension to a format
+pub fn format_for_extension(ext: &str) -> Option<PackageFormat> {
+    match ext.to_lowercase().as_str() {
+        "json" => Some(PackageFormat::Json),
+        "yaml" | "yml" => Some(PackageFormat::Yaml),
+        "toml" => Some(PackageFormat::Toml),
+        "msgpack" => Some(PackageFormat::MsgPack),
+        _ => None,
+    }
+}
+
+/// Pick the response format for an `Accept` header, defaulting to JSON
+pub fn negotiate(accept: Option<&str>) -> PackageFormat {
+    accept
+        .and_then(|accept| accept.split(',').find_map(format_for_media_type))
+        .unwrap_or(PackageFormat::Json)
+}
+
+/// Map a `Content-Type` or `Accept` value to a format
+pub fn format_for_media_type(value: &str) -> Option<PackageFormat> {
+    let essence = value.split(';').next().unwrap_or("").trim().to_lowercase();
+    match essence.as_str() {
+        "application/json" => Some(PackageFormat::Json),
+        "application/yaml" | "application/x-yaml" | "text/yaml" => Some(PackageFormat::Yaml),
+        "application/toml" | "text/toml" => Some(PackageFormat::Toml),
+        "application/msgpack" | "application/x-msgpack" | "application/vnd.msgpack" => Some(PackageFormat::MsgPack),
+        _ => None,
+    }
+}
+
+/// Decode a response body in the given format
+pub fn decode<T: DeserializeOwned>(format: &PackageFormat, body: &[u8]) -> Result<T, GetError> {
+    Ok(match format {
+        PackageFormat::Json => serde_json::from_slice(body)?,
+        PackageFormat::Yaml => serde_yaml::from_slice(body)?,
+        PackageFormat::Toml => toml::from_str(&String::from_utf8(body.to_vec())?)?,
+        PackageFormat::MsgPack => rmp_serde::from_slice(body)?,
+    })
+}
+
+/// Encode a value in the given format
+pub fn encode<T: Serialize>(format: &PackageFormat, value: &T) -> Result<Vec<u8>, GetError> {
+    Ok(match format {
+        PackageFormat::Json => serde_json::to_vec_pretty(value)?,
+        PackageFormat::Yaml => serde_yaml::to_string(value)?.into_bytes(),
+        PackageFormat::Toml => toml::to_string(value)?.into_bytes(),
+        // Named fields keep MsgPack payloads readable by other implementations
+        PackageFormat::MsgPack => rmp_serde::to_vec_named(value)?,
+    })
+}
+
+/// Load every package manifest (`PackageInfo` in any supported format) under `dir`
+pub fn load_manifests(dir: &Path) -> Result<Vec<(PathBuf, PackageInfo)>, GetError> {
+    let mut manifests = Vec::new();
+    for entry in WalkDir::new(dir).sort_by_file_name() {
+        let entry = entry.map_err(|e| GetError::IoError(e.to_string()))?;
+        let path = entry.path();
+        let format = match path.extension().and_then(|e| e.to_str()).and_then(format_for_extension) {
+            Some(format) if entry.file_type().is_file() => format,
+            _ => continue,
+        };
+
+        let body = std::fs::read(path)?;
+        let package: PackageInfo = decode(&format, &body).map_err(|e| {
+            GetError::ParseError(format!("{}: {}", path.display(), e))
+        })?;
+        manifests.push((path.to_path_buf(), package));
+    }
+    Ok(manifests)
+}
+
+/// Group manifests by package name, newest version first
+pub fn group_packages(packages: Vec<PackageInfo>) -> BTreeMap<String, Vec<PackageInfo>> {
+    let mut grouped: BTreeMap<String, Vec<PackageInfo>> = BTreeMap::new();
+    for package in packages {
+        grouped.entry(package.name.to_lowercase()).or_default().push(package);
+    }
+    for versions in grouped.values_mut() {
+        versions.sort_by(|a, b| Version::parse(&b.version).cmp(&Version::parse(&a.version)));
+    }
+    grouped
+}
+
+/// Build the repository index for grouped packages
+pub fn build_index(grouped: &BTreeMap<String, Vec<PackageInfo>>) -> RepositoryIndex {
+    RepositoryIndex {
+        packages: grouped.values().filter_map(|v| IndexEntry::from_versions(v)).collect(),
+    }
+}
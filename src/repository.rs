@@ -5,8 +5,25 @@ use crate::error::GetError;
 use crate::logging::Logger;
 use indicatif::{MultiProgress, ProgressBar};
 
-pub const WINGET_PKG_REPO_URL: &str = "https://github.com/microsoft/winget-pkgs";
 pub const SCOOP_MAIN_REPO_URL: &str = "https://github.com/ScoopInstaller/Main";
+pub const WINGET_SOURCE_URL: &str = "https://cdn.winget.microsoft.com/cache";
+
+/// Scoop buckets that can be added by name alone
+pub const SCOOP_KNOWN_BUCKETS: &[(&str, &str)] = &[
+    ("main", SCOOP_MAIN_REPO_URL),
+    ("extras", "https://github.com/ScoopInstaller/Extras"),
+    ("versions", "https://github.com/ScoopInstaller/Versions"),
+    ("java", "https://github.com/ScoopInstaller/Java"),
+    ("nerd-fonts", "https://github.com/matthewjberger/scoop-nerd-fonts"),
+];
+
+/// Look up the repository URL of a known Scoop bucket
+pub fn known_bucket_url(name: &str) -> Option<&'static str> {
+    SCOOP_KNOWN_BUCKETS
+        .iter()
+        .find(|(bucket, _)| bucket.eq_ignore_ascii_case(name))
+        .map(|(_, url)| *url)
+}
 
 /// Ensures a repository is cloned and up-to-date
 pub fn ensure_repo(
@@ -22,7 +39,7 @@ pub fn ensure_repo(
         pb.enable_steady_tick(Duration::from_millis(100));
         
         let status = Command::new("git")
-            .args(&["clone", repo_url, local_path.to_str().unwrap()])
+            .args(["clone", repo_url, local_path.to_str().unwrap()])
             .status()?;
             
         if status.success() {
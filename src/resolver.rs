@@ -0,0 +1,598 @@
+//! Dependency resolution
+//!
+//! Builds the dependency graph of a set of requested packages across every
+//! source, then orders it so dependencies are installed before the packages
+//! that need them. Looking packages up is left to a [`PackageLookup`], which
+//! knows how to ask repositories, Winget, Scoop and Chocolatey for a
+//! package's metadata.
+//!
+//! Requests and dependencies carry version requirements (`tool@^1.2`,
+//! `lib>=3,<4`). Every package is resolved to the newest version meeting all
+//! the requirements placed on it. When a later requirement rules out a
+//! version picked earlier, the resolver remembers the combined requirement
+//! and starts over; when no version meets them all, it reports who asked for
+//! what ("a needs b>=2, c needs b<2").
+//!
+//! Packages whose installed version already meets their requirements are kept
+//! in the graph (so the tree view shows them) but are not expanded further and
+//! are left out of the install order, unless they were requested explicitly.
+
+use std::collections::HashMap;
+
+use crate::error::GetError;
+use crate::version::{split_package_spec, Version, VersionReq};
+
+/// A package resolved to one version in one source
+#[derive(Debug, Clone)]
+pub struct ResolvedPackage {
+    pub name: String,
+    pub version: String,
+    /// Human-readable source, e.g. `winget` or a repository name
+    pub source: String,
+    /// Request that installs exactly this package, e.g. `scoop:main/git@2.45.1`
+    pub install_spec: String,
+    /// Dependency requests, in the same form `get install` accepts
+    pub dependencies: Vec<String>,
+}
+
+/// Looks up package metadata for the resolver
+pub trait PackageLookup {
+    /// Resolve `package` (a name, optionally `source:` qualified) to its
+    /// newest version meeting `requirement`
+    fn resolve(&mut self, package: &str, requirement: &VersionReq) -> Result<ResolvedPackage, GetError>;
+
+    /// The installed version of `package`, if it is installed
+    fn installed_version(&mut self, package: &ResolvedPackage) -> Option<String>;
+}
+
+/// A version requirement and who placed it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Requirement {
+    /// The dependent package, or `None` for a requested package
+    pub from: Option<String>,
+    pub requirement: VersionReq,
+}
+
+impl Requirement {
+    fn describe(&self, package: &str) -> String {
+        let requirement = if self.requirement.is_any() { String::new() } else { self.requirement.to_string() };
+        match &self.from {
+            Some(from) => format!("{} needs {}{}", from, package, requirement),
+            None => format!("{}{} was requested", package, requirement),
+        }
+    }
+}
+
+/// A node of the dependency graph
+#[derive(Debug, Clone)]
+pub struct GraphNode {
+    /// The request the package was first resolved from, without its version
+    pub spec: String,
+    pub package: ResolvedPackage,
+    pub installed_version: Option<String>,
+    pub requested: bool,
+    pub requirements: Vec<Requirement>,
+    /// Indices of the nodes this one depends on
+    pub dependencies: Vec<usize>,
+    expanded: bool,
+}
+
+impl GraphNode {
+    /// The combined requirement on this package
+    pub fn requirement(&self) -> VersionReq {
+        combine(&self.requirements)
+    }
+
+    /// Whether the installed version already meets every requirement
+    pub fn is_satisfied(&self) -> bool {
+        let requirement = self.requirement();
+        self.installed_version
+            .as_deref()
+            .is_some_and(|installed| requirement.matches(&Version::parse(installed)))
+    }
+}
+
+/// The dependency graph of an install
+#[derive(Debug, Default)]
+pub struct DependencyGraph {
+    pub nodes: Vec<GraphNode>,
+    pub roots: Vec<usize>,
+}
+
+fn combine(requirements: &[Requirement]) -> VersionReq {
+    requirements.iter().fold(VersionReq::any(), |all, r| all.and(&r.requirement))
+}
+
+/// Packages are identified by source and name, so the same name in two
+/// sources are two different packages
+fn node_key(package: &ResolvedPackage) -> String {
+    format!("{}:{}", package.source.to_lowercase(), package.name.to_lowercase())
+}
+
+/// Why building the graph stopped early
+enum Interrupt {
+    Failed(GetError),
+    /// A version picked earlier was ruled out; start over knowing this
+    Retry(String, Vec<Requirement>),
+}
+
+impl From<GetError> for Interrupt {
+    fn from(e: GetError) -> Self {
+        Interrupt::Failed(e)
+    }
+}
+
+/// Resolve `specs` and, transitively, everything they depend on
+pub fn build_graph(specs: &[String], lookup: &mut dyn PackageLookup) -> Result<DependencyGraph, GetError> {
+    // Learned requirements only accumulate and there are finitely many of
+    // them, so this terminates
+    let mut learned: HashMap<String, Vec<Requirement>> = HashMap::new();
+    loop {
+        let result = {
+            let mut builder = GraphBuilder {
+                lookup: &mut *lookup,
+                learned: &learned,
+                graph: DependencyGraph::default(),
+                by_key: HashMap::new(),
+                by_spec: HashMap::new(),
+                pending: Vec::new(),
+            };
+            match builder.build(specs) {
+                Ok(()) => Ok(builder.graph),
+                Err(interrupt) => Err(interrupt),
+            }
+        };
+        match result {
+            Ok(graph) => return Ok(graph),
+            Err(Interrupt::Failed(e)) => return Err(e),
+            Err(Interrupt::Retry(spec, requirements)) => {
+                if learned.get(&spec) == Some(&requirements) {
+                    return Err(GetError::ValidationError(format!(
+                        "Could not settle on a version of '{}' meeting {}",
+                        spec,
+                        combine(&requirements)
+                    )));
+                }
+                learned.insert(spec, requirements);
+            }
+        }
+    }
+}
+
+struct GraphBuilder<'a> {
+    lookup: &'a mut dyn PackageLookup,
+    learned: &'a HashMap<String, Vec<Requirement>>,
+    graph: DependencyGraph,
+    by_key: HashMap<String, usize>,
+    by_spec: HashMap<String, usize>,
+    pending: Vec<usize>,
+}
+
+impl GraphBuilder<'_> {
+    fn build(&mut self, specs: &[String]) -> Result<(), Interrupt> {
+        for spec in specs {
+            let root = self.add(spec, None)?;
+            self.graph.nodes[root].requested = true;
+            if !self.graph.roots.contains(&root) {
+                self.graph.roots.push(root);
+            }
+            self.pending.push(root);
+
+            while let Some(index) = self.pending.pop() {
+                let node = &self.graph.nodes[index];
+                if node.expanded || (node.is_satisfied() && !node.requested) {
+                    continue;
+                }
+                self.graph.nodes[index].expanded = true;
+
+                let from = Some(self.graph.nodes[index].package.name.clone());
+                for dependency in self.graph.nodes[index].package.dependencies.clone() {
+                    let child = self.add(&dependency, from.clone())?;
+                    if !self.graph.nodes[index].dependencies.contains(&child) {
+                        self.graph.nodes[index].dependencies.push(child);
+                    }
+                    self.pending.push(child);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn add(&mut self, spec: &str, from: Option<String>) -> Result<usize, Interrupt> {
+        let (package, version) = split_package_spec(spec);
+        let requirement = Requirement {
+            from,
+            requirement: VersionReq::parse(version.unwrap_or("*"))?,
+        };
+        let spec_key = package.to_lowercase();
+
+        if let Some(&index) = self.by_spec.get(&spec_key) {
+            return self.constrain(index, requirement);
+        }
+
+        let mut requirements = self.learned.get(&spec_key).cloned().unwrap_or_default();
+        if !requirements.contains(&requirement) {
+            requirements.push(requirement.clone());
+        }
+        let resolved = self.resolve(package, &requirements)?;
+
+        // Two spellings of the same package (`git`, `scoop:git`) share a node
+        let key = node_key(&resolved);
+        if let Some(&index) = self.by_key.get(&key) {
+            self.by_spec.insert(spec_key, index);
+            return self.constrain(index, requirement);
+        }
+
+        let installed_version = self.lookup.installed_version(&resolved);
+        self.graph.nodes.push(GraphNode {
+            spec: package.to_string(),
+            package: resolved,
+            installed_version,
+            requested: false,
+            requirements,
+            dependencies: Vec::new(),
+            expanded: false,
+        });
+        let index = self.graph.nodes.len() - 1;
+        self.by_key.insert(key, index);
+        self.by_spec.insert(spec_key, index);
+        Ok(index)
+    }
+
+    /// Add a requirement to a package that is already in the graph
+    fn constrain(&mut self, index: usize, requirement: Requirement) -> Result<usize, Interrupt> {
+        let node = &mut self.graph.nodes[index];
+        if node.requirements.contains(&requirement) {
+            return Ok(index);
+        }
+        node.requirements.push(requirement);
+        let fits = node.requirement().matches(&Version::parse(&node.package.version));
+        let (spec, requirements) = (node.spec.clone(), node.requirements.clone());
+
+        // An installed version that no longer fits has to be replaced, so
+        // the package may need expanding after all
+        self.pending.push(index);
+        if fits {
+            return Ok(index);
+        }
+
+        self.resolve(&spec, &requirements)?;
+        Err(Interrupt::Retry(spec.to_lowercase(), requirements))
+    }
+
+    /// The newest version meeting every requirement, or a readable conflict
+    fn resolve(&mut self, package: &str, requirements: &[Requirement]) -> Result<ResolvedPackage, GetError> {
+        let requirement = combine(requirements);
+        let conflict = || {
+            let parts: Vec<String> = requirements.iter().map(|r| r.describe(package)).collect();
+            GetError::ValidationError(format!("Conflicting requirements for '{}': {}", package, parts.join(", ")))
+        };
+
+        match self.lookup.resolve(package, &requirement) {
+            Ok(resolved) if requirement.matches(&Version::parse(&resolved.version)) => Ok(resolved),
+            Ok(_) | Err(GetError::PackageNotFound(_)) if requirements.len() > 1 => Err(conflict()),
+            Ok(resolved) => Err(GetError::PackageNotFound(format!(
+                "no version of '{}' matches {} (found {})",
+                package, requirement, resolved.version
+            ))),
+            Err(GetError::PackageNotFound(msg)) => match &requirements[0].from {
+                Some(from) => Err(GetError::PackageNotFound(format!("dependency '{}' of '{}': {}", package, from, msg))),
+                None => Err(GetError::PackageNotFound(msg)),
+            },
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl DependencyGraph {
+    /// Packages to install, dependencies first. Fails on a dependency cycle.
+    pub fn install_order(&self) -> Result<Vec<&ResolvedPackage>, GetError> {
+        #[derive(Clone, Copy, PartialEq)]
+        enum Mark {
+            New,
+            Visiting,
+            Done,
+        }
+
+        fn visit<'a>(
+            graph: &'a DependencyGraph,
+            index: usize,
+            marks: &mut [Mark],
+            path: &mut Vec<usize>,
+            order: &mut Vec<&'a ResolvedPackage>,
+        ) -> Result<(), GetError> {
+            match marks[index] {
+                Mark::Done => return Ok(()),
+                Mark::Visiting => {
+                    let start = path.iter().position(|&i| i == index).unwrap_or(0);
+                    let cycle: Vec<&str> = path[start..]
+                        .iter()
+                        .chain(std::iter::once(&index))
+                        .map(|&i| graph.nodes[i].package.name.as_str())
+                        .collect();
+                    return Err(GetError::ValidationError(format!(
+                        "Dependency cycle: {}",
+                        cycle.join(" -> ")
+                    )));
+                }
+                Mark::New => {}
+            }
+
+            marks[index] = Mark::Visiting;
+            path.push(index);
+            for &dependency in &graph.nodes[index].dependencies {
+                visit(graph, dependency, marks, path, order)?;
+            }
+            path.pop();
+            marks[index] = Mark::Done;
+
+            let node = &graph.nodes[index];
+            if node.requested || !node.is_satisfied() {
+                order.push(&node.package);
+            }
+            Ok(())
+        }
+
+        let mut marks = vec![Mark::New; self.nodes.len()];
+        let mut order = Vec::new();
+        for &root in &self.roots {
+            visit(self, root, &mut marks, &mut Vec::new(), &mut order)?;
+        }
+        Ok(order)
+    }
+
+    /// Render the graph as an indented tree, one root after another.
+    /// Repeated subtrees are printed once and marked `(*)`.
+    pub fn render_tree(&self) -> String {
+        #[allow(clippy::too_many_arguments)]
+        fn render(
+            graph: &DependencyGraph,
+            index: usize,
+            prefix: &str,
+            last: bool,
+            is_root: bool,
+            path: &mut Vec<usize>,
+            shown: &mut [bool],
+            out: &mut String,
+        ) {
+            let node = &graph.nodes[index];
+            let connector = if is_root { "" } else if last { "└── " } else { "├── " };
+            let mut notes = Vec::new();
+            let requirement = node.requirement();
+            if !requirement.is_any() {
+                notes.push(requirement.to_string());
+            }
+            match &node.installed_version {
+                Some(installed) if node.is_satisfied() => notes.push(format!("installed {}", installed)),
+                Some(installed) => notes.push(format!("upgrades {}", installed)),
+                None => {}
+            }
+            let cycle = path.contains(&index);
+            if cycle {
+                notes.push("cycle".to_string());
+            } else if shown[index] && !node.dependencies.is_empty() {
+                notes.push("*".to_string());
+            }
+            let notes = if notes.is_empty() { String::new() } else { format!(" ({})", notes.join(", ")) };
+            out.push_str(&format!(
+                "{}{}{} {} [{}]{}\n",
+                prefix, connector, node.package.name, node.package.version, node.package.source, notes
+            ));
+
+            if cycle || shown[index] {
+                return;
+            }
+            shown[index] = true;
+
+            let child_prefix = if is_root {
+                String::new()
+            } else {
+                format!("{}{}", prefix, if last { "    " } else { "│   " })
+            };
+            path.push(index);
+            let count = node.dependencies.len();
+            for (i, &dependency) in node.dependencies.iter().enumerate() {
+                render(graph, dependency, &child_prefix, i + 1 == count, false, path, shown, out);
+            }
+            path.pop();
+        }
+
+        let mut out = String::new();
+        let mut shown = vec![false; self.nodes.len()];
+        for &root in &self.roots {
+            render(self, root, "", true, true, &mut Vec::new(), &mut shown, &mut out);
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Packages by name: every version with its dependencies
+    #[derive(Default)]
+    struct MemoryLookup {
+        packages: HashMap<String, Vec<(String, Vec<String>)>>,
+        installed: HashMap<String, String>,
+    }
+
+    impl MemoryLookup {
+        fn with(mut self, name: &str, version: &str, dependencies: &[&str]) -> Self {
+            self.packages
+                .entry(name.to_string())
+                .or_default()
+                .push((version.to_string(), dependencies.iter().map(|d| d.to_string()).collect()));
+            self
+        }
+
+        fn installed(mut self, name: &str, version: &str) -> Self {
+            self.installed.insert(name.to_string(), version.to_string());
+            self
+        }
+    }
+
+    impl PackageLookup for MemoryLookup {
+        fn resolve(&mut self, package: &str, requirement: &VersionReq) -> Result<ResolvedPackage, GetError> {
+            let versions = self
+                .packages
+                .get(package)
+                .ok_or_else(|| GetError::PackageNotFound(format!("'{}' not found", package)))?;
+            let (version, dependencies) = versions
+                .iter()
+                .filter(|(version, _)| requirement.matches(&Version::parse(version)))
+                .max_by_key(|(version, _)| Version::parse(version))
+                .ok_or_else(|| GetError::PackageNotFound(format!("no '{}' matching {}", package, requirement)))?;
+            Ok(ResolvedPackage {
+                name: package.to_string(),
+                version: version.clone(),
+                source: "memory".to_string(),
+                install_spec: format!("{}@{}", package, version),
+                dependencies: dependencies.clone(),
+            })
+        }
+
+        fn installed_version(&mut self, package: &ResolvedPackage) -> Option<String> {
+            self.installed.get(&package.name).cloned()
+        }
+    }
+
+    fn order(graph: &DependencyGraph) -> Vec<String> {
+        graph
+            .install_order()
+            .unwrap()
+            .iter()
+            .map(|p| format!("{}@{}", p.name, p.version))
+            .collect()
+    }
+
+    fn build(specs: &[&str], lookup: &mut MemoryLookup) -> Result<DependencyGraph, GetError> {
+        let specs: Vec<String> = specs.iter().map(|s| s.to_string()).collect();
+        build_graph(&specs, lookup)
+    }
+
+    #[test]
+    fn orders_dependencies_first() {
+        let mut lookup = MemoryLookup::default()
+            .with("app", "1.0", &["b", "c"])
+            .with("b", "1.0", &["d"])
+            .with("c", "1.0", &["d"])
+            .with("d", "1.0", &[]);
+        let graph = build(&["app"], &mut lookup).unwrap();
+        assert_eq!(order(&graph), ["d@1.0", "b@1.0", "c@1.0", "app@1.0"]);
+        // The shared dependency is one node
+        assert_eq!(graph.nodes.len(), 4);
+    }
+
+    #[test]
+    fn picks_the_newest_version_meeting_every_requirement() {
+        let mut lookup = MemoryLookup::default()
+            .with("app", "1.0", &["lib@^1.2", "other"])
+            .with("other", "1.0", &["lib>=1.4"])
+            .with("lib", "1.2.0", &[])
+            .with("lib", "1.5.0", &[])
+            .with("lib", "2.0.0", &[]);
+        let graph = build(&["app"], &mut lookup).unwrap();
+        assert_eq!(order(&graph), ["lib@1.5.0", "other@1.0", "app@1.0"]);
+    }
+
+    #[test]
+    fn backtracks_when_a_later_requirement_rules_out_a_pick() {
+        // `lib` resolves to 2.0 and is expanded before `old` rules that out
+        let mut lookup = MemoryLookup::default()
+            .with("app", "1.0", &["old", "lib"])
+            .with("old", "1.0", &["lib<2"])
+            .with("lib", "1.0", &["base@1"])
+            .with("lib", "2.0", &["base@2"])
+            .with("base", "1", &[])
+            .with("base", "2", &[]);
+        let graph = build(&["app"], &mut lookup).unwrap();
+        assert_eq!(order(&graph), ["base@1", "lib@1.0", "old@1.0", "app@1.0"]);
+        // Nothing from the abandoned pick is left behind
+        assert!(!graph.nodes.iter().any(|n| n.package.name == "base" && n.package.version == "2"));
+    }
+
+    #[test]
+    fn reports_who_asked_for_conflicting_versions() {
+        let mut lookup = MemoryLookup::default()
+            .with("app", "1.0", &["lib>=2", "old"])
+            .with("old", "1.0", &["lib<2"])
+            .with("lib", "1.0", &[])
+            .with("lib", "2.0", &[]);
+        let message = build(&["app"], &mut lookup).unwrap_err().to_string();
+        assert!(message.contains("Conflicting requirements for 'lib'"), "{}", message);
+        assert!(message.contains("app needs lib>=2"), "{}", message);
+        assert!(message.contains("old needs lib<2"), "{}", message);
+    }
+
+    #[test]
+    fn conflicts_between_requests_are_reported() {
+        let mut lookup = MemoryLookup::default().with("lib", "1.0", &[]).with("lib", "2.0", &[]);
+        let message = build(&["lib@^1", "lib@^2"], &mut lookup).unwrap_err().to_string();
+        assert!(message.contains("lib^1 was requested"), "{}", message);
+        assert!(message.contains("lib^2 was requested"), "{}", message);
+    }
+
+    #[test]
+    fn names_the_dependent_of_a_missing_package() {
+        let mut lookup = MemoryLookup::default().with("app", "1.0", &["ghost"]);
+        let message = build(&["app"], &mut lookup).unwrap_err().to_string();
+        assert!(message.contains("dependency 'ghost' of 'app'"), "{}", message);
+    }
+
+    #[test]
+    fn detects_cycles() {
+        let mut lookup = MemoryLookup::default()
+            .with("a", "1.0", &["b"])
+            .with("b", "1.0", &["c"])
+            .with("c", "1.0", &["a"]);
+        let graph = build(&["a"], &mut lookup).unwrap();
+        let message = graph.install_order().unwrap_err().to_string();
+        assert!(message.contains("Dependency cycle: a -> b -> c -> a"), "{}", message);
+        assert!(graph.render_tree().contains("(cycle)"));
+    }
+
+    #[test]
+    fn skips_satisfied_dependencies_but_not_requested_packages() {
+        let mut lookup = MemoryLookup::default()
+            .with("app", "1.0", &["lib@^1"])
+            .with("lib", "1.5", &["base"])
+            .with("base", "1.0", &[])
+            .installed("lib", "1.2")
+            .installed("app", "1.0");
+        let graph = build(&["app"], &mut lookup).unwrap();
+        assert_eq!(order(&graph), ["app@1.0"]);
+        // A satisfied package isn't expanded
+        assert!(!graph.nodes.iter().any(|n| n.package.name == "base"));
+
+        // An installed version outside the requirement is upgraded
+        let mut lookup = MemoryLookup::default()
+            .with("app", "1.0", &["lib@^1.4"])
+            .with("lib", "1.5", &[])
+            .installed("lib", "1.2");
+        let graph = build(&["app"], &mut lookup).unwrap();
+        assert_eq!(order(&graph), ["lib@1.5", "app@1.0"]);
+        assert!(graph.render_tree().contains("upgrades 1.2"));
+    }
+
+    #[test]
+    fn renders_shared_dependencies_once() {
+        let mut lookup = MemoryLookup::default()
+            .with("app", "1.0", &["cli@^2", "gui"])
+            .with("cli", "2.1", &["core"])
+            .with("gui", "0.3", &["core"])
+            .with("core", "1.0", &["libc"])
+            .with("libc", "2.0", &[])
+            .installed("libc", "2.0");
+        let graph = build(&["app"], &mut lookup).unwrap();
+        let expected = [
+            "app 1.0 [memory]",
+            "├── cli 2.1 [memory] (^2)",
+            "│   └── core 1.0 [memory]",
+            "│       └── libc 2.0 [memory] (installed 2.0)",
+            "└── gui 0.3 [memory]",
+            "    └── core 1.0 [memory] (*)",
+        ];
+        assert_eq!(graph.render_tree().lines().collect::<Vec<_>>(), expected);
+    }
+}
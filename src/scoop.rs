@@ -1,14 +1,23 @@
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use indicatif::{ProgressBar, MultiProgress};
+use regex::Regex;
+use reqwest::blocking::Client;
+use reqwest::header::USER_AGENT;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 use crate::{Config, GetError, Logger};
-
-const SCOOP_MAIN_REPO_URL: &str = "https://github.com/ScoopInstaller/Main.git";
+use get::apps::BinSpec;
+use get::config::ScoopBucket;
+use get::package_db::{package_db_path, PackageDb};
+use get::repository::known_bucket_url;
+use get::utils::sha256_file;
+use get::version::Version;
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct ScoopManifest {
@@ -18,7 +27,7 @@ pub struct ScoopManifest {
     license: Option<String>,
     url: String,
     hash: String,
-    bin: Option<String>,
+    bin: Option<BinSpec>,
     installer: Option<ScoopInstaller>,
     checkver: Option<ScoopCheckVer>,
     autoupdate: Option<ScoopAutoupdate>,
@@ -29,28 +38,444 @@ pub struct ScoopInstaller {
     script: Vec<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+/// Where a manifest's latest version is published, and how to read it
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct ScoopCheckVer {
-    url: String,
-    regex: String,
+    /// The page to check; Scoop defaults it to the manifest's homepage
+    #[serde(default)]
+    pub url: String,
+    #[serde(default, alias = "re")]
+    pub regex: String,
+    /// A GitHub repository whose latest release is the version
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub github: Option<String>,
+    /// Builds the version from the regex groups, as in `${1}.${2}`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replace: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+/// How to build a manifest's download for a new version
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct ScoopAutoupdate {
-    url: String,
+    pub url: String,
+}
+
+/// A version found by a checkver, with the regex groups that found it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckedVersion {
+    pub version: String,
+    /// `$match1`, `$matchName`, ... by variable name
+    pub matches: BTreeMap<String, String>,
+}
+
+impl ScoopCheckVer {
+    /// Fetch the checkver page of a manifest currently at `current` and read
+    /// the version off it. The URL may use the current version's `$version`
+    /// variables.
+    pub fn check(&self, current: &str) -> Result<CheckedVersion, GetError> {
+        let url = substitute(&self.url, &version_variables(current));
+        let response = Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()?
+            .get(&url)
+            .header(USER_AGENT, "get-package-manager/1.0")
+            .send()?;
+        if !response.status().is_success() {
+            return Err(GetError::NetworkError(format!(
+                "checkver page '{}' returned HTTP {}",
+                url,
+                response.status()
+            )));
+        }
+        let page = response.text()?;
+        self.read_version(&page).map_err(|e| match e {
+            GetError::PackageNotFound(message) => GetError::PackageNotFound(format!("{} at '{}'", message, url)),
+            e => e,
+        })
+    }
+
+    /// Read the version off a checkver page: `replace` if given, else the
+    /// regex's `version` group, its first group, or the whole match
+    pub fn read_version(&self, page: &str) -> Result<CheckedVersion, GetError> {
+        let regex = Regex::new(&self.regex)
+            .map_err(|e| GetError::ValidationError(format!("Invalid checkver regex '{}': {}", self.regex, e)))?;
+        let captures = regex
+            .captures(page)
+            .ok_or_else(|| GetError::PackageNotFound(format!("checkver regex '{}' matched nothing", self.regex)))?;
+        let version = match &self.replace {
+            Some(replace) => {
+                let mut version = String::new();
+                captures.expand(replace, &mut version);
+                version
+            }
+            None => captures
+                .name("version")
+                .or_else(|| captures.get(1))
+                .or_else(|| captures.get(0))
+                .map(|m| m.as_str().to_string())
+                .unwrap_or_default(),
+        };
+
+        // Scoop names groups `$match1` and `$matchName`
+        let mut matches = BTreeMap::new();
+        for (i, name) in regex.capture_names().enumerate().skip(1) {
+            let Some(value) = captures.get(i) else {
+                continue;
+            };
+            matches.insert(format!("match{}", i), value.as_str().to_string());
+            if let Some(name) = name {
+                let mut chars = name.chars();
+                let title: String = chars.next().map(|c| c.to_uppercase().chain(chars).collect()).unwrap_or_default();
+                matches.insert(format!("match{}", title), value.as_str().to_string());
+            }
+        }
+        Ok(CheckedVersion { version: version.trim().to_string(), matches })
+    }
+}
+
+impl CheckedVersion {
+    /// Every `$` variable autoupdate templates can use for this version
+    pub fn variables(&self) -> Vec<(String, String)> {
+        let mut variables = version_variables(&self.version);
+        variables.extend(self.matches.iter().map(|(name, value)| (name.clone(), value.clone())));
+        variables
+    }
+}
+
+impl ScoopAutoupdate {
+    /// The download URL for a version
+    pub fn url_for(&self, checked: &CheckedVersion) -> String {
+        substitute(&self.url, &checked.variables())
+    }
+}
+
+/// Scoop's version variables: `$version`, `$majorVersion`, `$cleanVersion`, ...
+fn version_variables(version: &str) -> Vec<(String, String)> {
+    let parts: Vec<&str> = version.split(['.', '-', '+']).collect();
+    let part = |i: usize| parts.get(i).copied().unwrap_or_default().to_string();
+    let (head, tail) = match version.match_indices('.').nth(2) {
+        Some((i, _)) => (&version[..i], &version[i..]),
+        None => (version, ""),
+    };
+    vec![
+        ("version".to_string(), version.to_string()),
+        ("majorVersion".to_string(), part(0)),
+        ("minorVersion".to_string(), part(1)),
+        ("patchVersion".to_string(), part(2)),
+        ("buildVersion".to_string(), part(3)),
+        ("preReleaseVersion".to_string(), version.split_once('-').map(|(_, pre)| pre).unwrap_or_default().to_string()),
+        ("cleanVersion".to_string(), version.replace(['.', '-'], "")),
+        ("underscoreVersion".to_string(), version.replace('.', "_")),
+        ("dashVersion".to_string(), version.replace('.', "-")),
+        ("matchHead".to_string(), head.to_string()),
+        ("matchTail".to_string(), tail.to_string()),
+    ]
+}
+
+/// Replace `$name` variables in `template`, longest names first so `$match10`
+/// is not read as `$match1`
+fn substitute(template: &str, variables: &[(String, String)]) -> String {
+    let mut variables: Vec<&(String, String)> = variables.iter().collect();
+    variables.sort_by_key(|(name, _)| std::cmp::Reverse(name.len()));
+    variables
+        .into_iter()
+        .fold(template.to_string(), |text, (name, value)| text.replace(&format!("${}", name), value))
+}
+
+/// The checkver of a manifest: a `{ url, regex }` or `{ github }` object, a
+/// bare regex for the homepage, or `"github"` for the homepage's releases
+pub fn manifest_checkver(manifest: &Value) -> Result<ScoopCheckVer, GetError> {
+    let homepage = manifest.get("homepage").and_then(Value::as_str).unwrap_or_default();
+    let mut checkver = match manifest.get("checkver") {
+        None => return Err(GetError::ValidationError("Manifest has no checkver".to_string())),
+        Some(Value::String(regex)) if regex == "github" => ScoopCheckVer {
+            url: String::new(),
+            regex: String::new(),
+            github: Some(homepage.to_string()),
+            replace: None,
+        },
+        Some(Value::String(regex)) => ScoopCheckVer { url: String::new(), regex: regex.clone(), github: None, replace: None },
+        Some(checkver) => serde_json::from_value(checkver.clone())?,
+    };
+    // Scoop reads the tag the latest release redirects to
+    if let Some(repository) = &checkver.github {
+        if checkver.url.is_empty() {
+            checkver.url = format!("{}/releases/latest", repository.trim_end_matches('/'));
+        }
+        if checkver.regex.is_empty() {
+            checkver.regex = r"/releases/tag/(?:v|V)?([\d.]+)".to_string();
+        }
+    }
+    if checkver.url.is_empty() {
+        checkver.url = homepage.to_string();
+    }
+    if checkver.regex.is_empty() {
+        return Err(GetError::ValidationError("checkver has no regex".to_string()));
+    }
+    Ok(checkver)
+}
+
+/// A manifest file, read as JSON so rewriting it keeps everything else as is
+pub fn read_manifest(path: &Path) -> Result<Value, GetError> {
+    serde_json::from_str(&std::fs::read_to_string(path)?)
+        .map_err(|e| GetError::ParseError(format!("Invalid manifest '{}': {}", path.display(), e)))
+}
+
+fn manifest_name(path: &Path) -> String {
+    path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default()
+}
+
+/// Check each manifest's checkver and report which are behind
+pub fn checkver_manifests(files: &[PathBuf], logger: &Logger) -> Result<(), GetError> {
+    let mut failed = Vec::new();
+    for path in files {
+        let name = manifest_name(path);
+        let result = read_manifest(path).and_then(|manifest| {
+            let current = manifest.get("version").and_then(Value::as_str).unwrap_or_default().to_string();
+            let checked = manifest_checkver(&manifest)?.check(&current)?;
+            Ok((current, checked))
+        });
+        match result {
+            Ok((current, checked)) if Version::parse(&checked.version) > Version::parse(&current) => {
+                println!("{}: {} (latest version: {})", name, current, checked.version);
+            }
+            Ok((current, _)) => println!("{}: {} (up to date)", name, current),
+            Err(e) => {
+                logger.error(&format!("{}: {}", name, e));
+                failed.push(name);
+            }
+        }
+    }
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(GetError::CommandError(format!("checkver failed for {}.", failed.join(", "))))
+    }
 }
 
-pub fn ensure_scoop_repo(config: &Config, logger: &Logger, m: &MultiProgress) -> Result<PathBuf, GetError> {
+/// Move each manifest behind its checkver to the latest version: rewrite
+/// `version`, build `url` from `autoupdate` and hash the new download
+pub fn autoupdate_manifests(files: &[PathBuf], logger: &Logger, m: &MultiProgress) -> Result<(), GetError> {
+    let mut failed = Vec::new();
+    for path in files {
+        let name = manifest_name(path);
+        match autoupdate_manifest(path, logger, m) {
+            Ok(Some((from, to))) => logger.info(&format!("{}: updated {} -> {}.", name, from, to)),
+            Ok(None) => logger.info(&format!("{}: up to date.", name)),
+            Err(e) => {
+                logger.error(&format!("{}: {}", name, e));
+                failed.push(name);
+            }
+        }
+    }
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(GetError::CommandError(format!("autoupdate failed for {}.", failed.join(", "))))
+    }
+}
+
+/// Update one manifest, returning the old and new version if it changed
+fn autoupdate_manifest(path: &Path, logger: &Logger, m: &MultiProgress) -> Result<Option<(String, String)>, GetError> {
+    let mut manifest = read_manifest(path)?;
+    let current = manifest.get("version").and_then(Value::as_str).unwrap_or_default().to_string();
+    let checked = manifest_checkver(&manifest)?.check(&current)?;
+    if Version::parse(&checked.version) <= Version::parse(&current) {
+        return Ok(None);
+    }
+
+    let work_dir = tempfile::tempdir()?;
+    update_manifest(&mut manifest, &checked, |url| {
+        let file = crate::download_installer(url, work_dir.path(), logger, m)?;
+        sha256_file(&file)
+    })
+    .map_err(|e| match e {
+        GetError::ParseError(message) => GetError::ParseError(format!("{}: {}", path.display(), message)),
+        e => e,
+    })?;
+
+    // Scoop manifests are indented with four spaces
+    let mut out = Vec::new();
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(b"    ");
+    manifest.serialize(&mut serde_json::Serializer::with_formatter(&mut out, formatter))?;
+    out.push(b'\n');
+    std::fs::write(path, out)?;
+    Ok(Some((current, checked.version)))
+}
+
+/// Move a manifest to a checked version: set `version`, and build `url` and
+/// `hash` from `autoupdate`, for the manifest and for each architecture it
+/// covers. Lists stay lists; `hash` hashes a download.
+fn update_manifest(
+    manifest: &mut Value,
+    checked: &CheckedVersion,
+    mut hash: impl FnMut(&str) -> Result<String, GetError>,
+) -> Result<(), GetError> {
+    let autoupdate = manifest
+        .get("autoupdate")
+        .cloned()
+        .ok_or_else(|| GetError::ValidationError("Manifest has no autoupdate".to_string()))?;
+    let mut templates = Vec::new();
+    if let Some(url) = autoupdate.get("url") {
+        templates.push((None, url));
+    }
+    if let Some(Value::Object(architectures)) = autoupdate.get("architecture") {
+        for (architecture, details) in architectures {
+            if let Some(url) = details.get("url") {
+                templates.push((Some(architecture.as_str()), url));
+            }
+        }
+    }
+    if templates.is_empty() {
+        return Err(GetError::ValidationError("autoupdate has no url".to_string()));
+    }
+
+    let object = manifest
+        .as_object_mut()
+        .ok_or_else(|| GetError::ParseError("Manifest is not an object".to_string()))?;
+    object.insert("version".to_string(), Value::String(checked.version.clone()));
+    let variables = checked.variables();
+    for (architecture, template) in templates {
+        let urls = match template {
+            Value::String(url) => vec![substitute(url, &variables)],
+            Value::Array(urls) => urls
+                .iter()
+                .map(|url| url.as_str().map(|url| substitute(url, &variables)))
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(|| GetError::ValidationError("autoupdate urls must be strings".to_string()))?,
+            _ => return Err(GetError::ValidationError("autoupdate url must be a string or a list".to_string())),
+        };
+        // `#/name` only renames the download; it stays in the manifest
+        let hashes = urls
+            .iter()
+            .map(|url| hash(url.split_once("#/").map_or(url.as_str(), |(url, _)| url)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let target = match architecture {
+            None => &mut *object,
+            Some(architecture) => object
+                .entry("architecture")
+                .or_insert_with(|| Value::Object(Default::default()))
+                .as_object_mut()
+                .and_then(|architectures| {
+                    architectures
+                        .entry(architecture)
+                        .or_insert_with(|| Value::Object(Default::default()))
+                        .as_object_mut()
+                })
+                .ok_or_else(|| GetError::ParseError(format!("Manifest architecture '{}' is not an object", architecture)))?,
+        };
+        let as_list = template.is_array() || target.get("url").is_some_and(Value::is_array);
+        let value = |items: Vec<String>| {
+            if as_list {
+                Value::Array(items.into_iter().map(Value::String).collect())
+            } else {
+                Value::String(items.into_iter().next().unwrap_or_default())
+            }
+        };
+        target.insert("url".to_string(), value(urls));
+        target.insert("hash".to_string(), value(hashes));
+    }
+    Ok(())
+}
+
+/// Local clone directory of a bucket
+pub fn bucket_path(config: &Config, bucket: &str) -> PathBuf {
+    config.get_repos_dir().join("buckets").join(bucket)
+}
+
+/// Name of a bucket's source in the package database
+pub fn scoop_source(bucket: &str) -> String {
+    format!("scoop/{}", bucket.to_lowercase())
+}
+
+/// The bucket of a package database source
+pub fn scoop_bucket(source: &str) -> &str {
+    source.strip_prefix("scoop/").unwrap_or(source)
+}
+
+/// Clone or update a bucket, returning its local path
+pub fn ensure_bucket(bucket: &ScoopBucket, config: &Config, logger: &Logger, m: &MultiProgress) -> Result<PathBuf, GetError> {
     if crate::SHOULD_TERMINATE.load(Ordering::SeqCst) {
         return Err(GetError::InvalidInput("Operation terminated by user.".to_string()));
     }
 
-    let repos_dir = config.get_repos_dir();
-    let scoop_local_path = repos_dir.join("scoop");
+    let bucket_local_path = bucket_path(config, &bucket.name);
+    if let Some(parent) = bucket_local_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    ensure_repo(&bucket.url, &bucket_local_path, logger, m)?;
+
+    Ok(bucket_local_path)
+}
+
+/// Add a known bucket by name, or a custom bucket by git URL, to `config`;
+/// the caller saves it
+pub fn add_bucket(name: &str, url: Option<&str>, config: &mut Config, logger: &Logger, m: &MultiProgress) -> Result<(), GetError> {
+    // The name becomes a directory under the repositories dir
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.') || name.starts_with('.') {
+        return Err(GetError::InvalidInput(format!("Invalid bucket name '{}'.", name)));
+    }
+
+    let url = match url {
+        Some(url) => url.to_string(),
+        None => known_bucket_url(name)
+            .ok_or_else(|| GetError::InvalidInput(format!(
+                "Unknown bucket '{}'. Provide a git URL: get bucket add {} <url>",
+                name, name
+            )))?
+            .to_string(),
+    };
+
+    let mut buckets = config.get_scoop_buckets();
+    if buckets.iter().any(|b| b.name.eq_ignore_ascii_case(name)) {
+        return Err(GetError::InvalidInput(format!("Bucket '{}' already exists.", name)));
+    }
+
+    let bucket = ScoopBucket { name: name.to_lowercase(), url };
+    ensure_bucket(&bucket, config, logger, m)?;
 
-    ensure_repo(SCOOP_MAIN_REPO_URL, &scoop_local_path, logger, m)?;
+    buckets.push(bucket);
+    config.scoop_buckets = Some(buckets);
 
-    Ok(scoop_local_path)
+    logger.info(&format!("Bucket '{}' added.", name));
+    Ok(())
+}
+
+/// Remove a bucket along with its clone and indexed packages from `config`;
+/// the caller saves it
+pub fn remove_bucket(name: &str, config: &mut Config, logger: &Logger) -> Result<(), GetError> {
+    let mut buckets = config.get_scoop_buckets();
+    let before = buckets.len();
+    buckets.retain(|b| !b.name.eq_ignore_ascii_case(name));
+    if buckets.len() == before {
+        return Err(GetError::InvalidInput(format!("Bucket '{}' is not configured.", name)));
+    }
+
+    let name = name.to_lowercase();
+    let local_path = bucket_path(config, &name);
+    if local_path.exists() {
+        std::fs::remove_dir_all(&local_path)?;
+    }
+    let db_path = package_db_path(config);
+    if db_path.exists() {
+        PackageDb::open(&db_path)?.remove_source(&scoop_source(&name))?;
+    }
+
+    config.scoop_buckets = Some(buckets);
+
+    logger.info(&format!("Bucket '{}' removed.", name));
+    Ok(())
+}
+
+/// Print the configured buckets
+pub fn list_buckets(config: &Config) {
+    println!("\nScoop Buckets:\n");
+    for bucket in config.get_scoop_buckets() {
+        let status = if bucket_path(config, &bucket.name).exists() { "" } else { " (not cloned yet)" };
+        println!("  - {} {}{}", bucket.name, bucket.url, status);
+    }
 }
 
 fn ensure_repo(repo_url: &str, local_path: &Path, logger: &Logger, m: &MultiProgress) -> Result<(), GetError> {
@@ -86,7 +511,7 @@ fn ensure_repo(repo_url: &str, local_path: &Path, logger: &Logger, m: &MultiProg
             pb.set_message(format!("Pulling latest changes for repository '{}'.", local_path.display()));
             pb.enable_steady_tick(Duration::from_millis(100));
             let status = Command::new("git")
-                .args(&["-C", local_path.to_str().unwrap(), "pull"])
+                .args(["-C", local_path.to_str().unwrap(), "pull"])
                 .status()?;
 
             pb.finish_and_clear();
@@ -121,7 +546,7 @@ fn ensure_repo(repo_url: &str, local_path: &Path, logger: &Logger, m: &MultiProg
         ));
         pb.enable_steady_tick(Duration::from_millis(100));
         let status = Command::new("git")
-            .args(&["clone", repo_url, local_path.to_str().unwrap()])
+            .args(["clone", repo_url, local_path.to_str().unwrap()])
             .status()?;
 
         pb.finish_and_clear();
@@ -152,11 +577,10 @@ pub fn install_scoop_package(package: &str, config: &Config, logger: &Logger, m:
     logger.log(&format!("Installing Scoop package '{}'...", package));
     
     let status = Command::new("scoop")
-        .args(&["install", package])
+        .args(["install", package])
         .status()?;
 
     if status.success() {
-        logger.info(&format!("Package '{}' installed successfully.", package));
         Ok(())
     } else {
         Err(GetError::CommandError(format!(
@@ -165,3 +589,112 @@ pub fn install_scoop_package(package: &str, config: &Config, logger: &Logger, m:
         )))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn reads_every_form_of_checkver() {
+        let checkver = |checkver: Value| {
+            manifest_checkver(&json!({ "homepage": "https://github.com/owner/app", "checkver": checkver })).unwrap()
+        };
+        let latest = "https://github.com/owner/app/releases/latest";
+
+        let regex = checkver(json!("v([\\d.]+)"));
+        assert_eq!((regex.url.as_str(), regex.regex.as_str()), ("https://github.com/owner/app", "v([\\d.]+)"));
+        assert_eq!(checkver(json!("github")).url, latest);
+        assert_eq!(checkver(json!({ "github": "https://github.com/owner/app/" })).url, latest);
+        let custom = checkver(json!({ "github": "https://github.com/owner/app", "re": "app-([\\d.]+)" }));
+        assert_eq!((custom.url.as_str(), custom.regex.as_str()), (latest, "app-([\\d.]+)"));
+        assert!(manifest_checkver(&json!({ "checkver": { "url": "https://example.com" } })).is_err());
+    }
+
+    fn checkver(regex: &str, replace: Option<&str>) -> ScoopCheckVer {
+        ScoopCheckVer { url: String::new(), regex: regex.to_string(), github: None, replace: replace.map(str::to_string) }
+    }
+
+    #[test]
+    fn reads_the_version_off_a_checkver_page() {
+        let page = "<a href=\"/dl/app-2.4.1-x64.zip\">App 2.4.1</a>";
+        assert_eq!(checkver(r"App ([\d.]+)", None).read_version(page).unwrap().version, "2.4.1");
+        assert_eq!(checkver(r"App [\d.]+", None).read_version(page).unwrap().version, "App 2.4.1");
+
+        let named = checkver(r"app-(?<version>[\d.]+)-(?<arch>\w+)\.zip", None).read_version(page).unwrap();
+        assert_eq!(named.version, "2.4.1");
+        assert_eq!(named.matches["match2"], "x64");
+        assert_eq!(named.matches["matchArch"], "x64");
+
+        let replaced = checkver(r"App (\d+)\.(\d+)", Some("${1}.${2}.0")).read_version(page).unwrap();
+        assert_eq!(replaced.version, "2.4.0");
+        assert!(matches!(checkver("Nope", None).read_version(page), Err(GetError::PackageNotFound(_))));
+        assert!(matches!(checkver("(", None).read_version(page), Err(GetError::ValidationError(_))));
+    }
+
+    #[test]
+    fn substitutes_version_and_match_variables() {
+        let mut matches = BTreeMap::new();
+        matches.insert("match1".to_string(), "one".to_string());
+        matches.insert("match10".to_string(), "ten".to_string());
+        let checked = CheckedVersion { version: "1.2.3-beta".to_string(), matches };
+        let variables = checked.variables();
+
+        assert_eq!(
+            substitute("/$majorVersion.$minorVersion/app-$version-$cleanVersion.zip", &variables),
+            "/1.2/app-1.2.3-beta-123beta.zip"
+        );
+        assert_eq!(substitute("$preReleaseVersion $underscoreVersion", &variables), "beta 1_2_3-beta");
+        assert_eq!(substitute("$match10 $match1", &variables), "ten one");
+        assert_eq!(substitute("$matchHead$matchTail", &version_variables("1.2.3.4")), "1.2.3.4");
+    }
+
+    #[test]
+    fn rewrites_urls_and_hashes_for_each_architecture() {
+        let mut manifest = json!({
+            "version": "1.0",
+            "url": ["https://example.com/1.0/app.zip", "https://example.com/1.0/extra.zip"],
+            "hash": ["old", "old"],
+            "architecture": {
+                "64bit": { "url": "https://example.com/1.0/app-x64.zip#/app.zip", "hash": "old" },
+                "32bit": { "url": ["https://example.com/1.0/app-x86.zip"], "hash": ["old"] }
+            },
+            "autoupdate": {
+                "url": ["https://example.com/$version/app.zip", "https://example.com/$version/extra.zip"],
+                "architecture": {
+                    "64bit": { "url": "https://example.com/$version/app-x64.zip#/app.zip" },
+                    "32bit": { "url": "https://example.com/$version/app-x86.zip" },
+                    "arm64": { "url": "https://example.com/$version/app-arm64.zip" }
+                }
+            }
+        });
+        let checked = CheckedVersion { version: "2.0".to_string(), matches: BTreeMap::new() };
+        let mut hashed = Vec::new();
+        update_manifest(&mut manifest, &checked, |url| {
+            hashed.push(url.to_string());
+            Ok(format!("hash of {}", url.rsplit('/').next().unwrap()))
+        })
+        .unwrap();
+
+        assert_eq!(manifest["version"], "2.0");
+        assert_eq!(manifest["url"], json!(["https://example.com/2.0/app.zip", "https://example.com/2.0/extra.zip"]));
+        assert_eq!(manifest["hash"], json!(["hash of app.zip", "hash of extra.zip"]));
+        let architecture = &manifest["architecture"];
+        assert_eq!(architecture["64bit"]["url"], "https://example.com/2.0/app-x64.zip#/app.zip");
+        assert_eq!(architecture["64bit"]["hash"], "hash of app-x64.zip");
+        assert_eq!(architecture["32bit"]["url"], json!(["https://example.com/2.0/app-x86.zip"]));
+        assert_eq!(architecture["32bit"]["hash"], json!(["hash of app-x86.zip"]));
+        assert_eq!(architecture["arm64"]["hash"], "hash of app-arm64.zip");
+        // The rename is not part of the download
+        assert!(hashed.contains(&"https://example.com/2.0/app-x64.zip".to_string()));
+    }
+
+    #[test]
+    fn needs_an_autoupdate_url() {
+        let checked = CheckedVersion { version: "2.0".to_string(), matches: BTreeMap::new() };
+        for mut manifest in [json!({ "version": "1.0" }), json!({ "version": "1.0", "autoupdate": { "hash": {} } })] {
+            assert!(update_manifest(&mut manifest, &checked, |_| Ok(String::new())).is_err());
+            assert_eq!(manifest["version"], "1.0");
+        }
+    }
+}
@@ -0,0 +1,335 @@
+//! Package source selection
+//!
+//! A package can come from any configured HTTP repository or from one of the
+//! built-in backends. The order in which sources are tried is decided by, in
+//! turn:
+//!
+//! 1. a `source:` qualifier on the request (`get install scoop:git`),
+//! 2. a pin in the config (`[pins] git = "winget"`),
+//! 3. the preferred manager (`default_package_manager`),
+//! 4. numeric priorities, highest first (`Repository.priority`, `[priorities]`).
+//!
+//! On Linux the distribution's package manager is a source too, under its own
+//! name (`apt:ripgrep`) or as `native:`. Language ecosystems (`cargo:`,
+//! `pipx:`, `npm:`, `go:`) are only tried when qualified, pinned, or given a
+//! priority in `[priorities]`. GitHub releases (`gh:owner/repo`) are only
+//! used when qualified or pinned. Plugins (`get-<name>` executables, see
+//! [`crate::plugin`]) are named sources too, ranked only with a priority.
+//!
+//! Qualified and pinned packages are only looked up in that one source.
+
+use std::fmt;
+
+use crate::config::{Config, Repository};
+use crate::ecosystem::Ecosystem;
+use crate::error::GetError;
+use crate::native::NativeKind;
+use crate::plugin::{discover_plugins, Plugin};
+
+/// Priority of repositories without an explicit `priority`
+pub const DEFAULT_REPOSITORY_PRIORITY: i32 = 100;
+
+/// Built-in backends and their default priorities
+pub const DEFAULT_BACKEND_PRIORITIES: [(&str, i32); 3] = [("choco", 50), ("scoop", 40), ("winget", 30)];
+
+/// Default priority of the native package manager, configurable as `native`
+/// or under the manager's own name
+pub const DEFAULT_NATIVE_PRIORITY: i32 = 60;
+
+/// Where a package is installed from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    /// An HTTP repository from `Config.repositories`, by name
+    Repository(String),
+    Chocolatey,
+    Scoop,
+    Winget,
+    /// The distribution's package manager
+    Native(NativeKind),
+    /// A language ecosystem's installer
+    Ecosystem(Ecosystem),
+    /// Release assets of a GitHub repository
+    GitHub,
+    /// A `get-<name>` plugin, by name
+    Plugin(String),
+}
+
+impl Source {
+    /// The backend for a name or alias, if it is one
+    pub fn backend(name: &str) -> Option<Source> {
+        match name.to_lowercase().as_str() {
+            "choco" | "chocolatey" => Some(Source::Chocolatey),
+            "scoop" => Some(Source::Scoop),
+            "winget" => Some(Source::Winget),
+            "gh" | "github" => Some(Source::GitHub),
+            "native" => NativeKind::detect().map(Source::Native),
+            other => NativeKind::from_name(other)
+                .map(Source::Native)
+                .or_else(|| Ecosystem::from_name(other).map(Source::Ecosystem)),
+        }
+    }
+
+    /// Resolve a backend name or repository name
+    pub fn named(name: &str, config: &Config) -> Result<Source, GetError> {
+        if let Some(backend) = Source::backend(name) {
+            return Ok(backend);
+        }
+        config
+            .get_repositories()
+            .iter()
+            .find(|r| r.name.eq_ignore_ascii_case(name))
+            .map(|r| Source::Repository(r.name.clone()))
+            .or_else(|| Plugin::find(name).map(|plugin| Source::Plugin(plugin.name)))
+            .ok_or_else(|| {
+                let mut known: Vec<String> = DEFAULT_BACKEND_PRIORITIES.iter().map(|(n, _)| n.to_string()).collect();
+                known.extend(NativeKind::ALL.iter().map(|kind| kind.name().to_string()));
+                known.extend(Ecosystem::ALL.iter().map(|ecosystem| ecosystem.name().to_string()));
+                known.push("gh".to_string());
+                known.extend(config.get_repositories().iter().map(|r| r.name.clone()));
+                known.extend(discover_plugins().into_iter().map(|plugin| plugin.name));
+                GetError::InvalidInput(format!("Unknown source '{}'. Known sources: {}", name, known.join(", ")))
+            })
+    }
+
+    /// The config key of a backend, or the repository name
+    pub fn name(&self) -> &str {
+        match self {
+            Source::Repository(name) => name,
+            Source::Chocolatey => "choco",
+            Source::Scoop => "scoop",
+            Source::Winget => "winget",
+            Source::Native(kind) => kind.name(),
+            Source::Ecosystem(ecosystem) => ecosystem.name(),
+            Source::GitHub => "gh",
+            Source::Plugin(name) => name,
+        }
+    }
+
+    /// The configured priority of this source
+    pub fn priority(&self, config: &Config) -> i32 {
+        match self {
+            Source::Repository(name) => config
+                .get_repositories()
+                .iter()
+                .find(|r| &r.name == name)
+                .and_then(|r| r.priority)
+                .unwrap_or(DEFAULT_REPOSITORY_PRIORITY),
+            Source::Native(kind) => config
+                .priorities
+                .get(kind.name())
+                .or_else(|| config.priorities.get("native"))
+                .copied()
+                .unwrap_or(DEFAULT_NATIVE_PRIORITY),
+            Source::Ecosystem(ecosystem) => config.priorities.get(ecosystem.name()).copied().unwrap_or(0),
+            Source::Plugin(name) => config.priorities.get(name).copied().unwrap_or(0),
+            backend => config.priorities.get(backend.name()).copied().unwrap_or_else(|| {
+                DEFAULT_BACKEND_PRIORITIES
+                    .iter()
+                    .find(|(name, _)| *name == backend.name())
+                    .map(|(_, priority)| *priority)
+                    .unwrap_or(0)
+            }),
+        }
+    }
+
+    /// The repository config for a `Source::Repository`
+    pub fn repository<'a>(&self, config: &'a Config) -> Option<&'a Repository> {
+        match self {
+            Source::Repository(name) => config.get_repositories().iter().find(|r| &r.name == name),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Source::Repository(name) => write!(f, "repository '{}'", name),
+            Source::Chocolatey => write!(f, "Chocolatey"),
+            Source::Scoop => write!(f, "Scoop"),
+            Source::Winget => write!(f, "Winget"),
+            Source::Native(kind) => write!(f, "{}", kind.name()),
+            Source::Ecosystem(ecosystem) => write!(f, "{}", ecosystem.name()),
+            Source::GitHub => write!(f, "GitHub"),
+            Source::Plugin(name) => write!(f, "plugin '{}'", name),
+        }
+    }
+}
+
+/// A source to try, and why it was chosen
+#[derive(Debug, Clone)]
+pub struct SourceChoice {
+    pub source: Source,
+    pub reason: String,
+}
+
+/// Split a `source:package` request; a package without a qualifier is returned as is
+pub fn split_source_qualifier<'a>(request: &'a str, config: &Config) -> Result<(Option<Source>, &'a str), GetError> {
+    match request.split_once(':') {
+        Some((source, package)) if !source.is_empty() && !package.is_empty() => {
+            Ok((Some(Source::named(source, config)?), package))
+        }
+        _ => Ok((None, request)),
+    }
+}
+
+/// Every source, ordered by preference, with the reason for its position
+pub fn ranked_sources(config: &Config) -> Vec<SourceChoice> {
+    let preferred = config.get_preferred_manager().and_then(Source::backend);
+
+    let mut sources: Vec<Source> = config
+        .get_repositories()
+        .iter()
+        .map(|r| Source::Repository(r.name.clone()))
+        .chain(NativeKind::detect().map(Source::Native))
+        .chain([Source::Chocolatey, Source::Scoop, Source::Winget])
+        .chain(
+            Ecosystem::ALL
+                .iter()
+                .filter(|ecosystem| config.priorities.contains_key(ecosystem.name()))
+                .map(|ecosystem| Source::Ecosystem(*ecosystem)),
+        )
+        .chain(
+            discover_plugins()
+                .into_iter()
+                .filter(|plugin| config.priorities.contains_key(&plugin.name))
+                .map(|plugin| Source::Plugin(plugin.name)),
+        )
+        .collect();
+    // Stable sort keeps config order between equal priorities
+    sources.sort_by_key(|source| (Some(source) != preferred.as_ref(), -source.priority(config)));
+
+    sources
+        .into_iter()
+        .map(|source| {
+            let reason = if Some(&source) == preferred.as_ref() {
+                "preferred manager".to_string()
+            } else {
+                format!("priority {}", source.priority(config))
+            };
+            SourceChoice { source, reason }
+        })
+        .collect()
+}
+
+/// Decide which sources to try for `request`, returning the package spec
+/// without its qualifier and the sources in order
+pub fn plan_sources<'a>(request: &'a str, config: &Config) -> Result<(&'a str, Vec<SourceChoice>), GetError> {
+    let (qualifier, package) = split_source_qualifier(request, config)?;
+    if let Some(source) = qualifier {
+        let reason = format!("requested with '{}:'", source.name());
+        return Ok((package, vec![SourceChoice { source, reason }]));
+    }
+
+    let (name, _) = crate::version::split_package_spec(package);
+    if let Some(pinned) = config.get_pin(name) {
+        let source = Source::named(pinned, config)?;
+        return Ok((package, vec![SourceChoice { source, reason: "pinned in config".to_string() }]));
+    }
+
+    Ok((package, ranked_sources(config)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::PackageFormat;
+
+    fn repository(name: &str, priority: Option<i32>) -> Repository {
+        Repository {
+            name: name.to_string(),
+            url: format!("https://{}.example.com", name),
+            package_format: PackageFormat::Json,
+            authentication: None,
+            priority,
+        }
+    }
+
+    fn config() -> Config {
+        Config {
+            repositories: vec![repository("low", Some(10)), repository("high", Some(200)), repository("plain", None)],
+            ..Default::default()
+        }
+    }
+
+    fn sources(choices: &[SourceChoice]) -> Vec<Source> {
+        choices.iter().map(|choice| choice.source.clone()).collect()
+    }
+
+    /// Where `source` is among `choices`
+    fn rank(choices: &[SourceChoice], source: &Source) -> usize {
+        choices.iter().position(|choice| &choice.source == source).unwrap()
+    }
+
+    #[test]
+    fn a_qualifier_beats_a_pin() {
+        let mut config = config();
+        config.pins.insert("git".to_string(), "winget".to_string());
+        config.default_package_manager = Some("choco".to_string());
+
+        let (package, choices) = plan_sources("scoop:git@2", &config).unwrap();
+        assert_eq!(package, "git@2");
+        assert_eq!(sources(&choices), [Source::Scoop]);
+        assert_eq!(choices[0].reason, "requested with 'scoop:'");
+
+        let (_, choices) = plan_sources("high:git", &config).unwrap();
+        assert_eq!(sources(&choices), [Source::Repository("high".to_string())]);
+        assert!(matches!(plan_sources("nowhere:git", &config), Err(GetError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn a_pin_beats_the_preferred_manager() {
+        let mut config = config();
+        config.pins.insert("git".to_string(), "low".to_string());
+        config.default_package_manager = Some("winget".to_string());
+
+        let (package, choices) = plan_sources("git@^2", &config).unwrap();
+        assert_eq!(package, "git@^2");
+        assert_eq!(sources(&choices), [Source::Repository("low".to_string())]);
+        assert_eq!(choices[0].reason, "pinned in config");
+
+        // Other packages aren't affected
+        let (_, choices) = plan_sources("curl", &config).unwrap();
+        assert_eq!(choices[0].source, Source::Winget);
+    }
+
+    #[test]
+    fn the_preferred_manager_beats_priorities() {
+        let mut config = config();
+        config.default_package_manager = Some("winget".to_string());
+
+        let (_, choices) = plan_sources("git", &config).unwrap();
+        assert_eq!(choices[0].source, Source::Winget);
+        assert_eq!(choices[0].reason, "preferred manager");
+        assert_eq!(choices[1].source, Source::Repository("high".to_string()));
+        assert_eq!(choices[1].reason, "priority 200");
+    }
+
+    #[test]
+    fn priorities_order_the_rest() {
+        let mut config = config();
+        let (_, choices) = plan_sources("git", &config).unwrap();
+        let order = [
+            Source::Repository("high".to_string()),
+            Source::Repository("plain".to_string()),
+            Source::Chocolatey,
+            Source::Scoop,
+            Source::Winget,
+            Source::Repository("low".to_string()),
+        ];
+        for pair in order.windows(2) {
+            assert!(rank(&choices, &pair[0]) < rank(&choices, &pair[1]), "{} before {}", pair[0], pair[1]);
+        }
+        // Ecosystems are only tried once they have a priority
+        assert!(!choices.iter().any(|choice| matches!(choice.source, Source::Ecosystem(_))));
+
+        config.priorities.insert("scoop".to_string(), 300);
+        config.priorities.insert("cargo".to_string(), 20);
+        let (_, choices) = plan_sources("git", &config).unwrap();
+        assert_eq!(choices[0].source, Source::Scoop);
+        assert_eq!(choices[0].reason, "priority 300");
+        let cargo = Source::Ecosystem(Ecosystem::Cargo);
+        assert!(rank(&choices, &cargo) < rank(&choices, &Source::Repository("low".to_string())));
+        assert!(rank(&choices, &Source::Winget) < rank(&choices, &cargo));
+    }
+}
@@ -0,0 +1,267 @@
+//! Static repository generation
+//!
+//! Turns a directory of `PackageInfo` manifests into a site that any plain web
+//! server can host as a repository (see `crate::protocol`):
+//!
+//! ```text
+//! <out>/index.json, index.msgpack
+//! <out>/packages/<name>/<version>.json, <version>.msgpack
+//! ```
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use indicatif::MultiProgress;
+
+use crate::config::PackageFormat;
+use crate::error::GetError;
+use crate::logging::Logger;
+use crate::package_manager::{InstallInstructions, PackageInfo};
+use crate::protocol;
+use crate::utils::{download_file, sha256_file};
+use crate::version::{split_package_spec, VersionReq};
+
+/// Formats written for every index and package file
+const SITE_FORMATS: [PackageFormat; 2] = [PackageFormat::Json, PackageFormat::MsgPack];
+
+/// What a build produced
+#[derive(Debug, Default)]
+pub struct BuildSummary {
+    pub packages: usize,
+    pub versions: usize,
+    pub hashes_computed: usize,
+}
+
+/// Check a manifest for problems a client would hit at install time
+pub fn validate_manifest(package: &PackageInfo) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    if package.name.trim().is_empty() {
+        problems.push("name is empty".to_string());
+    } else if package.name.contains(|c: char| c == '/' || c == '\\' || c.is_whitespace()) {
+        problems.push(format!("name '{}' must not contain slashes or whitespace", package.name));
+    }
+    if package.version.trim().is_empty() {
+        problems.push("version is empty".to_string());
+    } else if package.version.contains(['/', '\\']) {
+        problems.push(format!("version '{}' must not contain slashes", package.version));
+    }
+    match url::Url::parse(&package.source_url) {
+        Ok(url) if url.scheme() == "http" || url.scheme() == "https" => {}
+        _ => problems.push(format!("source_url '{}' is not an http(s) URL", package.source_url)),
+    }
+    if !package.checksum.is_empty()
+        && (package.checksum.len() != 64 || !package.checksum.chars().all(|c| c.is_ascii_hexdigit()))
+    {
+        problems.push("checksum is not a hex-encoded SHA-256".to_string());
+    }
+    match &package.install_instructions {
+        InstallInstructions::Script { interpreter, script } if interpreter.is_empty() || script.is_empty() => {
+            problems.push("script install instructions need an interpreter and a script".to_string());
+        }
+        _ => {}
+    }
+    for dependency in &package.dependencies {
+        let (name, requirement) = split_package_spec(dependency);
+        if name.trim().is_empty() {
+            problems.push("dependency names must not be empty".to_string());
+        } else if let Some(Err(e)) = requirement.map(VersionReq::parse) {
+            problems.push(format!("dependency '{}': {}", dependency, e));
+        }
+    }
+
+    problems
+}
+
+/// Download an artifact and compute its SHA-256
+fn compute_checksum(package: &PackageInfo, work_dir: &Path, logger: &Logger, m: &MultiProgress) -> Result<String, GetError> {
+    logger.log(&format!("Computing checksum for '{}' {}...", package.name, package.version));
+    let path = download_file(&package.source_url, work_dir, logger, m)?;
+    let checksum = sha256_file(&path)?;
+    fs::remove_file(&path)?;
+    Ok(checksum)
+}
+
+fn write_formats<T: serde::Serialize>(base: &Path, value: &T) -> Result<(), GetError> {
+    for format in SITE_FORMATS {
+        let path = PathBuf::from(format!("{}.{}", base.display(), protocol::extension(&format)));
+        fs::write(path, protocol::encode(&format, value)?)?;
+    }
+    Ok(())
+}
+
+/// Validate every manifest under `manifests_dir`, fill in missing checksums
+/// and write a static repository to `out_dir`.
+pub fn build_static_repository(
+    manifests_dir: &Path,
+    out_dir: &Path,
+    logger: &Logger,
+    m: &MultiProgress,
+) -> Result<BuildSummary, GetError> {
+    let manifests = protocol::load_manifests(manifests_dir)?;
+    if manifests.is_empty() {
+        return Err(GetError::InvalidInput(format!(
+            "No manifests found in '{}'",
+            manifests_dir.display()
+        )));
+    }
+
+    // Report every problem at once rather than one per run
+    let mut problems = Vec::new();
+    let mut seen = HashSet::new();
+    for (path, package) in &manifests {
+        for problem in validate_manifest(package) {
+            problems.push(format!("{}: {}", path.display(), problem));
+        }
+        if !seen.insert((package.name.to_lowercase(), package.version.clone())) {
+            problems.push(format!(
+                "{}: duplicate manifest for '{}' {}",
+                path.display(),
+                package.name,
+                package.version
+            ));
+        }
+    }
+    if !problems.is_empty() {
+        return Err(GetError::ValidationError(format!(
+            "{} problem(s) found:\n  {}",
+            problems.len(),
+            problems.join("\n  ")
+        )));
+    }
+
+    let work_dir = tempfile::tempdir()?;
+    let mut summary = BuildSummary::default();
+    let mut packages = Vec::with_capacity(manifests.len());
+    for (_, mut package) in manifests {
+        if package.checksum.is_empty() {
+            package.checksum = compute_checksum(&package, work_dir.path(), logger, m)?;
+            summary.hashes_computed += 1;
+        }
+        packages.push(package);
+    }
+
+    let grouped = protocol::group_packages(packages);
+    summary.packages = grouped.len();
+
+    fs::create_dir_all(out_dir)?;
+    for versions in grouped.values() {
+        for package in versions {
+            let package_dir = out_dir.join("packages").join(&package.name);
+            fs::create_dir_all(&package_dir)?;
+            write_formats(&package_dir.join(&package.version), package)?;
+            summary.versions += 1;
+        }
+    }
+    write_formats(&out_dir.join("index"), &protocol::build_index(&grouped))?;
+
+    logger.info(&format!(
+        "Built repository with {} packages ({} versions) in '{}'",
+        summary.packages,
+        summary.versions,
+        out_dir.display()
+    ));
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logging::LogLevel;
+    use crate::protocol::RepositoryIndex;
+    use crate::utils::sha256_file;
+    use indicatif::ProgressDrawTarget;
+    use std::thread;
+
+    const ARTIFACT: &str = "#!/bin/sh\necho tool\n";
+
+    fn package(name: &str, version: &str, source_url: &str, checksum: &str) -> PackageInfo {
+        PackageInfo {
+            name: name.to_string(),
+            version: version.to_string(),
+            description: format!("The {} package", name),
+            author: None,
+            license: None,
+            source_url: source_url.to_string(),
+            checksum: checksum.to_string(),
+            dependencies: Vec::new(),
+            install_instructions: InstallInstructions::Script { interpreter: "sh".to_string(), script: "true".to_string() },
+            uninstall_instructions: None,
+        }
+    }
+
+    fn write_manifest(dir: &Path, file: &str, package: &PackageInfo) {
+        fs::write(dir.join(file), serde_json::to_string(package).unwrap()).unwrap();
+    }
+
+    /// Serve `ARTIFACT` at every path until the test process exits
+    fn serve() -> String {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", server.server_addr().to_ip().unwrap());
+        thread::spawn(move || {
+            for request in server.incoming_requests() {
+                let _ = request.respond(tiny_http::Response::from_string(ARTIFACT));
+            }
+        });
+        url
+    }
+
+    fn build(manifests: &Path, out: &Path) -> Result<BuildSummary, GetError> {
+        let m = MultiProgress::with_draw_target(ProgressDrawTarget::hidden());
+        build_static_repository(manifests, out, &Logger::new(LogLevel::Minimal), &m)
+    }
+
+    #[test]
+    fn builds_a_site_and_fills_in_missing_checksums() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifests = dir.path().join("manifests");
+        fs::create_dir(&manifests).unwrap();
+        let url = format!("{}/tool.sh", serve());
+        write_manifest(&manifests, "tool-1.json", &package("tool", "1.0.0", &url, &"a".repeat(64)));
+        write_manifest(&manifests, "tool-2.json", &package("tool", "2.0.0", &url, ""));
+        write_manifest(&manifests, "other.json", &package("other", "0.1.0", &url, &"b".repeat(64)));
+        let out = dir.path().join("site");
+
+        let summary = build(&manifests, &out).unwrap();
+        assert_eq!((summary.packages, summary.versions, summary.hashes_computed), (2, 3, 1));
+
+        fs::write(dir.path().join("artifact"), ARTIFACT).unwrap();
+        let expected = sha256_file(&dir.path().join("artifact")).unwrap();
+        for format in SITE_FORMATS {
+            let extension = protocol::extension(&format);
+            let built: PackageInfo =
+                protocol::decode(&format, &fs::read(out.join(format!("packages/tool/2.0.0.{}", extension))).unwrap()).unwrap();
+            assert_eq!(built.checksum, expected);
+            let index: RepositoryIndex =
+                protocol::decode(&format, &fs::read(out.join(format!("index.{}", extension))).unwrap()).unwrap();
+            let entries: Vec<_> = index.packages.iter().map(|p| (p.name.as_str(), p.versions.clone())).collect();
+            assert_eq!(entries, [("other", vec!["0.1.0".to_string()]), ("tool", vec!["2.0.0".to_string(), "1.0.0".to_string()])]);
+        }
+    }
+
+    #[test]
+    fn reports_every_problem_before_building() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifests = dir.path().join("manifests");
+        fs::create_dir(&manifests).unwrap();
+        let url = "https://example.com/tool.sh";
+        write_manifest(&manifests, "a.json", &package("tool", "1.0.0", url, &"a".repeat(64)));
+        write_manifest(&manifests, "b.json", &package("Tool", "1.0.0", url, &"a".repeat(64)));
+        write_manifest(&manifests, "c.json", &package("bad name", "1.0.0", "ftp://example.com", "xyz"));
+        let out = dir.path().join("site");
+
+        match build(&manifests, &out) {
+            Err(GetError::ValidationError(message)) => {
+                assert!(message.starts_with("4 problem(s)"), "{}", message);
+                assert!(message.contains("duplicate manifest for 'Tool' 1.0.0"), "{}", message);
+            }
+            other => panic!("expected a validation error, got {:?}", other),
+        }
+        assert!(!out.exists());
+
+        let empty = dir.path().join("empty");
+        fs::create_dir(&empty).unwrap();
+        assert!(matches!(build(&empty, &out), Err(GetError::InvalidInput(_))));
+    }
+}
@@ -1,6 +1,6 @@
 //! Utility functions for the package manager
 
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 use std::time::Duration;
 use crate::error::GetError;
 use crate::logging::Logger;
@@ -38,7 +38,7 @@ pub fn download_file(
 
     let file_name = url
         .split('/')
-        .last()
+        .next_back()
         .ok_or_else(|| GetError::InvalidInput("Invalid URL".to_string()))?;
     let file_path = download_dir.join(file_name);
 
@@ -73,13 +73,11 @@ pub fn download_file(
     Ok(file_path)
 }
 
-/// Verify file checksum
-pub fn verify_checksum(file_path: &Path, expected_hash: &str, logger: &Logger) -> Result<(), GetError> {
-    logger.log("Verifying checksum...");
-
+/// Compute the hex-encoded SHA-256 of a file
+pub fn sha256_file(file_path: &Path) -> Result<String, GetError> {
     let mut file = File::open(file_path)?;
     let mut hasher = Sha256::new();
-    let mut buffer = [0u8; 1024 * 1024]; // 1MB buffer
+    let mut buffer = vec![0u8; 1024 * 1024]; // 1MB buffer
 
     loop {
         let n = file.read(&mut buffer)?;
@@ -89,8 +87,14 @@ pub fn verify_checksum(file_path: &Path, expected_hash: &str, logger: &Logger) -
         hasher.update(&buffer[..n]);
     }
 
-    let result = hasher.finalize();
-    let calculated_hash = hex_encode(result);
+    Ok(hex_encode(hasher.finalize()))
+}
+
+/// Verify file checksum
+pub fn verify_checksum(file_path: &Path, expected_hash: &str, logger: &Logger) -> Result<(), GetError> {
+    logger.log("Verifying checksum...");
+
+    let calculated_hash = sha256_file(file_path)?;
 
     if calculated_hash.eq_ignore_ascii_case(expected_hash) {
         logger.log("Checksum verification passed");
@@ -102,3 +106,37 @@ pub fn verify_checksum(file_path: &Path, expected_hash: &str, logger: &Logger) -
         )))
     }
 }
+
+/// Check that a package `what` (its name or version) is a single plain path
+/// component, so joining it onto a directory stays inside that directory
+pub fn validate_path_component(what: &str, value: &str) -> Result<(), GetError> {
+    let mut components = Path::new(value).components();
+    let is_single = matches!((components.next(), components.next()), (Some(Component::Normal(_)), None));
+    if value.is_empty() || value.contains(['/', '\\']) || value == "." || value == ".." || !is_single {
+        return Err(GetError::ValidationError(format!("Invalid package {} '{}'", what, value)));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_plain_names_and_versions() {
+        for value in ["ripgrep", "1.2.3", "v1.0-beta+build.5", "node_modules", "..hidden"] {
+            assert!(validate_path_component("name", value).is_ok(), "{}", value);
+        }
+    }
+
+    #[test]
+    fn rejects_values_that_leave_their_directory() {
+        for value in ["", ".", "..", "../evil", "a/b", "a\\b", "/etc", "C:\\Windows", "a/"] {
+            assert!(
+                matches!(validate_path_component("version", value), Err(GetError::ValidationError(_))),
+                "{}",
+                value
+            );
+        }
+    }
+}
@@ -0,0 +1,624 @@
+//! Version parsing and comparison
+//!
+//! Package sources use loosely formatted version strings: Winget accepts
+//! anything from `1.2` to `2024.05.01-beta2`, Scoop versions are free-form,
+//! and native manifests use semver. [`Version`] orders all of them in a way
+//! that agrees with semver where the input is semver. [`VersionReq`]
+//! describes which of those versions a request or dependency accepts.
+
+use std::cmp::Ordering;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::GetError;
+
+/// A single dot-separated version component
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Number(u64),
+    Text(String),
+}
+
+/// A parsed, comparable version string
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub struct Version {
+    raw: String,
+    release: Vec<Segment>,
+    pre_release: Vec<Segment>,
+}
+
+impl Version {
+    /// Parse a version string. Parsing never fails; unrecognised text is
+    /// compared lexically.
+    pub fn parse(input: &str) -> Version {
+        let raw = input.trim().to_string();
+        // Tolerate tag-style versions such as `v1.2.3`
+        let trimmed = raw
+            .strip_prefix(['v', 'V'])
+            .filter(|rest| rest.starts_with(|c: char| c.is_ascii_digit()))
+            .unwrap_or(raw.as_str());
+
+        // Build metadata never affects precedence
+        let without_build = trimmed.split('+').next().unwrap_or("");
+        let (release, pre_release) = match without_build.split_once('-') {
+            Some((release, pre)) => (release, pre),
+            None => (without_build, ""),
+        };
+
+        let mut release = split_segments(release);
+        // `1.2` and `1.2.0` are the same version
+        while matches!(release.last(), Some(Segment::Number(0))) && release.len() > 1 {
+            release.pop();
+        }
+        let pre_release = split_segments(pre_release);
+
+        Version {
+            raw,
+            release,
+            pre_release,
+        }
+    }
+
+    /// Whether this is a pre-release (`1.0.0-beta`, `2.0-rc1`, `2.0rc1`)
+    pub fn is_pre_release(&self) -> bool {
+        !self.pre_release.is_empty() || self.release.iter().any(is_pre_release_tag)
+    }
+
+    /// The leading numeric components, without trailing zeros
+    fn numeric_prefix(&self) -> Vec<u64> {
+        let mut numbers: Vec<u64> = self
+            .release
+            .iter()
+            .map_while(|s| match s {
+                Segment::Number(n) => Some(*n),
+                Segment::Text(_) => None,
+            })
+            .collect();
+        while numbers.last() == Some(&0) {
+            numbers.pop();
+        }
+        numbers
+    }
+
+    /// The numeric release components, with missing components as zero
+    pub fn numbers(&self) -> Vec<u64> {
+        self.release
+            .iter()
+            .map(|s| match s {
+                Segment::Number(n) => *n,
+                Segment::Text(_) => 0,
+            })
+            .collect()
+    }
+
+    /// The original version string
+    pub fn as_str(&self) -> &str {
+        &self.raw
+    }
+}
+
+/// Split `1.2a.3` into `[1, 2, "a", 3]`, treating `.` and `_` as separators
+/// and breaking between digits and letters.
+fn split_segments(input: &str) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    for part in input.split(['.', '_']).filter(|p| !p.is_empty()) {
+        let mut current = String::new();
+        let mut current_is_digit = None;
+        for c in part.chars() {
+            let is_digit = c.is_ascii_digit();
+            if current_is_digit.is_some() && current_is_digit != Some(is_digit) {
+                segments.push(to_segment(&current));
+                current.clear();
+            }
+            current.push(c);
+            current_is_digit = Some(is_digit);
+        }
+        if !current.is_empty() {
+            segments.push(to_segment(&current));
+        }
+    }
+    segments
+}
+
+fn to_segment(text: &str) -> Segment {
+    match text.parse::<u64>() {
+        Ok(n) => Segment::Number(n),
+        Err(_) => Segment::Text(text.to_lowercase()),
+    }
+}
+
+/// Text that marks a build before the release it trails, as in `2.0rc1`;
+/// other text marks a later one, as in openssl's `1.1.1w`
+fn is_pre_release_tag(segment: &Segment) -> bool {
+    matches!(segment, Segment::Text(text) if ["alpha", "beta", "rc", "pre", "preview", "dev"].contains(&text.as_str()))
+}
+
+/// Where a version with a trailing `text` sorts relative to the one without
+fn trailing_text(text: &Segment) -> Ordering {
+    if is_pre_release_tag(text) {
+        Ordering::Less
+    } else {
+        Ordering::Greater
+    }
+}
+
+fn compare_segments(a: &[Segment], b: &[Segment]) -> Ordering {
+    for i in 0..a.len().max(b.len()) {
+        let ordering = match (a.get(i), b.get(i)) {
+            (Some(Segment::Number(x)), Some(Segment::Number(y))) => x.cmp(y),
+            (Some(Segment::Text(x)), Some(Segment::Text(y))) => x.cmp(y),
+            // Numbers sort after text (`1.0.1` > `1.0.beta`)
+            (Some(Segment::Number(_)), Some(Segment::Text(_))) => Ordering::Greater,
+            (Some(Segment::Text(_)), Some(Segment::Number(_))) => Ordering::Less,
+            // A missing numeric component counts as zero
+            (Some(Segment::Number(x)), None) => x.cmp(&0),
+            (None, Some(Segment::Number(y))) => 0.cmp(y),
+            // `1.0rc1` < `1.0` < `1.0a`
+            (Some(text), None) => trailing_text(text),
+            (None, Some(text)) => trailing_text(text).reverse(),
+            (None, None) => Ordering::Equal,
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        compare_segments(&self.release, &other.release).then_with(|| {
+            match (self.pre_release.is_empty(), other.pre_release.is_empty()) {
+                (true, true) => Ordering::Equal,
+                // A release sorts after any of its pre-releases
+                (true, false) => Ordering::Greater,
+                (false, true) => Ordering::Less,
+                (false, false) => compare_segments(&self.pre_release, &other.pre_release),
+            }
+        })
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Version {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Version {}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.raw)
+    }
+}
+
+impl From<String> for Version {
+    fn from(raw: String) -> Self {
+        Version::parse(&raw)
+    }
+}
+
+impl From<Version> for String {
+    fn from(version: Version) -> Self {
+        version.raw
+    }
+}
+
+impl std::str::FromStr for Version {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Version::parse(s))
+    }
+}
+
+/// How a [`Comparator`] relates a version to its operand
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    /// `=1.2` or a bare `1.2`
+    Exact,
+    Greater,
+    GreaterEq,
+    Less,
+    LessEq,
+    /// `~1.2`: the same minor version (or major, for `~1`)
+    Tilde,
+    /// `^1.2`: no change to the leftmost non-zero component
+    Caret,
+    /// `1.2.*`
+    Wildcard,
+}
+
+/// One condition of a [`VersionReq`], such as `>=3` or `^1.2`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Comparator {
+    op: Op,
+    version: Version,
+    /// Numeric components as written, so `^0.0` and `^0` can be told apart
+    components: Vec<u64>,
+}
+
+impl Comparator {
+    fn parse(input: &str) -> Option<Comparator> {
+        let input = input.trim();
+        let (op, text) = [
+            (">=", Op::GreaterEq),
+            ("<=", Op::LessEq),
+            ("==", Op::Exact),
+            (">", Op::Greater),
+            ("<", Op::Less),
+            ("=", Op::Exact),
+            ("^", Op::Caret),
+            ("~", Op::Tilde),
+        ]
+        .iter()
+        .find_map(|(prefix, op)| input.strip_prefix(prefix).map(|rest| (*op, rest.trim())))
+        .unwrap_or((Op::Exact, input));
+
+        // `1.2.*` and `1.2.x` pin everything before the wildcard
+        let (op, text) = match text.strip_suffix(".*").or_else(|| text.strip_suffix(".x")) {
+            Some(prefix) if op == Op::Exact => (Op::Wildcard, prefix),
+            _ => (op, text),
+        };
+        if text.is_empty() || text.contains('*') {
+            return None;
+        }
+
+        let components = text
+            .trim_start_matches(['v', 'V'])
+            .split('.')
+            .map_while(|part| part.parse::<u64>().ok())
+            .collect();
+        Some(Comparator {
+            op,
+            version: Version::parse(text),
+            components,
+        })
+    }
+
+    /// The first version past the range of `~`, `^` and `*`
+    fn upper_bound(&self) -> Version {
+        let mut bound = self.components.clone();
+        let keep = match self.op {
+            Op::Tilde => bound.len().min(2),
+            Op::Caret => bound.iter().position(|&n| n != 0).map_or(bound.len(), |i| i + 1),
+            _ => bound.len(),
+        };
+        bound.truncate(keep.max(1));
+        if let Some(last) = bound.last_mut() {
+            *last += 1;
+        }
+        let bound: Vec<String> = bound.iter().map(u64::to_string).collect();
+        Version::parse(&bound.join("."))
+    }
+
+    pub fn matches(&self, version: &Version) -> bool {
+        match self.op {
+            Op::Exact => version == &self.version,
+            Op::Greater => version > &self.version,
+            Op::GreaterEq => version >= &self.version,
+            Op::Less => version < &self.version,
+            Op::LessEq => version <= &self.version,
+            Op::Tilde | Op::Caret | Op::Wildcard => {
+                // Components that aren't numbers can't be bumped; compare them exactly
+                if self.components.is_empty() {
+                    return version == &self.version;
+                }
+                // Pre-releases of the bound are not in the range: `^1` stops before `2.0-rc1`
+                let upper = self.upper_bound();
+                version >= &self.version && version < &upper && version.numeric_prefix() != upper.numeric_prefix()
+            }
+        }
+    }
+}
+
+impl fmt::Display for Comparator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.op {
+            Op::Exact => write!(f, "={}", self.version),
+            Op::Greater => write!(f, ">{}", self.version),
+            Op::GreaterEq => write!(f, ">={}", self.version),
+            Op::Less => write!(f, "<{}", self.version),
+            Op::LessEq => write!(f, "<={}", self.version),
+            Op::Tilde => write!(f, "~{}", self.version),
+            Op::Caret => write!(f, "^{}", self.version),
+            Op::Wildcard => write!(f, "{}.*", self.version),
+        }
+    }
+}
+
+/// A version requirement: comma-separated comparators that must all hold,
+/// e.g. `^1.2`, `>=3,<4` or `2.*`. A bare version is an exact requirement,
+/// matching what `name@version` has always meant.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VersionReq {
+    comparators: Vec<Comparator>,
+}
+
+impl VersionReq {
+    /// The requirement every version satisfies
+    pub fn any() -> VersionReq {
+        VersionReq::default()
+    }
+
+    /// Exactly `version`
+    pub fn exact(version: &str) -> VersionReq {
+        VersionReq {
+            comparators: vec![Comparator {
+                op: Op::Exact,
+                version: Version::parse(version),
+                components: Vec::new(),
+            }],
+        }
+    }
+
+    /// At least `version`, as Winget's `MinimumVersion` means
+    pub fn at_least(version: &str) -> VersionReq {
+        VersionReq {
+            comparators: vec![Comparator {
+                op: Op::GreaterEq,
+                version: Version::parse(version),
+                components: Vec::new(),
+            }],
+        }
+    }
+
+    pub fn parse(input: &str) -> Result<VersionReq, GetError> {
+        let input = input.trim();
+        if input.is_empty() || input == "*" {
+            return Ok(VersionReq::any());
+        }
+        let comparators = input
+            .split(',')
+            .map(|part| {
+                Comparator::parse(part)
+                    .ok_or_else(|| GetError::InvalidInput(format!("Invalid version requirement '{}'", input)))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(VersionReq { comparators })
+    }
+
+    pub fn matches(&self, version: &Version) -> bool {
+        self.comparators.iter().all(|c| c.matches(version))
+    }
+
+    pub fn is_any(&self) -> bool {
+        self.comparators.is_empty()
+    }
+
+    /// The version an exact requirement names
+    pub fn exact_version(&self) -> Option<&Version> {
+        match self.comparators.as_slice() {
+            [Comparator { op: Op::Exact, version, .. }] => Some(version),
+            _ => None,
+        }
+    }
+
+    /// Versions satisfying both requirements
+    pub fn and(&self, other: &VersionReq) -> VersionReq {
+        let mut comparators = self.comparators.clone();
+        for comparator in &other.comparators {
+            if !comparators.contains(comparator) {
+                comparators.push(comparator.clone());
+            }
+        }
+        VersionReq { comparators }
+    }
+}
+
+impl fmt::Display for VersionReq {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.comparators.is_empty() {
+            return write!(f, "*");
+        }
+        let parts: Vec<String> = self.comparators.iter().map(Comparator::to_string).collect();
+        write!(f, "{}", parts.join(","))
+    }
+}
+
+/// Split a `name@version` package spec into its parts. The version part may
+/// be any [`VersionReq`]; `name>=2` and `name^1.2` are accepted as well, as
+/// manifests tend to write dependencies that way.
+pub fn split_package_spec(spec: &str) -> (&str, Option<&str>) {
+    match spec.rsplit_once('@') {
+        Some((name, version)) if !name.is_empty() && !version.is_empty() => (name, Some(version)),
+        _ => match spec.find(['<', '>', '=', '^', '~']) {
+            Some(i) if i > 0 && i + 1 < spec.len() => (spec[..i].trim_end(), Some(spec[i..].trim())),
+            _ => (spec, None),
+        },
+    }
+}
+
+/// Pick the newest of `candidates` matching `wanted` (a version or a
+/// [`VersionReq`]), preferring stable versions and falling back to the newest
+/// pre-release. With no `wanted`, any version matches.
+pub fn select_version<'a, T>(
+    candidates: &'a [T],
+    version_of: impl Fn(&T) -> &str,
+    wanted: Option<&str>,
+) -> Option<&'a T> {
+    let requirement = match wanted {
+        Some(wanted) => VersionReq::parse(wanted).unwrap_or_else(|_| VersionReq::exact(wanted)),
+        None => VersionReq::any(),
+    };
+    let newest = |stable_only: bool| {
+        candidates
+            .iter()
+            .map(|c| (Version::parse(version_of(c)), c))
+            .filter(|(v, _)| requirement.matches(v))
+            .filter(|(v, _)| !stable_only || !v.is_pre_release())
+            .max_by(|(a, _), (b, _)| a.cmp(b))
+            .map(|(_, c)| c)
+    };
+    newest(true).or_else(|| newest(false))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(input: &str) -> Version {
+        Version::parse(input)
+    }
+
+    fn req(input: &str) -> VersionReq {
+        VersionReq::parse(input).unwrap()
+    }
+
+    #[test]
+    fn orders_pre_releases_before_the_release() {
+        let ordered = ["1.0.0-alpha", "1.0.0-alpha.1", "1.0.0-beta", "1.0.0-rc.1", "1.0.0-rc.2", "1.0.0-rc.10", "1.0.0"];
+        for pair in ordered.windows(2) {
+            assert!(v(pair[0]) < v(pair[1]), "{} < {}", pair[0], pair[1]);
+        }
+        assert!(v("2.0-rc1").is_pre_release());
+        assert!(!v("2.0").is_pre_release());
+    }
+
+    #[test]
+    fn orders_numeric_components_numerically() {
+        assert!(v("1.10") > v("1.9"));
+        assert!(v("1.2.10") > v("1.2.9"));
+        assert!(v("10.0") > v("9.99.99"));
+        assert_eq!(v("1.2"), v("1.2.0"));
+        assert_eq!(v("v1.2.3"), v("1.2.3"));
+        assert_eq!(v("1.2.3+build.5"), v("1.2.3"));
+        assert!(v("1.0rc1") < v("1.0"));
+        assert!(v("1.0.beta") < v("1.0.1"));
+        assert_eq!(v("v1.2.3").as_str(), "v1.2.3");
+    }
+
+    #[test]
+    fn orders_letter_suffixes_after_the_release() {
+        assert!(v("1.1.1w") > v("1.1.1"));
+        assert!(v("1.1.1w") > v("1.1.1a"));
+        assert!(v("1.1.1w") < v("1.1.2"));
+        assert!(v("2.0a") > v("2.0"));
+        assert!(!v("2.0a").is_pre_release());
+        for tag in ["alpha", "beta", "rc", "pre", "preview", "dev"] {
+            let pre = format!("2.0{}1", tag);
+            assert!(v(&pre) < v("2.0"), "{}", pre);
+            assert!(v(&pre).is_pre_release(), "{}", pre);
+        }
+    }
+
+    #[test]
+    fn ranges_stop_before_pre_releases_of_their_bound() {
+        for version in ["2.0-rc1", "2.0.0-alpha", "2.0rc1"] {
+            assert!(!req("^1").matches(&v(version)), "^1 {}", version);
+            assert!(!req("^1.2").matches(&v(version)), "^1.2 {}", version);
+        }
+        assert!(!req("~1.2").matches(&v("1.3.0-beta")));
+        assert!(!req("1.*").matches(&v("2.0-rc1")));
+        assert!(req("^1").matches(&v("1.9.9")));
+        assert!(req("^1").matches(&v("1.5.0-beta")));
+        assert!(req("^2.0-rc1").matches(&v("2.0-rc2")));
+    }
+
+    #[test]
+    fn orders_date_versions() {
+        assert!(v("2024.05.01") < v("2024.12.1"));
+        assert!(v("2023.12.31") < v("2024.1.1"));
+        assert!(v("20240501") < v("20241201"));
+        assert!(v("2024.05.01-beta2") < v("2024.05.01"));
+    }
+
+    #[test]
+    fn caret_allows_changes_right_of_the_first_non_zero_component() {
+        let caret = req("^1.2");
+        assert!(caret.matches(&v("1.2")));
+        assert!(caret.matches(&v("1.9.9")));
+        assert!(!caret.matches(&v("1.1.9")));
+        assert!(!caret.matches(&v("2.0")));
+
+        let caret = req("^0.2.1");
+        assert!(caret.matches(&v("0.2.5")));
+        assert!(!caret.matches(&v("0.2.0")));
+        assert!(!caret.matches(&v("0.3.0")));
+
+        let caret = req("^0.0.3");
+        assert!(caret.matches(&v("0.0.3")));
+        assert!(!caret.matches(&v("0.0.4")));
+    }
+
+    #[test]
+    fn tilde_allows_patch_changes() {
+        let tilde = req("~1.2.3");
+        assert!(tilde.matches(&v("1.2.3")));
+        assert!(tilde.matches(&v("1.2.9")));
+        assert!(!tilde.matches(&v("1.2.2")));
+        assert!(!tilde.matches(&v("1.3.0")));
+
+        let tilde = req("~1");
+        assert!(tilde.matches(&v("1.9")));
+        assert!(!tilde.matches(&v("2.0")));
+    }
+
+    #[test]
+    fn wildcard_pins_the_leading_components() {
+        let wildcard = req("2.*");
+        assert!(wildcard.matches(&v("2.0")));
+        assert!(wildcard.matches(&v("2.5.1")));
+        assert!(!wildcard.matches(&v("1.9")));
+        assert!(!wildcard.matches(&v("3.0")));
+
+        assert!(req("1.2.x").matches(&v("1.2.7")));
+        assert!(!req("1.2.x").matches(&v("1.3.0")));
+        assert!(req("*").is_any());
+        assert!(req("*").matches(&v("0.0.1")));
+    }
+
+    #[test]
+    fn comparison_operators() {
+        let range = req(">=3, <4");
+        assert!(range.matches(&v("3")));
+        assert!(range.matches(&v("3.9.9")));
+        assert!(!range.matches(&v("2.9")));
+        assert!(!range.matches(&v("4.0")));
+
+        assert!(!req(">1").matches(&v("1.0")));
+        assert!(req(">1").matches(&v("1.0.1")));
+        assert!(req("<=2").matches(&v("2.0")));
+        assert!(!req("<2").matches(&v("2.0")));
+        assert!(req("=1.2").matches(&v("1.2.0")));
+        assert!(req("==1.2").matches(&v("1.2")));
+        assert_eq!(req("1.2").exact_version(), Some(&v("1.2")));
+    }
+
+    #[test]
+    fn rejects_malformed_requirements() {
+        assert!(VersionReq::parse(">=").is_err());
+        assert!(VersionReq::parse("1.*.3").is_err());
+        assert!(VersionReq::parse(">=1,").is_err());
+    }
+
+    #[test]
+    fn splits_package_specs() {
+        assert_eq!(split_package_spec("ripgrep"), ("ripgrep", None));
+        assert_eq!(split_package_spec("ripgrep@14.1.0"), ("ripgrep", Some("14.1.0")));
+        assert_eq!(split_package_spec("ripgrep@^14"), ("ripgrep", Some("^14")));
+        assert_eq!(split_package_spec("@scope/pkg@1.0"), ("@scope/pkg", Some("1.0")));
+        assert_eq!(split_package_spec("@scope/pkg"), ("@scope/pkg", None));
+        assert_eq!(split_package_spec("ripgrep@"), ("ripgrep@", None));
+        assert_eq!(split_package_spec("ripgrep>=14"), ("ripgrep", Some(">=14")));
+        assert_eq!(split_package_spec("ripgrep ~14.1"), ("ripgrep", Some("~14.1")));
+    }
+
+    #[test]
+    fn selects_the_newest_stable_version() {
+        let versions = ["1.0", "1.2", "2.0-beta", "1.10"];
+        assert_eq!(select_version(&versions, |s| s, None), Some(&"1.10"));
+        assert_eq!(select_version(&versions, |s| s, Some("^1.0,<1.5")), Some(&"1.2"));
+        assert_eq!(select_version(&versions, |s| s, Some(">=2.0-alpha")), Some(&"2.0-beta"));
+        assert_eq!(select_version(&versions, |s| s, Some("3")), None);
+    }
+}
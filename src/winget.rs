@@ -0,0 +1,388 @@
+//! Winget manifest model
+//!
+//! A winget-pkgs version directory holds a multi-file manifest: a `version`
+//! manifest, an `installer` manifest, one `defaultLocale` manifest and any
+//! number of additional `locale` manifests. Older packages may instead ship a
+//! single `singleton` manifest, and the Winget CDN serves `merged` manifests.
+//! This module parses any of these layouts and merges it into a single
+//! [`WingetPackage`].
+
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::GetError;
+
+/// One YAML file of a Winget manifest set.
+///
+/// Every manifest type shares this shape; which fields are present depends on
+/// `ManifestType`.
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "PascalCase")]
+struct WingetManifestFile {
+    manifest_type: Option<String>,
+    package_identifier: Option<String>,
+    package_version: Option<String>,
+    default_locale: Option<String>,
+
+    // Installer manifest
+    installer_type: Option<String>,
+    installer_switches: Option<InstallerSwitches>,
+    commands: Option<Vec<String>>,
+    dependencies: Option<WingetDependencies>,
+    installers: Option<Vec<WingetInstaller>>,
+
+    // Locale manifests
+    #[serde(flatten)]
+    locale: WingetLocale,
+}
+
+/// Installer entry from an installer manifest
+#[derive(Debug, Deserialize, Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct WingetInstaller {
+    pub architecture: String,
+    pub installer_type: Option<String>,
+    pub installer_url: String,
+    pub installer_sha256: String,
+    pub installer_switches: Option<InstallerSwitches>,
+    pub scope: Option<String>,
+    pub installer_locale: Option<String>,
+    pub dependencies: Option<WingetDependencies>,
+}
+
+/// Dependencies declared at the manifest root or on a single installer
+#[derive(Debug, Deserialize, Clone, Serialize, Default)]
+#[serde(rename_all = "PascalCase")]
+pub struct WingetDependencies {
+    pub windows_features: Option<Vec<String>>,
+    pub windows_libraries: Option<Vec<String>>,
+    pub package_dependencies: Option<Vec<WingetPackageDependency>>,
+    pub external_dependencies: Option<Vec<String>>,
+}
+
+/// Another Winget package that must be installed first
+#[derive(Debug, Deserialize, Clone, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct WingetPackageDependency {
+    pub package_identifier: String,
+    pub minimum_version: Option<String>,
+}
+
+/// Installer switches
+#[derive(Debug, Deserialize, Clone, Serialize, Default)]
+#[serde(rename_all = "PascalCase")]
+pub struct InstallerSwitches {
+    pub silent: Option<String>,
+    pub silent_with_progress: Option<String>,
+    pub custom: Option<String>,
+}
+
+/// Locale-specific metadata from a `defaultLocale` or `locale` manifest
+#[derive(Debug, Deserialize, Clone, Serialize, Default)]
+#[serde(rename_all = "PascalCase")]
+pub struct WingetLocale {
+    pub package_locale: Option<String>,
+    pub publisher: Option<String>,
+    pub publisher_url: Option<String>,
+    pub package_name: Option<String>,
+    pub package_url: Option<String>,
+    pub license: Option<String>,
+    pub short_description: Option<String>,
+    pub description: Option<String>,
+    pub moniker: Option<String>,
+    pub tags: Option<Vec<String>>,
+    pub release_notes: Option<String>,
+    pub release_notes_url: Option<String>,
+}
+
+/// A fully merged Winget package version
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct WingetPackage {
+    pub identifier: String,
+    pub version: String,
+    pub default_locale: String,
+    pub installers: Vec<WingetInstaller>,
+    pub commands: Vec<String>,
+    /// Root-level dependencies, shared by every installer
+    #[serde(default)]
+    pub dependencies: WingetDependencies,
+    /// Locale manifests, with the default locale first
+    pub locales: Vec<WingetLocale>,
+}
+
+/// Pick the installer best suited to the current machine's architecture
+pub fn select_installer(installers: &[WingetInstaller]) -> Option<&WingetInstaller> {
+    let native = match std::env::consts::ARCH {
+        "x86_64" => "x64",
+        "aarch64" => "arm64",
+        "x86" => "x86",
+        "arm" => "arm",
+        other => other,
+    };
+
+    installers
+        .iter()
+        .find(|i| i.architecture.eq_ignore_ascii_case(native))
+        .or_else(|| installers.iter().find(|i| i.architecture.eq_ignore_ascii_case("neutral")))
+        // x64 Windows runs x86 installers
+        .or_else(|| if native == "x64" { installers.iter().find(|i| i.architecture == "x86") } else { None })
+        .or_else(|| installers.first())
+}
+
+impl WingetLocale {
+    /// Fill every missing field from `fallback`
+    fn or(mut self, fallback: &WingetLocale) -> WingetLocale {
+        macro_rules! fill {
+            ($($field:ident),*) => {
+                $(if self.$field.is_none() { self.$field = fallback.$field.clone(); })*
+            };
+        }
+        fill!(
+            package_locale, publisher, publisher_url, package_name, package_url, license,
+            short_description, description, moniker, tags, release_notes, release_notes_url
+        );
+        self
+    }
+}
+
+impl WingetPackage {
+    /// Winget packages required by `installer` (or by every installer when `None`)
+    pub fn package_dependencies(&self, installer: Option<&WingetInstaller>) -> Vec<WingetPackageDependency> {
+        let mut dependencies = self.dependencies.package_dependencies.clone().unwrap_or_default();
+        let installer_dependencies = installer
+            .and_then(|i| i.dependencies.as_ref())
+            .and_then(|d| d.package_dependencies.clone())
+            .unwrap_or_default();
+        for dependency in installer_dependencies {
+            if !dependencies.iter().any(|d| d.package_identifier.eq_ignore_ascii_case(&dependency.package_identifier)) {
+                dependencies.push(dependency);
+            }
+        }
+        dependencies
+    }
+
+    /// Metadata for `locale`, falling back field by field to the default locale.
+    ///
+    /// An exact locale match (`de-DE`) wins over a language match (`de-AT`).
+    pub fn localized(&self, locale: &str) -> WingetLocale {
+        let default = self.locales.first().cloned().unwrap_or_default();
+        let language = locale.split('-').next().unwrap_or(locale);
+
+        let tag_of = |l: &WingetLocale| l.package_locale.clone().unwrap_or_default();
+        let chosen = self
+            .locales
+            .iter()
+            .find(|l| tag_of(l).eq_ignore_ascii_case(locale))
+            .or_else(|| {
+                self.locales.iter().find(|l| {
+                    tag_of(l)
+                        .split('-')
+                        .next()
+                        .is_some_and(|lang| lang.eq_ignore_ascii_case(language))
+                })
+            });
+
+        match chosen {
+            Some(l) => l.clone().or(&default),
+            None => default,
+        }
+    }
+
+    /// Whether the package identifier, name, moniker, tags or description match `query`
+    pub fn matches(&self, query: &str, locale: &str) -> bool {
+        let query = query.to_lowercase();
+        let text = self.localized(locale);
+        let contains = |s: &Option<String>| s.as_ref().is_some_and(|s| s.to_lowercase().contains(&query));
+
+        self.identifier.to_lowercase().contains(&query)
+            || contains(&text.package_name)
+            || contains(&text.moniker)
+            || contains(&text.short_description)
+            || text.tags.as_ref().is_some_and(|tags| tags.iter().any(|t| t.to_lowercase() == query))
+            || self.commands.iter().any(|c| c.to_lowercase() == query)
+    }
+}
+
+/// Parse every manifest file in a Winget version directory and merge them
+pub fn load_manifest_dir(dir: &Path) -> Result<WingetPackage, GetError> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|s| s.to_str()) == Some("yaml") {
+            let text = fs::read_to_string(&path)?;
+            let file: WingetManifestFile = serde_yaml::from_str(&text)?;
+            files.push(file);
+        }
+    }
+    merge_manifests(files)
+        .ok_or_else(|| GetError::ParseError(format!("Incomplete Winget manifest in '{}'", dir.display())))
+}
+
+/// Parse a single-file (`singleton` or `merged`) manifest
+pub fn parse_manifest_str(text: &str) -> Result<WingetPackage, GetError> {
+    let file: WingetManifestFile = serde_yaml::from_str(text)?;
+    merge_manifests(vec![file])
+        .ok_or_else(|| GetError::ParseError("Incomplete Winget manifest".to_string()))
+}
+
+fn merge_manifests(files: Vec<WingetManifestFile>) -> Option<WingetPackage> {
+    let mut identifier = None;
+    let mut version = None;
+    let mut default_locale = None;
+    let mut installers = Vec::new();
+    let mut commands = Vec::new();
+    let mut dependencies = WingetDependencies::default();
+    let mut default_text = None;
+    let mut other_locales = Vec::new();
+
+    for file in files {
+        identifier = identifier.or_else(|| file.package_identifier.clone());
+        version = version.or_else(|| file.package_version.clone());
+
+        match file.manifest_type.as_deref().unwrap_or("singleton") {
+            "version" => default_locale = file.default_locale.clone(),
+            "installer" | "singleton" | "merged" => {
+                // Root-level installer fields act as defaults for every installer entry
+                for mut installer in file.installers.clone().unwrap_or_default() {
+                    if installer.installer_type.is_none() {
+                        installer.installer_type = file.installer_type.clone();
+                    }
+                    if installer.installer_switches.is_none() {
+                        installer.installer_switches = file.installer_switches.clone();
+                    }
+                    installers.push(installer);
+                }
+                commands.extend(file.commands.clone().unwrap_or_default());
+                if let Some(root) = file.dependencies.clone() {
+                    dependencies = root;
+                }
+
+                // Single-file manifests carry the default locale text as well
+                if file.manifest_type.as_deref() != Some("installer") {
+                    default_text = Some(file.locale.clone());
+                }
+            }
+            "defaultLocale" => default_text = Some(file.locale.clone()),
+            "locale" => other_locales.push(file.locale.clone()),
+            _ => {}
+        }
+    }
+
+    let mut default_text = default_text?;
+    let default_locale = default_locale
+        .or_else(|| default_text.package_locale.clone())
+        .unwrap_or_else(|| "en-US".to_string());
+    default_text.package_locale.get_or_insert_with(|| default_locale.clone());
+
+    let mut locales = vec![default_text];
+    locales.extend(other_locales);
+
+    Some(WingetPackage {
+        identifier: identifier?,
+        version: version?,
+        default_locale,
+        installers,
+        commands,
+        dependencies,
+        locales,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VERSION: &str = "PackageIdentifier: Tool.Tool\nPackageVersion: 2.1.0\nDefaultLocale: en-US\nManifestType: version\n";
+    const INSTALLER: &str = "\
+PackageIdentifier: Tool.Tool
+PackageVersion: 2.1.0
+InstallerType: msi
+InstallerSwitches:
+  Silent: /qn
+Commands: [tool]
+Installers:
+  - Architecture: x64
+    InstallerUrl: https://example.com/tool-x64.msi
+    InstallerSha256: AA
+  - Architecture: arm64
+    InstallerType: exe
+    InstallerUrl: https://example.com/tool-arm64.exe
+    InstallerSha256: BB
+ManifestType: installer
+";
+    const DEFAULT_LOCALE: &str = "\
+PackageIdentifier: Tool.Tool
+PackageVersion: 2.1.0
+PackageLocale: en-US
+Publisher: Tool Makers
+PackageName: Tool
+PackageUrl: https://example.com/tool
+ShortDescription: Does tool things
+Moniker: tl
+Tags: [cli, utility]
+ReleaseNotes: Faster
+ManifestType: defaultLocale
+";
+    const GERMAN: &str = "\
+PackageIdentifier: Tool.Tool
+PackageVersion: 2.1.0
+PackageLocale: de-DE
+ShortDescription: Macht Werkzeugdinge
+ManifestType: locale
+";
+
+    fn package() -> WingetPackage {
+        let dir = tempfile::tempdir().unwrap();
+        for (name, text) in [
+            ("Tool.Tool.yaml", VERSION),
+            ("Tool.Tool.installer.yaml", INSTALLER),
+            ("Tool.Tool.locale.en-US.yaml", DEFAULT_LOCALE),
+            ("Tool.Tool.locale.de-DE.yaml", GERMAN),
+        ] {
+            fs::write(dir.path().join(name), text).unwrap();
+        }
+        load_manifest_dir(dir.path()).unwrap()
+    }
+
+    #[test]
+    fn merges_a_multi_file_manifest() {
+        let package = package();
+        assert_eq!((package.identifier.as_str(), package.version.as_str()), ("Tool.Tool", "2.1.0"));
+        assert_eq!(package.default_locale, "en-US");
+        assert_eq!(package.commands, ["tool"]);
+        // Root installer fields are defaults for each installer
+        let types: Vec<_> = package.installers.iter().map(|i| i.installer_type.as_deref()).collect();
+        assert_eq!(types, [Some("msi"), Some("exe")]);
+        assert_eq!(package.installers[1].installer_switches.as_ref().and_then(|s| s.silent.as_deref()), Some("/qn"));
+        assert_eq!(package.locales[0].package_locale.as_deref(), Some("en-US"));
+        assert_eq!(package.locales.len(), 2);
+    }
+
+    #[test]
+    fn picks_text_for_the_locale() {
+        let package = package();
+        let german = package.localized("de-AT");
+        assert_eq!(german.short_description.as_deref(), Some("Macht Werkzeugdinge"));
+        // Fields the locale leaves out come from the default locale
+        assert_eq!(german.publisher.as_deref(), Some("Tool Makers"));
+        assert_eq!(german.release_notes.as_deref(), Some("Faster"));
+        assert_eq!(package.localized("fr-FR").short_description.as_deref(), Some("Does tool things"));
+
+        assert!(package.matches("tl", "en-US"));
+        assert!(package.matches("utility", "en-US"));
+        assert!(package.matches("werkzeug", "de-DE"));
+        assert!(!package.matches("werkzeug", "en-US"));
+    }
+
+    #[test]
+    fn reads_singleton_manifests() {
+        let singleton = format!("{}{}", INSTALLER.replace("ManifestType: installer\n", ""), "PackageName: Tool\nManifestType: singleton\n");
+        let package = parse_manifest_str(&singleton).unwrap();
+        assert_eq!(package.installers.len(), 2);
+        assert_eq!(package.localized("en-US").package_name.as_deref(), Some("Tool"));
+        // An installer manifest alone has no text to show
+        assert!(parse_manifest_str(INSTALLER).is_err());
+    }
+}
@@ -0,0 +1,502 @@
+//! Official Winget source index
+//!
+//! Instead of cloning microsoft/winget-pkgs, the Winget client downloads
+//! `source.msix` from the Winget CDN. The package bundles `Public/index.db`, a
+//! SQLite database listing every package version together with the path of
+//! its merged manifest on the CDN. Manifests are only fetched when needed.
+//!
+//! `base_url` may also be a local directory laid out like the CDN
+//! (`source.msix` plus `manifests/...`), which is how a fixture is served.
+
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use indicatif::MultiProgress;
+use reqwest::blocking::Client;
+use reqwest::header::USER_AGENT;
+use rusqlite::{params, Connection, OpenFlags};
+
+use crate::error::GetError;
+use crate::logging::Logger;
+use crate::utils::download_file;
+use crate::version::{select_version, Version};
+use crate::winget::{self, WingetPackage};
+
+/// Location of the index database inside `source.msix`
+const INDEX_DB_ENTRY: &str = "Public/index.db";
+
+/// How long a downloaded index is used before it is refreshed
+const INDEX_MAX_AGE: Duration = Duration::from_secs(24 * 3600);
+
+/// A package version listed in the source index
+#[derive(Debug, Clone)]
+pub struct WingetSourceEntry {
+    pub identifier: String,
+    pub name: String,
+    pub moniker: Option<String>,
+    pub version: String,
+    /// Manifest path relative to the source base URL
+    pub manifest_path: String,
+}
+
+/// The Winget source at `base_url`, cached under `cache_dir`
+pub struct WingetSource {
+    base_url: String,
+    cache_dir: PathBuf,
+}
+
+impl WingetSource {
+    /// Create a source for `base_url` (e.g. `https://cdn.winget.microsoft.com/cache`)
+    pub fn new(base_url: &str, cache_dir: &Path) -> Self {
+        WingetSource {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            cache_dir: cache_dir.to_path_buf(),
+        }
+    }
+
+    /// Path of the extracted index database
+    pub fn index_path(&self) -> PathBuf {
+        self.cache_dir.join("index.db")
+    }
+
+    fn is_local(&self) -> bool {
+        !(self.base_url.starts_with("http://") || self.base_url.starts_with("https://"))
+    }
+
+    /// Download `source.msix` and extract its index if the cached copy is
+    /// missing or older than a day.
+    pub fn update(&self, logger: &Logger, m: &MultiProgress) -> Result<(), GetError> {
+        let index_path = self.index_path();
+        let is_fresh = fs::metadata(&index_path)
+            .and_then(|meta| meta.modified())
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+            .is_some_and(|age| age < INDEX_MAX_AGE);
+
+        if is_fresh {
+            logger.log("Winget source index is up-to-date.");
+            return Ok(());
+        }
+
+        fs::create_dir_all(&self.cache_dir)?;
+        let msix_path = if self.is_local() {
+            PathBuf::from(&self.base_url).join("source.msix")
+        } else {
+            logger.log("Downloading Winget source index...");
+            download_file(&format!("{}/source.msix", self.base_url), &self.cache_dir, logger, m)?
+        };
+
+        extract_index(&msix_path, &index_path)?;
+        logger.log("Winget source index updated.");
+        Ok(())
+    }
+
+    fn open(&self) -> Result<Connection, GetError> {
+        Ok(Connection::open_with_flags(
+            self.index_path(),
+            OpenFlags::SQLITE_OPEN_READ_ONLY,
+        )?)
+    }
+
+    /// Find package versions whose identifier, name or moniker contains
+    /// `query`, or whose tags or commands equal it.
+    pub fn search(&self, query: &str) -> Result<Vec<WingetSourceEntry>, GetError> {
+        let pattern = format!(
+            "%{}%",
+            query.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+        );
+        let sql = format!(
+            "{} WHERE ids.id LIKE ?1 ESCAPE '\\' OR names.name LIKE ?1 ESCAPE '\\' \
+             OR monikers.moniker LIKE ?1 ESCAPE '\\' \
+             OR manifest.rowid IN (SELECT tags_map.manifest FROM tags_map \
+                 JOIN tags ON tags.rowid = tags_map.tag WHERE tags.tag = ?2 COLLATE NOCASE) \
+             OR manifest.rowid IN (SELECT commands_map.manifest FROM commands_map \
+                 JOIN commands ON commands.rowid = commands_map.command WHERE commands.command = ?2 COLLATE NOCASE)",
+            SELECT_ENTRIES
+        );
+        self.query_entries(&sql, params![pattern, query])
+    }
+
+    /// Package versions that declare `command`
+    pub fn provides(&self, command: &str) -> Result<Vec<WingetSourceEntry>, GetError> {
+        let sql = format!(
+            "{} WHERE manifest.rowid IN (SELECT commands_map.manifest FROM commands_map \
+                 JOIN commands ON commands.rowid = commands_map.command WHERE commands.command = ?1 COLLATE NOCASE)",
+            SELECT_ENTRIES
+        );
+        self.query_entries(&sql, params![command])
+    }
+
+    /// Every version of `identifier` (case-insensitive), newest first
+    pub fn versions(&self, identifier: &str) -> Result<Vec<WingetSourceEntry>, GetError> {
+        let sql = format!("{} WHERE ids.id = ?1 COLLATE NOCASE", SELECT_ENTRIES);
+        let mut entries = self.query_entries(&sql, params![identifier])?;
+        entries.sort_by(|a, b| Version::parse(&b.version).cmp(&Version::parse(&a.version)));
+        Ok(entries)
+    }
+
+    /// Resolve `identifier` at `version`, or at its latest version
+    pub fn resolve(&self, identifier: &str, version: Option<&str>) -> Result<WingetSourceEntry, GetError> {
+        let versions = self.versions(identifier)?;
+        if versions.is_empty() {
+            return Err(GetError::PackageNotFound(format!("'{}' not found in Winget", identifier)));
+        }
+
+        select_version(&versions, |e| e.version.as_str(), version)
+            .cloned()
+            .ok_or_else(|| {
+                let available: Vec<&str> = versions.iter().map(|e| e.version.as_str()).collect();
+                GetError::PackageNotFound(format!(
+                    "Version '{}' of '{}' not found. Available versions: {}",
+                    version.unwrap_or_default(),
+                    identifier,
+                    available.join(", ")
+                ))
+            })
+    }
+
+    /// Fetch and parse the merged manifest of `entry`
+    pub fn fetch_manifest(&self, entry: &WingetSourceEntry) -> Result<WingetPackage, GetError> {
+        let text = if self.is_local() {
+            fs::read_to_string(PathBuf::from(&self.base_url).join(&entry.manifest_path))?
+        } else {
+            let client = Client::builder().timeout(Duration::from_secs(30)).build()?;
+            let response = client
+                .get(format!("{}/{}", self.base_url, entry.manifest_path))
+                .header(USER_AGENT, "get-package-manager/1.0")
+                .send()?;
+            if !response.status().is_success() {
+                return Err(GetError::NetworkError(format!(
+                    "Failed to fetch manifest for '{}': HTTP {}",
+                    entry.identifier,
+                    response.status()
+                )));
+            }
+            response.text()?
+        };
+
+        winget::parse_manifest_str(&text)
+    }
+
+    fn query_entries(
+        &self,
+        sql: &str,
+        params: impl rusqlite::Params,
+    ) -> Result<Vec<WingetSourceEntry>, GetError> {
+        let conn = self.open()?;
+        let mut stmt = conn.prepare(sql)?;
+        let rows = stmt
+            .query_map(params, |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, i64>(4)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        rows.into_iter()
+            .map(|(identifier, name, moniker, version, pathpart)| {
+                Ok(WingetSourceEntry {
+                    identifier,
+                    name,
+                    moniker,
+                    version,
+                    manifest_path: manifest_path(&conn, pathpart)?,
+                })
+            })
+            .collect()
+    }
+}
+
+const SELECT_ENTRIES: &str = "SELECT ids.id, names.name, monikers.moniker, versions.version, manifest.pathpart \
+     FROM manifest \
+     JOIN ids ON ids.rowid = manifest.id \
+     JOIN names ON names.rowid = manifest.name \
+     JOIN versions ON versions.rowid = manifest.version \
+     LEFT JOIN monikers ON monikers.rowid = manifest.moniker";
+
+/// Rebuild a manifest path from the `pathparts` tree, where each part points at its parent
+fn manifest_path(conn: &Connection, leaf: i64) -> Result<String, GetError> {
+    let mut stmt = conn.prepare(
+        "WITH RECURSIVE path(id, parent, pathpart, depth) AS ( \
+             SELECT rowid, parent, pathpart, 0 FROM pathparts WHERE rowid = ?1 \
+             UNION ALL \
+             SELECT pathparts.rowid, pathparts.parent, pathparts.pathpart, path.depth + 1 \
+             FROM pathparts JOIN path ON pathparts.rowid = path.parent \
+         ) SELECT pathpart FROM path ORDER BY depth DESC",
+    )?;
+    let parts = stmt
+        .query_map(params![leaf], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(parts.join("/"))
+}
+
+/// Extract the index database from a `source.msix` package
+fn extract_index(msix_path: &Path, index_path: &Path) -> Result<(), GetError> {
+    let file = File::open(msix_path)?;
+    let mut archive = zip::ZipArchive::new(file)
+        .map_err(|e| GetError::ParseError(format!("Invalid source package: {}", e)))?;
+    let mut entry = archive
+        .by_name(INDEX_DB_ENTRY)
+        .map_err(|_| GetError::ParseError(format!("'{}' missing from source package", INDEX_DB_ENTRY)))?;
+
+    // Write next to the target first so a failed extraction never leaves a truncated index
+    let tmp_path = index_path.with_extension("db.tmp");
+    let mut out = File::create(&tmp_path)?;
+    io::copy(&mut entry, &mut out)?;
+    drop(out);
+    fs::rename(&tmp_path, index_path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logging::LogLevel;
+    use std::io::Write;
+    use std::thread;
+
+    const MANIFEST: &str = "PackageIdentifier: BurntSushi.ripgrep.MSVC\n\
+        PackageVersion: 14.1.0\n\
+        PackageLocale: en-US\n\
+        PackageName: RipGrep MSVC\n\
+        ShortDescription: Recursively searches directories for a regex pattern\n\
+        Commands:\n- rg\n\
+        Installers:\n- Architecture: x64\n  InstallerType: zip\n  InstallerUrl: https://example.com/rg.zip\n  InstallerSha256: 00\n\
+        ManifestType: merged\n";
+
+    /// Identifier, name, moniker and version, then tags and commands
+    type Fixture = (&'static str, &'static str, Option<&'static str>, &'static str, &'static [&'static str], &'static [&'static str]);
+
+    const PACKAGES: &[Fixture] = &[
+        ("BurntSushi.ripgrep.MSVC", "RipGrep MSVC", Some("ripgrep"), "13.0.0", &["search"], &["rg"]),
+        ("BurntSushi.ripgrep.MSVC", "RipGrep MSVC", Some("ripgrep"), "14.1.0", &["search"], &["rg"]),
+        ("sharkdp.fd", "fd", None, "10.1.0", &["find", "search"], &["fd"]),
+        ("Git.Git", "Git", Some("git"), "2.45.1", &[], &["git", "gitk"]),
+    ];
+
+    /// Build an index database in the layout of the official source
+    fn build_index(path: &Path) {
+        let conn = Connection::open(path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE ids(id TEXT);
+             CREATE TABLE names(name TEXT);
+             CREATE TABLE monikers(moniker TEXT);
+             CREATE TABLE versions(version TEXT);
+             CREATE TABLE pathparts(parent INT64, pathpart TEXT);
+             CREATE TABLE manifest(id INT64, name INT64, moniker INT64, version INT64, pathpart INT64);
+             CREATE TABLE tags(tag TEXT);
+             CREATE TABLE tags_map(manifest INT64, tag INT64);
+             CREATE TABLE commands(command TEXT);
+             CREATE TABLE commands_map(manifest INT64, command INT64);",
+        )
+        .unwrap();
+
+        let intern = |table: &str, column: &str, value: &str| -> i64 {
+            let existing = conn
+                .query_row(&format!("SELECT rowid FROM {} WHERE {} = ?1", table, column), params![value], |row| row.get(0))
+                .ok();
+            existing.unwrap_or_else(|| {
+                conn.execute(&format!("INSERT INTO {}({}) VALUES (?1)", table, column), params![value]).unwrap();
+                conn.last_insert_rowid()
+            })
+        };
+        let path_part = |parent: Option<i64>, part: &str| -> i64 {
+            conn.execute("INSERT INTO pathparts(parent, pathpart) VALUES (?1, ?2)", params![parent, part]).unwrap();
+            conn.last_insert_rowid()
+        };
+
+        for (identifier, name, moniker, version, tags, commands) in PACKAGES {
+            let mut parent = path_part(None, "manifests");
+            for part in identifier.split('.') {
+                parent = path_part(Some(parent), part);
+            }
+            parent = path_part(Some(parent), version);
+            let leaf = path_part(Some(parent), "manifest.yaml");
+
+            let id = intern("ids", "id", identifier);
+            let name = intern("names", "name", name);
+            let moniker = moniker.map(|m| intern("monikers", "moniker", m));
+            let version = intern("versions", "version", version);
+            conn.execute(
+                "INSERT INTO manifest(id, name, moniker, version, pathpart) VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![id, name, moniker, version, leaf],
+            )
+            .unwrap();
+            let manifest = conn.last_insert_rowid();
+            for tag in *tags {
+                let tag = intern("tags", "tag", tag);
+                conn.execute("INSERT INTO tags_map VALUES (?1, ?2)", params![manifest, tag]).unwrap();
+            }
+            for command in *commands {
+                let command = intern("commands", "command", command);
+                conn.execute("INSERT INTO commands_map VALUES (?1, ?2)", params![manifest, command]).unwrap();
+            }
+        }
+    }
+
+    /// Lay out a directory like the CDN: `source.msix` plus one manifest
+    fn build_source(root: &Path) {
+        let db_path = root.join("index.db");
+        build_index(&db_path);
+
+        let mut msix = zip::ZipWriter::new(File::create(root.join("source.msix")).unwrap());
+        msix.start_file(INDEX_DB_ENTRY, zip::write::SimpleFileOptions::default()).unwrap();
+        msix.write_all(&fs::read(&db_path).unwrap()).unwrap();
+        msix.finish().unwrap();
+        fs::remove_file(&db_path).unwrap();
+
+        let manifest_dir = root.join("manifests/BurntSushi/ripgrep/MSVC/14.1.0");
+        fs::create_dir_all(&manifest_dir).unwrap();
+        fs::write(manifest_dir.join("manifest.yaml"), MANIFEST).unwrap();
+    }
+
+    /// Serve `root` over HTTP until the test process exits
+    fn serve(root: PathBuf) -> String {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", server.server_addr().to_ip().unwrap());
+        thread::spawn(move || {
+            for request in server.incoming_requests() {
+                let path = root.join(request.url().trim_start_matches('/'));
+                let response = match fs::read(path) {
+                    Ok(body) => tiny_http::Response::from_data(body),
+                    Err(_) => tiny_http::Response::from_data(Vec::new()).with_status_code(404),
+                };
+                let _ = request.respond(response);
+            }
+        });
+        url
+    }
+
+    fn updated_source(base_url: &str, cache_dir: &Path) -> WingetSource {
+        let source = WingetSource::new(base_url, cache_dir);
+        source.update(&Logger::new(LogLevel::Minimal), &MultiProgress::new()).unwrap();
+        source
+    }
+
+    fn ids(entries: &[WingetSourceEntry]) -> Vec<(&str, &str)> {
+        let mut ids: Vec<_> = entries.iter().map(|e| (e.identifier.as_str(), e.version.as_str())).collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn searches_identifiers_names_monikers_tags_and_commands() {
+        let root = tempfile::tempdir().unwrap();
+        build_source(root.path());
+        let cache = tempfile::tempdir().unwrap();
+        let source = updated_source(root.path().to_str().unwrap(), cache.path());
+
+        assert_eq!(
+            ids(&source.search("ripgrep").unwrap()),
+            [("BurntSushi.ripgrep.MSVC", "13.0.0"), ("BurntSushi.ripgrep.MSVC", "14.1.0")]
+        );
+        // Tags and commands only match exactly
+        assert_eq!(
+            ids(&source.search("find").unwrap()),
+            [("sharkdp.fd", "10.1.0")]
+        );
+        assert_eq!(ids(&source.search("GITK").unwrap()), [("Git.Git", "2.45.1")]);
+        assert_eq!(source.search("search").unwrap().len(), 3);
+        // LIKE wildcards in the query are literal
+        assert!(source.search("%").unwrap().is_empty());
+        assert!(source.search("_").unwrap().is_empty());
+
+        let entry = &source.search("fd").unwrap()[0];
+        assert_eq!(entry.manifest_path, "manifests/sharkdp/fd/10.1.0/manifest.yaml");
+        assert_eq!(entry.moniker, None);
+    }
+
+    #[test]
+    fn finds_packages_providing_a_command() {
+        let root = tempfile::tempdir().unwrap();
+        build_source(root.path());
+        let cache = tempfile::tempdir().unwrap();
+        let source = updated_source(root.path().to_str().unwrap(), cache.path());
+
+        assert_eq!(ids(&source.provides("gitk").unwrap()), [("Git.Git", "2.45.1")]);
+        assert_eq!(source.provides("RG").unwrap().len(), 2);
+        assert!(source.provides("rip").unwrap().is_empty());
+    }
+
+    #[test]
+    fn resolves_versions() {
+        let root = tempfile::tempdir().unwrap();
+        build_source(root.path());
+        let cache = tempfile::tempdir().unwrap();
+        let source = updated_source(root.path().to_str().unwrap(), cache.path());
+
+        let versions: Vec<String> = source
+            .versions("burntsushi.ripgrep.msvc")
+            .unwrap()
+            .into_iter()
+            .map(|e| e.version)
+            .collect();
+        assert_eq!(versions, ["14.1.0", "13.0.0"]);
+        assert_eq!(source.resolve("BurntSushi.ripgrep.MSVC", None).unwrap().version, "14.1.0");
+        assert_eq!(source.resolve("BurntSushi.ripgrep.MSVC", Some("13.0.0")).unwrap().version, "13.0.0");
+        assert!(matches!(source.resolve("BurntSushi.ripgrep.MSVC", Some("12")), Err(GetError::PackageNotFound(_))));
+        assert!(matches!(source.resolve("Missing.Package", None), Err(GetError::PackageNotFound(_))));
+    }
+
+    #[test]
+    fn downloads_the_index_and_manifests_over_http() {
+        let root = tempfile::tempdir().unwrap();
+        build_source(root.path());
+        let url = serve(root.path().to_path_buf());
+        let cache = tempfile::tempdir().unwrap();
+        let source = updated_source(&url, cache.path());
+
+        assert!(source.index_path().exists());
+        let entry = source.resolve("BurntSushi.ripgrep.MSVC", None).unwrap();
+        let manifest = source.fetch_manifest(&entry).unwrap();
+        assert_eq!(manifest.identifier, "BurntSushi.ripgrep.MSVC");
+        assert_eq!(manifest.version, "14.1.0");
+        assert_eq!(manifest.commands, ["rg"]);
+
+        let missing = WingetSourceEntry { manifest_path: "manifests/missing.yaml".to_string(), ..entry };
+        assert!(matches!(source.fetch_manifest(&missing), Err(GetError::NetworkError(_))));
+    }
+
+    #[test]
+    fn refreshes_only_a_stale_index() {
+        let root = tempfile::tempdir().unwrap();
+        build_source(root.path());
+        let cache = tempfile::tempdir().unwrap();
+        let source = updated_source(root.path().to_str().unwrap(), cache.path());
+        let logger = Logger::new(LogLevel::Minimal);
+        let m = MultiProgress::new();
+
+        // A fresh index is kept even when the source goes away
+        fs::remove_file(root.path().join("source.msix")).unwrap();
+        source.update(&logger, &m).unwrap();
+        assert_eq!(source.search("fd").unwrap().len(), 1);
+
+        // A stale one is replaced, so a missing source is an error
+        let stale = SystemTime::now() - INDEX_MAX_AGE - Duration::from_secs(60);
+        File::options().write(true).open(source.index_path()).unwrap().set_modified(stale).unwrap();
+        assert!(source.update(&logger, &m).is_err());
+
+        build_source(root.path());
+        source.update(&logger, &m).unwrap();
+        assert_eq!(source.search("fd").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn rejects_a_source_package_without_an_index() {
+        let root = tempfile::tempdir().unwrap();
+        let mut msix = zip::ZipWriter::new(File::create(root.path().join("source.msix")).unwrap());
+        msix.start_file("AppxManifest.xml", zip::write::SimpleFileOptions::default()).unwrap();
+        msix.finish().unwrap();
+
+        let source = WingetSource::new(root.path().to_str().unwrap(), &root.path().join("cache"));
+        let result = source.update(&Logger::new(LogLevel::Minimal), &MultiProgress::new());
+        assert!(matches!(result, Err(GetError::ParseError(_))));
+        assert!(!source.index_path().exists());
+    }
+}
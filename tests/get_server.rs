@@ -0,0 +1,232 @@
+//! End-to-end tests of `get-server` against the repository client
+
+use std::fs::{self, File};
+use std::io::Write;
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use indicatif::{MultiProgress, ProgressDrawTarget};
+use sha2::{Digest, Sha256};
+
+use get::bundle::create_bundle;
+use get::config::{Config, PackageFormat, Repository, RepositoryAuth};
+use get::logging::{LogLevel, Logger};
+use get::package_manager::{
+    ArchiveFormat, InstallInstructions, PackageInfo, PackageManager, UniversalPackageManager,
+};
+
+/// A running `get-server`, stopped on drop
+struct Server {
+    child: Child,
+    url: String,
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn free_port() -> u16 {
+    TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port()
+}
+
+fn start_server(root: &Path, port: u16, auth: &[&str]) -> Server {
+    let child = Command::new(env!("CARGO_BIN_EXE_get-server"))
+        .arg(root)
+        .args(["--bind", &format!("127.0.0.1:{}", port)])
+        .args(auth)
+        .env_remove("GET_SERVER_BASIC")
+        .env_remove("GET_SERVER_TOKEN")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    let server = Server { child, url: format!("http://127.0.0.1:{}", port) };
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while TcpStream::connect(("127.0.0.1", port)).is_err() {
+        assert!(Instant::now() < deadline, "get-server did not start");
+        thread::sleep(Duration::from_millis(50));
+    }
+    server
+}
+
+/// Lay out a repository with one zipped package whose artifact is served by
+/// the server on `port`
+fn build_repository(root: &Path, port: u16, name: &str, extract_path: &Path) -> PackageInfo {
+    add_package(root, port, name, "1.0", &[], extract_path)
+}
+
+fn add_package(root: &Path, port: u16, name: &str, version: &str, dependencies: &[&str], extract_path: &Path) -> PackageInfo {
+    let artifact_name = format!("{}-{}.zip", name, version);
+    let artifact_path = root.join("artifacts").join(&artifact_name);
+    fs::create_dir_all(artifact_path.parent().unwrap()).unwrap();
+    let mut zip = zip::ZipWriter::new(File::create(&artifact_path).unwrap());
+    zip.start_file("bin/hello.txt", zip::write::SimpleFileOptions::default()).unwrap();
+    zip.write_all(b"hello").unwrap();
+    zip.finish().unwrap();
+
+    let package = PackageInfo {
+        name: name.to_string(),
+        version: version.to_string(),
+        description: "A greeting".to_string(),
+        author: None,
+        license: None,
+        source_url: format!("http://127.0.0.1:{}/artifacts/{}", port, artifact_name),
+        checksum: hex::encode(Sha256::digest(fs::read(&artifact_path).unwrap())),
+        dependencies: dependencies.iter().map(|d| d.to_string()).collect(),
+        install_instructions: InstallInstructions::Archive {
+            format: ArchiveFormat::Zip,
+            extract_path: extract_path.to_string_lossy().into_owned(),
+            strip_components: 0,
+            post_extract_commands: Vec::new(),
+        },
+        uninstall_instructions: None,
+    };
+    let manifests = root.join("manifests").join(name);
+    fs::create_dir_all(&manifests).unwrap();
+    fs::write(manifests.join(format!("{}.json", version)), serde_json::to_vec(&package).unwrap()).unwrap();
+    package
+}
+
+/// A configuration using only the server, keeping its state under `home`
+fn config_for(home: &Path, url: &str, authentication: Option<RepositoryAuth>) -> Config {
+    Config {
+        home: Some(home.to_string_lossy().into_owned()),
+        default_download_dir: Some(home.join("downloads").to_string_lossy().into_owned()),
+        repositories: vec![Repository {
+            name: "local".to_string(),
+            url: url.to_string(),
+            package_format: PackageFormat::Json,
+            authentication,
+            priority: None,
+        }],
+        ..Default::default()
+    }
+}
+
+fn quiet() -> (Logger, MultiProgress) {
+    (Logger::new(LogLevel::Minimal), MultiProgress::with_draw_target(ProgressDrawTarget::hidden()))
+}
+
+fn installed_file(extract_path: &Path) -> PathBuf {
+    extract_path.join("bin").join("hello.txt")
+}
+
+#[test]
+fn indexes_searches_fetches_and_installs() {
+    let home = tempfile::tempdir().unwrap();
+    let root = tempfile::tempdir().unwrap();
+    let port = free_port();
+    let extract_path = home.path().join("hello");
+    build_repository(root.path(), port, "hello", &extract_path);
+    let server = start_server(root.path(), port, &[]);
+
+    let config = config_for(home.path(), &server.url, None);
+    let repo = &config.repositories[0];
+    let (logger, m) = quiet();
+    let manager = UniversalPackageManager;
+
+    let index = manager.fetch_index(repo, &config).unwrap().expect("server has an index");
+    assert_eq!(index.packages.len(), 1);
+    assert_eq!(index.packages[0].name, "hello");
+
+    let results = manager.search_repositories("greet", &config, &logger).unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].repository, "local");
+    assert!(manager.search_repositories("missing", &config, &logger).unwrap().is_empty());
+
+    let package = manager.fetch_package_info("hello@1.0", &config, &logger, &m).unwrap();
+    assert_eq!(package.version, "1.0");
+    assert!(manager.fetch_package_info("missing", &config, &logger, &m).is_err());
+
+    let report = manager.install("hello", &config, &logger, &m).unwrap();
+    assert_eq!(report.version.as_deref(), Some("1.0"));
+    assert_eq!(fs::read_to_string(installed_file(&extract_path)).unwrap(), "hello");
+}
+
+#[test]
+fn sends_credentials_for_index_and_artifacts() {
+    let home = tempfile::tempdir().unwrap();
+    let root = tempfile::tempdir().unwrap();
+    let port = free_port();
+    let extract_path = home.path().join("secret");
+    build_repository(root.path(), port, "secret", &extract_path);
+    let server = start_server(root.path(), port, &["--token", "s3cret"]);
+    let (logger, m) = quiet();
+    let manager = UniversalPackageManager;
+
+    // Without credentials nothing is visible, and a search skips the repository
+    let anonymous = config_for(home.path(), &server.url, None);
+    assert!(manager.fetch_index(&anonymous.repositories[0], &anonymous).is_err());
+    assert!(manager.search_repositories("secret", &anonymous, &logger).unwrap().is_empty());
+    assert!(manager.install("secret", &anonymous, &logger, &m).is_err());
+
+    let wrong = config_for(home.path(), &server.url, Some(RepositoryAuth::Token { token: "wrong".to_string() }));
+    assert!(manager.fetch_index(&wrong.repositories[0], &wrong).is_err());
+
+    // The artifact lives on the same server, so it needs the token as well
+    let config = config_for(home.path(), &server.url, Some(RepositoryAuth::Token { token: "s3cret".to_string() }));
+    assert_eq!(manager.search_repositories("secret", &config, &logger).unwrap().len(), 1);
+    manager.install("secret", &config, &logger, &m).unwrap();
+    assert_eq!(fs::read_to_string(installed_file(&extract_path)).unwrap(), "hello");
+}
+
+#[test]
+fn accepts_basic_auth() {
+    let home = tempfile::tempdir().unwrap();
+    let root = tempfile::tempdir().unwrap();
+    let port = free_port();
+    let extract_path = home.path().join("basic");
+    build_repository(root.path(), port, "basic", &extract_path);
+    let server = start_server(root.path(), port, &["--basic", "user:pass"]);
+    let (logger, m) = quiet();
+    let manager = UniversalPackageManager;
+
+    let wrong = config_for(
+        home.path(),
+        &server.url,
+        Some(RepositoryAuth::Basic { username: "user".to_string(), password: "nope".to_string() }),
+    );
+    assert!(manager.install("basic", &wrong, &logger, &m).is_err());
+
+    let config = config_for(
+        home.path(),
+        &server.url,
+        Some(RepositoryAuth::Basic { username: "user".to_string(), password: "pass".to_string() }),
+    );
+    manager.install("basic", &config, &logger, &m).unwrap();
+    assert!(installed_file(&extract_path).exists());
+}
+
+#[test]
+fn bundles_one_version_of_each_dependency() {
+    let home = tempfile::tempdir().unwrap();
+    let root = tempfile::tempdir().unwrap();
+    let port = free_port();
+    let extract_path = home.path().join("bundled");
+    add_package(root.path(), port, "app", "1.0", &["lib", "tool"], &extract_path);
+    add_package(root.path(), port, "tool", "1.0", &["lib<2"], &extract_path);
+    add_package(root.path(), port, "lib", "1.0", &[], &extract_path);
+    add_package(root.path(), port, "lib", "2.0", &[], &extract_path);
+    add_package(root.path(), port, "broken", "1.0", &["lib>=2", "tool"], &extract_path);
+    let server = start_server(root.path(), port, &[]);
+    let config = config_for(home.path(), &server.url, None);
+    let (logger, m) = quiet();
+
+    let output = home.path().join("app.getpkg");
+    let manifest = create_bundle(&["app".to_string()], &output, &config, &logger, &m).unwrap();
+    let mut bundled: Vec<String> = manifest.packages.iter().map(|p| format!("{}@{}", p.name, p.version)).collect();
+    bundled.sort();
+    assert_eq!(bundled, ["app@1.0", "lib@1.0", "tool@1.0"]);
+
+    let message = create_bundle(&["broken".to_string()], &home.path().join("broken.getpkg"), &config, &logger, &m)
+        .unwrap_err()
+        .to_string();
+    assert!(message.contains("Conflicting requirements for 'lib'"), "{}", message);
+}